@@ -0,0 +1,117 @@
+//! Derive macros for [clickhouse-srv](https://github.com/sundy-li/clickhouse-srv).
+//!
+//! Enable the `derive` feature of `clickhouse-srv` and use the re-exports from
+//! `clickhouse_srv::types` rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::format_ident;
+use quote::quote;
+use syn::parse_macro_input;
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Field;
+use syn::Fields;
+
+/// Implements `clickhouse_srv::types::IntoBlock` for a struct with named fields.
+///
+/// Every field becomes a column named after the field, in declaration order. The
+/// column type follows from the field type the same way as for `Block::column`,
+/// so `Option<T>` fields become `Nullable` columns.
+#[proc_macro_derive(IntoBlock)]
+pub fn derive_into_block(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_into_block(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_into_block(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = named_fields(input, "IntoBlock")?;
+
+    let idents: Vec<_> = fields.iter().map(|field| field.ident.as_ref().unwrap()).collect();
+    let names: Vec<_> = idents.iter().map(|ident| ident.to_string()).collect();
+    let vars: Vec<_> = idents.iter().map(|ident| format_ident!("__{}", ident)).collect();
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::clickhouse_srv::types::IntoBlock for #name #ty_generics
+        #where_clause
+        {
+            fn into_block(rows: ::std::vec::Vec<Self>) -> ::clickhouse_srv::types::Block {
+                #( let mut #vars = ::std::vec::Vec::with_capacity(rows.len()); )*
+                for row in rows {
+                    #( #vars.push(row.#idents); )*
+                }
+                ::clickhouse_srv::types::Block::new()
+                    #( .column(#names, #vars) )*
+            }
+        }
+    })
+}
+
+/// Implements `clickhouse_srv::types::FromBlock` for a struct with named fields.
+///
+/// Each field is read from the column with the same name through `FromSql`, so a
+/// missing column or a column of another type is reported as an error. `Option<T>`
+/// fields read `Nullable` columns.
+#[proc_macro_derive(FromBlock)]
+pub fn derive_from_block(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_block(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_from_block(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = named_fields(input, "FromBlock")?;
+
+    let idents: Vec<_> = fields.iter().map(|field| field.ident.as_ref().unwrap()).collect();
+    let names: Vec<_> = idents.iter().map(|ident| ident.to_string()).collect();
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::clickhouse_srv::types::FromBlock for #name #ty_generics
+        #where_clause
+        {
+            fn from_block(
+                block: &::clickhouse_srv::types::Block
+            ) -> ::clickhouse_srv::errors::Result<::std::vec::Vec<Self>> {
+                #( block.get_column(#names)?; )*
+
+                let mut rows = ::std::vec::Vec::with_capacity(block.row_count());
+                for row in 0..block.row_count() {
+                    rows.push(Self {
+                        #( #idents: block.get(row, #names)?, )*
+                    });
+                }
+                Ok(rows)
+            }
+        }
+    })
+}
+
+fn named_fields<'a>(
+    input: &'a DeriveInput,
+    derive: &str
+) -> syn::Result<&'a Punctuated<Field, Comma>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(&fields.named),
+            _ => Err(syn::Error::new_spanned(
+                &input.ident,
+                format!("{} can only be derived for structs with named fields", derive)
+            ))
+        },
+        _ => Err(syn::Error::new_spanned(
+            &input.ident,
+            format!("{} can only be derived for structs", derive)
+        ))
+    }
+}
@@ -0,0 +1,78 @@
+use clickhouse_srv::errors::Error;
+use clickhouse_srv::errors::FromSqlError;
+use clickhouse_srv::types::Block;
+use clickhouse_srv::types::FromBlock;
+use clickhouse_srv::types::IntoBlock;
+
+#[derive(Clone, Debug, PartialEq, FromBlock, IntoBlock)]
+struct Event {
+    id: u64,
+    name: String,
+    score: Option<f64>
+}
+
+#[derive(Debug, FromBlock)]
+struct Named {
+    name: u64
+}
+
+#[test]
+fn test_round_trip() {
+    let events = vec![
+        Event {
+            id: 1,
+            name: "start".to_string(),
+            score: Some(0.5)
+        },
+        Event {
+            id: 2,
+            name: "stop".to_string(),
+            score: None
+        },
+    ];
+
+    let block = Event::into_block(events.clone());
+    assert_eq!(Event::from_block(&block).unwrap(), events);
+}
+
+#[test]
+fn test_columns_matched_by_name() {
+    let block = Block::new()
+        .column("score", vec![None, Some(1.5_f64)])
+        .column("extra", vec![0_u8, 0])
+        .column("name", vec!["a", "b"])
+        .column("id", vec![7_u64, 8]);
+
+    let events = Event::from_block(&block).unwrap();
+    assert_eq!(events[1], Event {
+        id: 8,
+        name: "b".to_string(),
+        score: Some(1.5)
+    });
+}
+
+#[test]
+fn test_missing_column() {
+    let block = Block::new().column("id", vec![1_u64]);
+
+    match Event::from_block(&block) {
+        Err(Error::FromSql(FromSqlError::MissingColumn { name })) => assert_eq!(name, "name"),
+        other => panic!("unexpected result {:?}", other)
+    }
+}
+
+#[test]
+fn test_mismatched_column() {
+    let block = Block::new().column("name", vec![3_u64]);
+    assert_eq!(Named::from_block(&block).unwrap()[0].name, 3);
+
+    let block = Block::new().column("name", vec!["a"]);
+
+    match Named::from_block(&block) {
+        Err(Error::FromSql(FromSqlError::InvalidType { src, dst })) => {
+            assert_eq!(src, "String");
+            assert_eq!(dst, "u64");
+        }
+        other => panic!("unexpected result {:?}", other)
+    }
+}
@@ -0,0 +1,49 @@
+use clickhouse_srv::types::Block;
+use clickhouse_srv::types::IntoBlock;
+
+#[derive(IntoBlock)]
+struct Event {
+    id: u64,
+    name: String,
+    score: Option<f64>
+}
+
+#[test]
+fn test_into_block() {
+    let events = vec![
+        Event {
+            id: 1,
+            name: "start".to_string(),
+            score: Some(0.5)
+        },
+        Event {
+            id: 2,
+            name: "stop".to_string(),
+            score: None
+        },
+    ];
+
+    let block = Event::into_block(events);
+    let expected = Block::new()
+        .column("id", vec![1_u64, 2])
+        .column("name", vec!["start", "stop"])
+        .column("score", vec![Some(0.5_f64), None]);
+
+    let header = |block: &Block| -> Vec<(String, String)> {
+        block
+            .columns()
+            .iter()
+            .map(|column| (column.name().to_string(), column.sql_type().to_string().into()))
+            .collect()
+    };
+    assert_eq!(header(&block), header(&expected));
+    assert_eq!(header(&block)[2].1, "Nullable(Float64)");
+    assert_eq!(block, expected);
+}
+
+#[test]
+fn test_into_empty_block() {
+    let block = Event::into_block(Vec::new());
+    assert_eq!(block.column_count(), 3);
+    assert_eq!(block.row_count(), 0);
+}
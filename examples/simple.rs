@@ -151,7 +151,8 @@ impl clickhouse_srv::ClickHouseSession for Session {
         Progress {
             rows: 100,
             bytes: 1000,
-            total_rows: 1000
+            total_rows: 1000,
+            ..Default::default()
         }
     }
 }
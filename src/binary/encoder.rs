@@ -2,7 +2,7 @@ use crate::binary;
 use crate::types::Marshal;
 use crate::types::StatBuffer;
 
-const MAX_VARINT_LEN64: usize = 10;
+pub(crate) const MAX_VARINT_LEN64: usize = 10;
 
 #[derive(Default)]
 pub struct Encoder {
@@ -1,6 +1,7 @@
 pub(crate) use self::encoder::Encoder;
 pub(crate) use self::parser::Parser;
 pub(crate) use self::read_ex::ReadEx;
+pub(crate) use self::read_ex::MAX_STRING_SIZE;
 pub(crate) use self::uvarint::put_uvarint;
 
 mod encoder;
@@ -1,3 +1,4 @@
+use std::convert::TryFrom;
 use std::io::Read;
 
 use chrono_tz::Tz;
@@ -6,10 +7,12 @@ use crate::binary::ReadEx;
 use crate::errors::DriverError;
 use crate::errors::Error;
 use crate::errors::Result;
+use crate::protocols::ClientPacket;
 use crate::protocols::HelloRequest;
 use crate::protocols::Packet;
 use crate::protocols::QueryRequest;
-use crate::protocols::{self};
+use crate::protocols::DBMS_MIN_REVISION_WITH_SCALARS;
+use crate::types::column::SerializationSettings;
 use crate::types::Block;
 
 /// The internal clickhouse client request parser.
@@ -32,17 +35,28 @@ impl<'a, T: Read> Parser<T> {
     pub(crate) fn parse_packet(
         &mut self,
         hello: &Option<HelloRequest>,
-        compress: bool
+        compress: bool,
+        max_query_size: usize,
+        settings: SerializationSettings
     ) -> Result<Packet> {
+        let client_revision = settings.client_revision;
         let packet = self.reader.read_uvarint()?;
-        match packet {
-            protocols::CLIENT_PING => Ok(Packet::Ping),
-            protocols::CLIENT_CANCEL => Ok(Packet::Cancel),
-            protocols::CLIENT_DATA | protocols::CLIENT_SCALAR => {
-                Ok(self.parse_data(packet == protocols::CLIENT_SCALAR, compress)?)
+        match ClientPacket::try_from(packet)? {
+            ClientPacket::Ping => Ok(Packet::Ping),
+            ClientPacket::Cancel => Ok(Packet::Cancel),
+            ClientPacket::Data => {
+                let (table_name, block) = self.parse_data(compress, settings)?;
+                Ok(Packet::Data(table_name, block))
             }
-            protocols::CLIENT_QUERY => Ok(self.parse_query(hello, compress)?),
-            protocols::CLIENT_HELLO => Ok(self.parse_hello()?),
+            // Clients older than scalars never send one, it is an unknown packet.
+            ClientPacket::Scalar if client_revision >= DBMS_MIN_REVISION_WITH_SCALARS => {
+                let (name, block) = self.parse_data(compress, settings)?;
+                Ok(Packet::Scalar(name, block))
+            }
+            ClientPacket::Query => {
+                Ok(self.parse_query(hello, max_query_size, settings)?)
+            }
+            ClientPacket::Hello => Ok(self.parse_hello()?),
 
             _ => Err(Error::Driver(DriverError::UnknownPacket { packet }))
         }
@@ -52,19 +66,35 @@ impl<'a, T: Read> Parser<T> {
         Ok(Packet::Hello(HelloRequest::read_from(&mut self.reader)?))
     }
 
-    fn parse_query(&mut self, hello: &Option<HelloRequest>, _compress: bool) -> Result<Packet> {
+    fn parse_query(
+        &mut self,
+        hello: &Option<HelloRequest>,
+        max_query_size: usize,
+        settings: SerializationSettings
+    ) -> Result<Packet> {
         match hello {
             Some(ref hello) => {
-                let query = QueryRequest::read_from(&mut self.reader, hello)?;
-                Ok(Packet::Query(query))
+                let query = QueryRequest::read_from(
+                    &mut self.reader,
+                    hello,
+                    settings.client_revision,
+                    max_query_size,
+                    settings.max_string_size
+                )?;
+                Ok(Packet::Query(Box::new(query)))
             }
             _ => Err(Error::Driver(DriverError::UnexpectedPacket))
         }
     }
 
-    fn parse_data(&mut self, _scalar: bool, compress: bool) -> Result<Packet> {
-        let _temporary_table = self.reader.read_string()?;
-        let block = Block::load(&mut self.reader, self.tz, compress)?;
-        Ok(Packet::Data(block))
+    // The name of a temporary table or of a scalar, and its block.
+    fn parse_data(
+        &mut self,
+        compress: bool,
+        settings: SerializationSettings
+    ) -> Result<(String, Block)> {
+        let name = self.reader.read_string()?;
+        let block = Block::load_with_settings(&mut self.reader, self.tz, compress, settings)?;
+        Ok((name, block))
     }
 }
@@ -1,6 +1,7 @@
 use std::io;
 use std::mem::MaybeUninit;
 
+use super::encoder::MAX_VARINT_LEN64;
 use crate::errors::DriverError;
 use crate::errors::Error;
 use crate::errors::Result;
@@ -13,13 +14,34 @@ pub(crate) trait ReadEx {
     fn read_scalar<V>(&mut self) -> Result<V>
     where V: Copy + Unmarshal<V> + StatBuffer;
     fn read_string(&mut self) -> Result<String>;
+    fn read_bounded_string(&mut self, max_size: usize) -> Result<String>;
+    fn read_bounded_bytes(&mut self, max_size: usize) -> Result<Vec<u8>>;
     fn skip_string(&mut self) -> Result<()>;
     fn read_uvarint(&mut self) -> Result<u64>;
-    fn read_str_into_buffer(&mut self, pool: &mut StringPool) -> Result<()>;
+    fn read_str_into_buffer(&mut self, pool: &mut StringPool, max_size: usize) -> Result<()>;
 }
 
 const MAX_STACK_BUFFER_LEN: usize = 1024;
 
+/// Longest string accepted from a client unless the server sets its own limit.
+/// The length prefix is checked before anything is allocated for it.
+pub(crate) const MAX_STRING_SIZE: usize = 16 << 20;
+
+fn read_len<R: ReadEx + ?Sized>(reader: &mut R, max_size: usize) -> Result<usize> {
+    let size = reader.read_uvarint()?;
+    if size > max_size as u64 {
+        return Err(Error::Driver(DriverError::TooLargeObject {
+            size,
+            max_size
+        }));
+    }
+    Ok(size as usize)
+}
+
+fn would_block() -> Error {
+    io::Error::new(io::ErrorKind::WouldBlock, "would block").into()
+}
+
 impl<T> ReadEx for T
 where T: io::Read
 {
@@ -31,10 +53,7 @@ where T: io::Read
                 self.read(buf)
             };
             match res_nread {
-                Ok(0) => {
-                    let ret = io::Error::new(io::ErrorKind::WouldBlock, "would block");
-                    return Err(ret.into());
-                }
+                Ok(0) => return Err(would_block()),
                 Ok(nread) => i += nread,
                 Err(e) => return Err(From::from(e))
             }
@@ -50,14 +69,29 @@ where T: io::Read
     }
 
     fn read_string(&mut self) -> Result<String> {
-        let str_len = self.read_uvarint()? as usize;
-        let mut buffer = vec![0_u8; str_len];
-        self.read_bytes(buffer.as_mut())?;
+        self.read_bounded_string(MAX_STRING_SIZE)
+    }
+
+    fn read_bounded_string(&mut self, max_size: usize) -> Result<String> {
+        let buffer = self.read_bounded_bytes(max_size)?;
         Ok(String::from_utf8(buffer)?)
     }
 
+    fn read_bounded_bytes(&mut self, max_size: usize) -> Result<Vec<u8>> {
+        let str_len = read_len(self, max_size)?;
+        // The buffer only grows with the bytes read, a length sent ahead of
+        // data that has not arrived yet allocates nothing.
+        let mut buffer = Vec::new();
+        let mut data = io::Read::take(&mut *self, str_len as u64);
+        io::Read::read_to_end(&mut data, &mut buffer)?;
+        if buffer.len() < str_len {
+            return Err(would_block());
+        }
+        Ok(buffer)
+    }
+
     fn skip_string(&mut self) -> Result<()> {
-        let str_len = self.read_uvarint()? as usize;
+        let str_len = read_len(self, MAX_STRING_SIZE)?;
 
         if str_len <= MAX_STACK_BUFFER_LEN {
             unsafe {
@@ -68,8 +102,11 @@ where T: io::Read
                 )?;
             }
         } else {
-            let mut buffer = vec![0_u8; str_len];
-            self.read_bytes(buffer.as_mut())?;
+            let mut data = io::Read::take(&mut *self, str_len as u64);
+            let skipped = io::copy(&mut data, &mut io::sink())?;
+            if skipped < str_len as u64 {
+                return Err(would_block());
+            }
         }
 
         Ok(())
@@ -78,26 +115,26 @@ where T: io::Read
     fn read_uvarint(&mut self) -> Result<u64> {
         let mut x = 0_u64;
         let mut s = 0_u32;
-        let mut i = 0_usize;
-        loop {
+        for i in 0..MAX_VARINT_LEN64 {
             let b: u8 = self.read_scalar()?;
 
+            // The last byte holds the top bit of a u64 and must end the varint.
+            if i == MAX_VARINT_LEN64 - 1 && b > 1 {
+                return Err(Error::Driver(DriverError::Overflow));
+            }
+
             if b < 0x80 {
-                if i == 9 && b > 1 {
-                    return Err(Error::Driver(DriverError::Overflow));
-                }
                 return Ok(x | (u64::from(b) << s));
             }
 
             x |= u64::from(b & 0x7f) << s;
             s += 7;
-
-            i += 1;
         }
+        Err(Error::Driver(DriverError::Overflow))
     }
 
-    fn read_str_into_buffer(&mut self, pool: &mut StringPool) -> Result<()> {
-        let str_len = self.read_uvarint()? as usize;
+    fn read_str_into_buffer(&mut self, pool: &mut StringPool, max_size: usize) -> Result<()> {
+        let str_len = read_len(self, max_size)?;
         let buffer = pool.allocate(str_len);
         self.read_bytes(buffer)?;
         Ok(())
@@ -117,3 +154,83 @@ fn test_read_uvarint() {
 
     assert_eq!(actual, 1346)
 }
+
+#[test]
+fn test_read_max_uvarint() {
+    use std::io::Cursor;
+
+    use super::Encoder;
+
+    let mut encoder = Encoder::new();
+    encoder.uvarint(u64::MAX);
+    assert_eq!(encoder.get_buffer_ref().len(), MAX_VARINT_LEN64);
+
+    let mut cursor = Cursor::new(encoder.get_buffer_ref());
+    assert_eq!(cursor.read_uvarint().unwrap(), u64::MAX);
+}
+
+#[test]
+fn test_read_malformed_uvarint() {
+    use std::io::Cursor;
+
+    // Continuation bits that never end.
+    let bytes = [0xff_u8; 32];
+    let mut cursor = Cursor::new(&bytes[..]);
+    let err = cursor.read_uvarint().unwrap_err();
+    assert!(matches!(err, Error::Driver(DriverError::Overflow)));
+    assert_eq!(cursor.position(), MAX_VARINT_LEN64 as u64);
+
+    // Ten bytes, but the value needs 65 bits.
+    let mut bytes = [0xff_u8; MAX_VARINT_LEN64];
+    bytes[MAX_VARINT_LEN64 - 1] = 0x02;
+    let mut cursor = Cursor::new(&bytes[..]);
+    let err = cursor.read_uvarint().unwrap_err();
+    assert!(matches!(err, Error::Driver(DriverError::Overflow)));
+}
+
+#[test]
+fn test_read_too_large_string() {
+    use std::io::Cursor;
+
+    use super::Encoder;
+
+    let mut encoder = Encoder::new();
+    encoder.uvarint(MAX_STRING_SIZE as u64 + 1);
+    encoder.write_bytes(b"abc");
+
+    let mut cursor = Cursor::new(encoder.get_buffer_ref());
+    let err = cursor.read_string().unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Driver(DriverError::TooLargeObject { size, max_size })
+            if size == MAX_STRING_SIZE as u64 + 1 && max_size == MAX_STRING_SIZE
+    ));
+
+    let mut cursor = Cursor::new(encoder.get_buffer_ref());
+    assert!(cursor.skip_string().is_err());
+
+    let mut encoder = Encoder::new();
+    encoder.string("SELECT 1");
+    let mut cursor = Cursor::new(encoder.get_buffer_ref());
+    assert!(cursor.read_bounded_string(7).is_err());
+    let mut cursor = Cursor::new(encoder.get_buffer_ref());
+    assert_eq!(cursor.read_bounded_string(8).unwrap(), "SELECT 1");
+}
+
+#[test]
+fn test_read_truncated_string() {
+    use std::io::Cursor;
+
+    use super::Encoder;
+
+    // The length is in bounds, but only 3 of its bytes have arrived.
+    let mut encoder = Encoder::new();
+    encoder.uvarint(MAX_STRING_SIZE as u64);
+    encoder.write_bytes(b"abc");
+
+    let mut cursor = Cursor::new(encoder.get_buffer_ref());
+    assert!(cursor.read_string().unwrap_err().is_would_block());
+
+    let mut cursor = Cursor::new(encoder.get_buffer_ref());
+    assert!(cursor.skip_string().unwrap_err().is_would_block());
+}
@@ -1,13 +1,29 @@
+use std::time::Duration;
+
 use log::debug;
+use uuid::Uuid;
 
 use crate::binary::Encoder;
+use crate::connection::max_block_size;
 use crate::connection::Connection;
+use crate::error_codes;
+use crate::errors::DriverError;
+use crate::errors::Error;
 use crate::errors::Result;
+use crate::errors::ServerError;
+use crate::protocols::constant_time_eq;
+use crate::protocols::interserver_hash;
+use crate::protocols::HelloRequest;
 use crate::protocols::HelloResponse;
 use crate::protocols::Packet;
+use crate::protocols::QueryRequest;
 use crate::protocols::Stage;
-use crate::protocols::SERVER_PONG;
+use crate::protocols::DBMS_MAX_SUPPORTED_PROTOCOL_VERSION;
+use crate::protocols::DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET;
+use crate::protocols::SECONDARY_QUERY;
+use crate::types::Progress;
 use crate::CHContext;
+use crate::HandshakeResult;
 
 pub struct Cmd {
     packet: Packet
@@ -19,75 +35,336 @@ impl Cmd {
     }
 
     pub async fn apply(self, connection: &mut Connection, ctx: &mut CHContext) -> Result<()> {
-        let mut encoder = Encoder::new();
         match self.packet {
             Packet::Ping => {
-                encoder.uvarint(SERVER_PONG);
+                connection.write_pong().await?;
+            }
+            Packet::Cancel => {
+                ctx.state.is_cancelled = true;
+                ctx.state.cancellation_token.cancel();
             }
-            // todo cancel
-            Packet::Cancel => {}
             Packet::Hello(hello) => {
+                if let Some(min_revision) = ctx.min_accepted_revision {
+                    if hello.client_revision < min_revision {
+                        return Err(Error::Server(ServerError {
+                            name: "UNKNOWN_PROTOCOL".to_string(),
+                            code: error_codes::UNKNOWN_PROTOCOL,
+                            message: format!(
+                                "Client revision {} is older than the minimum accepted {}",
+                                hello.client_revision, min_revision
+                            ),
+                            stack_trace: "".to_string()
+                        }));
+                    }
+                }
+
+                let server_revision = connection
+                    .session
+                    .dbms_tcp_protocol_version()
+                    .min(DBMS_MAX_SUPPORTED_PROTOCOL_VERSION);
+                let client_revision = server_revision.min(hello.client_revision);
+
+                let session = connection.session.clone();
+                if hello.is_interserver() {
+                    // Such a client has no password, every query it sends is
+                    // signed with the cluster secret instead.
+                    if hello.salt.is_empty() || session.interserver_secret(&hello.cluster).is_none()
+                    {
+                        return Err(authentication_failed(
+                            "Interserver authentication failed (no salt/cluster secret)"
+                                .to_string()
+                        ));
+                    }
+                    // Older revisions send no hash with the query.
+                    if client_revision < DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET {
+                        return Err(authentication_failed(format!(
+                            "Interserver authentication needs protocol revision {}, the \
+                             connection uses {}",
+                            DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET, client_revision
+                        )));
+                    }
+                } else if !session
+                    .authenticate(&hello.user, &hello.password, ctx.client_addr)
+                    .await?
+                {
+                    return Err(authentication_failed(format!(
+                        "{}: Authentication failed: password is incorrect or there is no user \
+                         with such name",
+                        hello.user
+                    )));
+                }
+
                 let response = HelloResponse {
                     dbms_name: connection.session.dbms_name().to_string(),
                     dbms_version_major: connection.session.dbms_version_major(),
                     dbms_version_minor: connection.session.dbms_version_minor(),
-                    dbms_tcp_protocol_version: connection.session.dbms_tcp_protocol_version(),
+                    dbms_tcp_protocol_version: server_revision,
                     timezone: connection.session.timezone().to_string(),
                     server_display_name: connection.session.server_display_name().to_string(),
                     dbms_version_patch: connection.session.dbms_version_patch()
                 };
 
-                ctx.client_revision = connection
-                    .session
-                    .dbms_tcp_protocol_version()
-                    .min(hello.client_revision);
+                ctx.client_revision = client_revision;
+                connection.client_revision = ctx.client_revision;
                 ctx.hello = Some(hello.clone());
 
+                // The addendum is only sent once the client has read our `Hello`.
+                let mut encoder = Encoder::new();
                 response.encode(&mut encoder, ctx.client_revision)?;
+                connection.write_bytes(encoder.get_buffer()).await?;
+                connection.read_hello_addendum(ctx).await?;
+
+                ctx.handshake = Some(HandshakeResult {
+                    client_revision: ctx.client_revision,
+                    server_revision: response.dbms_tcp_protocol_version,
+                    client_name: hello.client_name,
+                    client_version_major: hello.client_version_major,
+                    client_version_minor: hello.client_version_minor,
+                    user: hello.user,
+                    default_database: hello.default_database,
+                    timezone: response.timezone,
+                    server_display_name: response.server_display_name,
+                    quota_key: ctx.quota_key.clone()
+                });
             }
             Packet::Query(query) => {
+                if let Some(hello) = ctx.hello.as_ref().filter(|hello| hello.is_interserver()) {
+                    check_interserver_hash(connection, hello, &query)?;
+                }
+
+                ctx.state.query_id = if query.query_id.is_empty() {
+                    generate_query_id()
+                } else {
+                    query.query_id.clone()
+                };
+                connection.query_id = ctx.state.query_id.clone();
+                ctx.state.initial_query_id = match query.client_info.query_kind {
+                    SECONDARY_QUERY if !query.client_info.initial_query_id.is_empty() => {
+                        query.client_info.initial_query_id.clone()
+                    }
+                    _ => ctx.state.query_id.clone()
+                };
+                connection.finished = false;
+                connection.header_sent = false;
                 ctx.state.query = query.query.clone();
+                ctx.state.processing_stage = query.stage;
                 ctx.state.compression = query.compression;
+                ctx.state.settings = query.settings.clone();
+                ctx.state.parameters = query.parameters.clone();
+                ctx.client_info = query.client_info.clone();
+                ctx.state.send_logs_level = query.send_logs_level;
+                ctx.state.is_cancelled = false;
+                ctx.state.cancellation_token = ctx.shutdown.child_token();
+                connection.cancellation_token = ctx.state.cancellation_token.clone();
+                read_external_data(connection, ctx).await?;
 
-                let session = connection.session.clone();
-                session.execute_query(ctx, connection).await?;
+                let timeout = match apply_limits(connection, ctx) {
+                    Ok(timeout) => timeout,
+                    Err(err) => return fail_query(connection, ctx, &err).await
+                };
 
-                if let Some(_) = &ctx.state.out {
-                    ctx.state.stage = Stage::InsertPrepare;
-                } else {
+                ctx.state.is_empty = query.query.trim().is_empty();
+                if ctx.state.is_empty {
+                    ctx.state.external_tables.clear();
+                    ctx.state.scalars.clear();
                     connection.write_end_of_stream().await?;
+                    return Ok(());
                 }
+
+                if let Some(limiter) = &ctx.concurrency_limiter {
+                    match limiter.try_acquire() {
+                        Ok(permit) => ctx.state.permit = Some(permit),
+                        Err(err) => return fail_query(connection, ctx, &err).await
+                    }
+                }
+
+                let session = connection.session.clone();
+                let res = match timeout {
+                    Some(timeout) => {
+                        let query = session.execute_query(ctx, connection);
+                        match tokio::time::timeout(timeout, query).await {
+                            Ok(res) => res,
+                            Err(_) => {
+                                ctx.state.cancellation_token.cancel();
+                                let err = Error::Server(ServerError {
+                                    name: "TIMEOUT_EXCEEDED".to_string(),
+                                    code: error_codes::TIMEOUT_EXCEEDED,
+                                    message: format!("Timeout exceeded: elapsed {:?}", timeout),
+                                    stack_trace: "".to_string()
+                                });
+                                // Only part of a packet went out, the connection
+                                // is closed without another byte.
+                                if connection.interrupted {
+                                    return Err(err);
+                                }
+                                return fail_query(connection, ctx, &err).await;
+                            }
+                        }
+                    }
+                    None => session.execute_query(ctx, connection).await
+                };
+                match res {
+                    // The client already has its `EndOfStream`, an exception
+                    // would be taken for the answer to its next query.
+                    Err(err) if connection.finished => {
+                        let query_id = &ctx.state.query_id;
+                        debug!("Query {} failed after it was finished: {}", query_id, err);
+                    }
+                    res => res?
+                }
+                ctx.state.is_cancelled = ctx.state.cancellation_token.is_cancelled();
+
+                if ctx.state.out.is_some() {
+                    ctx.state.stage = Stage::InsertStarted;
+                }
+                if !ctx.state.is_inserting() {
+                    ctx.state.external_tables.clear();
+                    ctx.state.scalars.clear();
+                    ctx.state.permit = None;
+                    if !connection.finished {
+                        write_session_progress(connection, ctx).await?;
+                        connection.write_end_of_stream().await?;
+                    }
+                }
+            }
+            Packet::Scalar(..) => {
+                // Scalars only come with a query.
+                return Err(Error::Driver(DriverError::UnexpectedPacket));
             }
-            Packet::Data(block) => {
+            Packet::Data(_, block) => {
                 if block.is_empty() {
                     match ctx.state.stage {
-                        Stage::InsertPrepare => {
-                            ctx.state.stage = Stage::InsertStarted;
-                        }
-                        Stage::InsertStarted => {
+                        Stage::InsertStarted if ctx.state.out.is_some() => {
                             // reset will reset the out, so the outer stream will break
                             ctx.state.reset();
 
                             ctx.state.sent_all_data.notified().await;
                             // wait stream finished
+                            write_session_progress(connection, ctx).await?;
                             connection.write_end_of_stream().await?;
                             ctx.state.stage = Stage::Default;
                         }
+                        Stage::InsertStarted => {
+                            let session = connection.session.clone();
+                            session.on_insert_block(ctx, block).await?;
+                            ctx.state.reset();
+                            write_session_progress(connection, ctx).await?;
+                            connection.write_end_of_stream().await?;
+                        }
                         _ => {}
                     }
-                } else {
-                    if let Some(out) = &ctx.state.out {
-                        // out.block_stream.
-                        out.send(block).await.unwrap();
-                    }
+                } else if let Some(out) = &ctx.state.out {
+                    // out.block_stream.
+                    out.send(block).await.unwrap();
+                } else if ctx.state.is_inserting() {
+                    let session = connection.session.clone();
+                    session.on_insert_block(ctx, block).await?;
                 }
             }
         };
 
-        let bytes = encoder.get_buffer();
-        if !bytes.is_empty() {
-            connection.write_bytes(bytes).await?;
-        }
         Ok(())
     }
 }
+
+fn authentication_failed(message: String) -> Error {
+    Error::Server(ServerError {
+        name: "AUTHENTICATION_FAILED".to_string(),
+        code: error_codes::AUTHENTICATION_FAILED,
+        message,
+        stack_trace: "".to_string()
+    })
+}
+
+/// Checks the hash a server of the cluster sends with each query, computed from
+/// the salt of its `Hello`, the cluster secret and the query.
+fn check_interserver_hash(
+    connection: &Connection,
+    hello: &HelloRequest,
+    query: &QueryRequest
+) -> Result<()> {
+    let secret = connection.session.interserver_secret(&hello.cluster).unwrap_or_default();
+    let expected = interserver_hash(
+        &hello.salt,
+        secret,
+        &query.query,
+        &query.query_id,
+        &query.client_info.initial_user
+    );
+
+    if !constant_time_eq(&query.interserver_hash, &expected) {
+        return Err(authentication_failed("Interserver authentication failed".to_string()));
+    }
+    Ok(())
+}
+
+/// A random (version 4) UUID, as the server makes up for queries without an id.
+fn generate_query_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Reads the scalars and temporary tables the client sends after its query, the
+/// latter as named data blocks up to an empty one. Clients send that empty
+/// block even when the query has neither.
+async fn read_external_data(connection: &mut Connection, ctx: &mut CHContext) -> Result<()> {
+    loop {
+        match connection.read_packet(ctx).await? {
+            Some(Packet::Data(table_name, block)) if table_name.is_empty() && block.is_empty() => {
+                return Ok(());
+            }
+            Some(Packet::Data(table_name, block)) => {
+                ctx.state.external_tables.entry(table_name).or_default().push(block);
+            }
+            Some(Packet::Scalar(name, block)) => {
+                ctx.state.scalars.insert(name, block);
+            }
+            Some(_) => return Err(Error::Driver(DriverError::UnexpectedPacket)),
+            None => return Err(Error::Driver(DriverError::UnexpectedEof { read: 0 }))
+        }
+    }
+}
+
+/// Answers the current query with `err` and leaves the connection ready for the
+/// next one, for errors that only concern the query.
+async fn fail_query(connection: &mut Connection, ctx: &mut CHContext, err: &Error) -> Result<()> {
+    debug!("Query {} failed: {}", ctx.state.query_id, err);
+    ctx.state.reset();
+    if connection.finished {
+        return Ok(());
+    }
+    connection.write_exception(err).await
+}
+
+/// Sends what `ClickHouseSession::get_progress` reports before the query ends,
+/// nothing if the session keeps no progress.
+async fn write_session_progress(connection: &mut Connection, ctx: &CHContext) -> Result<()> {
+    let progress = connection.session.get_progress();
+    if progress == Progress::default() {
+        return Ok(());
+    }
+    connection.write_progress(progress, ctx.client_revision).await
+}
+
+/// Applies the settings of the query the server reads itself and returns the
+/// time it may run. A value that does not parse fails the query up front.
+fn apply_limits(connection: &mut Connection, ctx: &CHContext) -> Result<Option<Duration>> {
+    connection.max_block_size = max_block_size(ctx)?;
+    execution_timeout(ctx)
+}
+
+/// Time a query may run, the lower of its `max_execution_time` setting (in
+/// seconds) and the server limit. Zero means unlimited for either.
+fn execution_timeout(ctx: &CHContext) -> Result<Option<Duration>> {
+    let setting = ctx
+        .state
+        .settings
+        .get_f64("max_execution_time")?
+        .filter(|secs| secs.is_finite() && *secs > 0.0)
+        .map(Duration::from_secs_f64);
+    let server = ctx.max_execution_time.filter(|timeout| !timeout.is_zero());
+
+    Ok(match (setting, server) {
+        (Some(setting), Some(server)) => Some(setting.min(server)),
+        (setting, server) => setting.or(server)
+    })
+}
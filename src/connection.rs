@@ -1,31 +1,79 @@
+use std::io;
 use std::io::Cursor;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use bytes::Buf;
 use bytes::BytesMut;
 use chrono_tz::Tz;
+use futures::FutureExt;
+use log::warn;
+use tokio::io::AsyncRead;
 use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
 use tokio::io::AsyncWriteExt;
 use tokio::io::BufWriter;
-use tokio::net::TcpStream;
+use tokio_util::sync::CancellationToken;
 
 use crate::binary::Encoder;
 use crate::binary::Parser;
+use crate::binary::ReadEx;
+use crate::binary::MAX_STRING_SIZE;
+use crate::errors::DriverError;
 use crate::errors::Error;
 use crate::errors::Result;
 use crate::protocols::ExceptionResponse;
+use crate::protocols::LogRecord;
+use crate::protocols::LogResponse;
+use crate::protocols::LogsLevel;
 use crate::protocols::Packet;
+use crate::protocols::ProfileEventsResponse;
+use crate::protocols::TableColumnsResponse;
+use crate::protocols::Stage;
+use crate::protocols::CLIENT_CANCEL;
+use crate::protocols::DEFAULT_MAX_QUERY_SIZE;
+use crate::protocols::DBMS_MIN_PROTOCOL_VERSION_WITH_ADDENDUM;
+use crate::protocols::DBMS_MIN_REVISION_WITH_COLUMN_DEFAULTS_METADATA;
+use crate::protocols::DBMS_MIN_PROTOCOL_VERSION_WITH_INCREMENTAL_PROFILE_EVENTS;
+use crate::protocols::DBMS_MIN_PROTOCOL_VERSION_WITH_QUOTA_KEY;
+use crate::protocols::DBMS_MIN_REVISION_WITH_SERVER_LOGS;
+use crate::protocols::DBMS_TCP_PROTOCOL_VERSION;
+use crate::protocols::SERVER_DATA;
 use crate::protocols::SERVER_END_OF_STREAM;
+use crate::protocols::SERVER_EXTREMES;
+use crate::protocols::SERVER_PONG;
+use crate::protocols::SERVER_TOTALS;
+use crate::types::block::MAX_DECOMPRESSED_SIZE;
+use crate::types::column::SerializationSettings;
 use crate::types::Block;
+use crate::types::ColumnType;
+use crate::types::Complex;
 use crate::types::Progress;
 use crate::CHContext;
 use crate::ClickHouseSession;
 
+/// Rows per result block when the query does not set `max_block_size`.
+const DEFAULT_MAX_BLOCK_SIZE: usize = 65505;
+
+/// Rows per result block of the current query, its `max_block_size` setting or
+/// else the server limit. Fails if the setting is not a number.
+pub(crate) fn max_block_size(ctx: &CHContext) -> Result<usize> {
+    let setting = ctx.state.settings.get_u64("max_block_size")?;
+    Ok(setting
+        .map(|size| size as usize)
+        .filter(|size| *size > 0)
+        .or(ctx.max_block_size)
+        .filter(|size| *size > 0)
+        .unwrap_or(DEFAULT_MAX_BLOCK_SIZE))
+}
+
 /// Send and receive `Packet` values from a remote peer.
 ///
 /// When implementing networking protocols, a message on that protocol is
 /// often composed of several smaller messages known as frames. The purpose of
-/// `Connection` is to read and write frames on the underlying `TcpStream`.
+/// `Connection` is to read and write frames on the underlying stream, which can
+/// be any transport such as a `TcpStream` or a TLS stream wrapping it.
 ///
 /// To read frames, the `Connection` uses an internal buffer, which is filled
 /// up until there are enough bytes to create a full frame. Once this happens,
@@ -34,39 +82,168 @@ use crate::ClickHouseSession;
 /// When sending frames, the frame is first encoded into the write buffer.
 /// The contents of the write buffer are then written to the socket.
 pub struct Connection {
-    // The `TcpStream`. It is decorated with a `BufWriter`, which provides write
-    // level buffering. The `BufWriter` implementation provided by Tokio is
-    // sufficient for our needs.
+    // The buffer for reading frames.
     pub buffer: BytesMut,
 
-    stream: BufWriter<TcpStream>,
+    // The transport. It is decorated with a `BufWriter`, which provides write
+    // level buffering. The `BufWriter` implementation provided by Tokio is
+    // sufficient for our needs.
+    stream: BufWriter<Box<dyn AsyncStream>>,
     pub session: Arc<dyn ClickHouseSession>,
 
-    // The buffer for reading frames.
     tz: Tz,
     with_stack_trace: bool,
-    compress: bool
+    compression: CompressionMethod,
+    send_logs_level: LogsLevel,
+    pub(crate) query_id: String,
+    // The revision agreed in `Hello`, data blocks are written for it.
+    pub(crate) client_revision: u64,
+    // `EndOfStream` was sent early for the running query, see `BlockSink::finish`.
+    pub(crate) finished: bool,
+    // The empty block with the result columns was sent for the running query.
+    pub(crate) header_sent: bool,
+    // Rows per block of the running query, see `max_block_size`.
+    pub(crate) max_block_size: usize,
+    // Set while `write_bytes` runs. It stays set when a query is dropped by its
+    // timeout in the middle of a packet, then nothing may follow the part sent.
+    pub(crate) interrupted: bool,
+    pub(crate) cancellation_token: CancellationToken,
+    stats: Arc<ConnectionStats>
+}
+
+/// Traffic counters of a [`Connection`], updated as packets go through it.
+///
+/// The counters are shared, a session can keep the handle returned by
+/// [`Connection::stats`] and read them after the connection is closed.
+#[derive(Debug, Default)]
+pub struct ConnectionStats {
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    packets_read: AtomicU64,
+    blocks_sent: AtomicU64
+}
+
+impl ConnectionStats {
+    /// Bytes received from the client.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    /// Bytes sent to the client.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written.load(Ordering::Relaxed)
+    }
+
+    /// Packets parsed from the client, `Hello` included.
+    pub fn packets_read(&self) -> u64 {
+        self.packets_read.load(Ordering::Relaxed)
+    }
+
+    /// `Data`, `Totals` and `Extremes` blocks sent to the client.
+    pub fn blocks_sent(&self) -> u64 {
+        self.blocks_sent.load(Ordering::Relaxed)
+    }
+}
+
+trait AsyncStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> AsyncStream for S {}
+
+/// Block compression negotiated with the client.
+///
+/// The client requests compression per query, every `Data` packet that follows
+/// is then framed with a CityHash128 checksum, the method byte and the
+/// compressed/uncompressed sizes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CompressionMethod {
+    #[default]
+    None,
+    LZ4,
+    /// Requested with the `network_compression_method` setting. Outgoing blocks
+    /// are valid zstd frames whose payload is stored uncompressed. Client blocks
+    /// are read when their frames hold raw or RLE blocks only, anything else
+    /// fails with `DriverError::UnsupportedCompression`.
+    ZSTD
+}
+
+impl CompressionMethod {
+    /// Returns `true` if blocks are compressed on the wire.
+    pub fn is_enabled(self) -> bool {
+        !matches!(self, CompressionMethod::None)
+    }
+
+    /// The method of a query: `compression` turns it on, the
+    /// `network_compression_method` setting picks it, LZ4 by default.
+    pub(crate) fn for_query(compression: u64, method_setting: Option<&str>) -> Self {
+        match (CompressionMethod::from(compression), method_setting) {
+            (CompressionMethod::LZ4, Some(method)) if method.eq_ignore_ascii_case("zstd") => {
+                CompressionMethod::ZSTD
+            }
+            (method, _) => method
+        }
+    }
+}
+
+impl From<u64> for CompressionMethod {
+    fn from(compression: u64) -> Self {
+        match compression {
+            0 => CompressionMethod::None,
+            _ => CompressionMethod::LZ4
+        }
+    }
 }
 
 impl Connection {
-    /// Create a new `Connection`, backed by `socket`. Read and write buffers
+    /// Create a new `Connection`, backed by `stream`. Read and write buffers
     /// are initialized.
-    pub fn new(
-        stream: TcpStream,
+    pub fn new<S>(
+        stream: S,
         session: Arc<dyn ClickHouseSession>,
         timezone: String
-    ) -> Result<Connection> {
+    ) -> Result<Connection>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static
+    {
         let tz: Tz = timezone.parse()?;
+        let stream: Box<dyn AsyncStream> = Box::new(stream);
+        let with_stack_trace = session.with_stack_trace();
         Ok(Connection {
             stream: BufWriter::new(stream),
             buffer: BytesMut::with_capacity(4 * 1024),
             session,
             tz,
-            with_stack_trace: false,
-            compress: true
+            with_stack_trace,
+            compression: CompressionMethod::default(),
+            send_logs_level: LogsLevel::None,
+            query_id: String::new(),
+            client_revision: DBMS_TCP_PROTOCOL_VERSION,
+            finished: false,
+            header_sent: false,
+            max_block_size: DEFAULT_MAX_BLOCK_SIZE,
+            interrupted: false,
+            cancellation_token: CancellationToken::new(),
+            stats: Arc::default()
         })
     }
 
+    /// Returns the traffic counters of this connection.
+    pub fn stats(&self) -> Arc<ConnectionStats> {
+        self.stats.clone()
+    }
+
+    /// Reads whatever the client sent so far into the buffer, `0` at the end of
+    /// the stream.
+    async fn fill_buffer(&mut self) -> Result<usize> {
+        let n = self.stream.read_buf(&mut self.buffer).await?;
+        self.stats.bytes_read.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+
+    /// Returns the compression negotiated by the last `Query` packet.
+    pub fn compression(&self) -> CompressionMethod {
+        self.compression
+    }
+
     /// Read a single `Packet` value from the underlying stream.
     ///
     /// The function waits until it has retrieved enough data to parse a frame.
@@ -79,6 +256,9 @@ impl Connection {
     /// is closed in a way that doesn't break a frame in half, it returns
     /// `None`. Otherwise, an error is returned.
     pub async fn read_packet(&mut self, ctx: &mut CHContext) -> crate::Result<Option<Packet>> {
+        // The client may be waiting for what was written so far.
+        self.flush().await?;
+
         loop {
             // Attempt to parse a frame from the buffered data. If enough data
             // has been buffered, the frame is returned.
@@ -91,7 +271,7 @@ impl Connection {
             //
             // On success, the number of bytes is returned. `0` indicates "end
             // of stream".
-            if 0 == self.stream.read_buf(&mut self.buffer).await? {
+            if 0 == self.fill_buffer().await? {
                 // The remote closed the connection. For this to be a clean
                 // shutdown, there should be no data in the read buffer. If
                 // there is, this means that the peer closed the socket while
@@ -99,12 +279,49 @@ impl Connection {
                 if self.buffer.is_empty() {
                     return Ok(None);
                 } else {
-                    return Err("connection reset by peer".into());
+                    return Err(self.truncated());
+                }
+            }
+        }
+    }
+
+    /// Reads the addendum that follows the `Hello` exchange on new revisions. It is
+    /// not a packet, just the fields themselves.
+    pub(crate) async fn read_hello_addendum(&mut self, ctx: &mut CHContext) -> Result<()> {
+        if ctx.client_revision < DBMS_MIN_PROTOCOL_VERSION_WITH_ADDENDUM {
+            return Ok(());
+        }
+        self.flush().await?;
+
+        loop {
+            let mut buf = Cursor::new(&self.buffer[..]);
+            match buf.read_string() {
+                Ok(quota_key) => {
+                    let len = buf.position() as usize;
+                    self.buffer.advance(len);
+                    if ctx.client_revision >= DBMS_MIN_PROTOCOL_VERSION_WITH_QUOTA_KEY {
+                        ctx.quota_key = quota_key;
+                    }
+                    return Ok(());
                 }
+                Err(err) if err.is_would_block() => {}
+                Err(err) => return Err(err)
+            }
+
+            if 0 == self.fill_buffer().await? {
+                return Err(self.truncated());
             }
         }
     }
 
+    // The client closed the connection in the middle of a packet.
+    fn truncated(&self) -> Error {
+        warn!("Connection closed with {} bytes of a packet read", self.buffer.len());
+        Error::Driver(DriverError::UnexpectedEof {
+            read: self.buffer.len()
+        })
+    }
+
     /// Tries to parse a frame from the buffer. If the buffer contains enough
     /// data, the frame is returned and the data removed from the buffer. If not
     /// enough data has been buffered yet, `Ok(None)` is returned. If the
@@ -118,13 +335,23 @@ impl Connection {
         let mut parser = Parser::new(&mut buf, self.tz);
 
         let hello = ctx.hello.clone();
-        let packet = parser.parse_packet(&hello, self.compress);
+        let compress = self.compression.is_enabled();
+        let max_query_size = ctx.max_query_size.unwrap_or(DEFAULT_MAX_QUERY_SIZE);
+        let settings = SerializationSettings {
+            client_revision: ctx.client_revision,
+            max_string_size: ctx.max_string_size.unwrap_or(MAX_STRING_SIZE),
+            max_decompressed_size: ctx.max_decompressed_size.unwrap_or(MAX_DECOMPRESSED_SIZE)
+        };
+        let packet = parser.parse_packet(&hello, compress, max_query_size, settings);
 
         match packet {
             Ok(packet) => {
-                match &packet {
-                    Packet::Query(ref query) => self.compress = query.compression > 0,
-                    _ => {}
+                if let Packet::Query(ref query) = &packet {
+                    self.compression = CompressionMethod::for_query(
+                        query.compression,
+                        query.settings.get_str("network_compression_method")
+                    );
+                    self.send_logs_level = query.send_logs_level;
                 }
                 // The `check` function will have advanced the cursor until the
                 // end of the frame. Since the cursor had position set to zero
@@ -133,6 +360,7 @@ impl Connection {
                 let len = buf.position() as usize;
                 buf.set_position(0);
                 self.buffer.advance(len);
+                self.stats.packets_read.fetch_add(1, Ordering::Relaxed);
                 // Return the parsed frame to the caller.
                 Ok(Some(packet))
             }
@@ -151,41 +379,297 @@ impl Connection {
         }
     }
 
+    /// Checks, without waiting, whether the client has cancelled the running
+    /// query. Bytes already sent by the client are buffered, and a pending
+    /// `Cancel` packet fires the query's cancellation token.
+    pub fn poll_cancel(&mut self) -> Result<bool> {
+        if self.cancellation_token.is_cancelled() {
+            return Ok(true);
+        }
+
+        while let Some(res) = self.fill_buffer().now_or_never() {
+            match res? {
+                // The end of stream is reported by the next `read_packet`.
+                0 => break,
+                _ => continue
+            }
+        }
+
+        if self.buffer.first() == Some(&(CLIENT_CANCEL as u8)) {
+            self.buffer.advance(1);
+            self.cancellation_token.cancel();
+        }
+        Ok(self.cancellation_token.is_cancelled())
+    }
+
+    /// Returns a sink streaming the result blocks of the current query.
+    pub fn block_sink(&mut self, ctx: &CHContext) -> BlockSink<'_> {
+        BlockSink {
+            max_block_size: self.max_block_size,
+            connection: self,
+            client_revision: ctx.client_revision
+        }
+    }
+
     pub async fn write_block(&mut self, block: &Block) -> Result<()> {
+        self.write_data_packet(block, SERVER_DATA).await
+    }
+
+    /// Answers an INSERT with the header block, the (usually empty) block whose
+    /// columns the client must send. The following data blocks are passed to
+    /// [`ClickHouseSession::on_insert_block`].
+    pub async fn start_insert(&mut self, ctx: &mut CHContext, header: &Block) -> Result<()> {
+        self.write_block(header).await?;
+        ctx.state.stage = Stage::InsertStarted;
+        Ok(())
+    }
+
+    /// Sends the `WITH TOTALS` row of a query, usually after all data blocks.
+    pub async fn write_totals(&mut self, block: &Block) -> Result<()> {
+        self.write_data_packet(block, SERVER_TOTALS).await
+    }
+
+    /// Sends the minimum and maximum rows of a query run with `extremes = 1`.
+    pub async fn write_extremes(&mut self, block: &Block) -> Result<()> {
+        self.write_data_packet(block, SERVER_EXTREMES).await
+    }
+
+    async fn write_data_packet<K>(&mut self, block: &Block<K>, packet: u64) -> Result<()>
+    where K: ColumnType {
+        self.poll_cancel()?;
+        if self.finished {
+            return Err(Error::Driver(DriverError::StreamFinished));
+        }
         let mut encoder = Encoder::new();
-        block.send_server_data(&mut encoder, self.compress);
-        self.stream.write_all(&encoder.get_buffer()).await?;
-        self.stream.flush().await?;
+        let settings = SerializationSettings {
+            client_revision: self.client_revision,
+            ..Default::default()
+        };
+        block.send_data(&mut encoder, packet, self.compression, settings)?;
+        self.write_bytes(encoder.get_buffer()).await?;
+        self.stats.blocks_sent.fetch_add(1, Ordering::Relaxed);
         Ok(())
     }
 
     pub async fn write_progress(&mut self, progress: Progress, client_revision: u64) -> Result<()> {
+        self.poll_cancel()?;
         let mut encoder = Encoder::new();
         progress.write(&mut encoder, client_revision);
-        self.stream.write_all(&encoder.get_buffer()).await?;
-        self.stream.flush().await?;
-        Ok(())
+        self.write_bytes(encoder.get_buffer()).await?;
+        self.flush().await
+    }
+
+    /// Sends named counters (e.g. `SelectedRows`) as a `ProfileEvents` block,
+    /// clients older than the revision that understands the packet get nothing.
+    pub async fn write_profile_events(
+        &mut self,
+        counters: &[(&str, i64)],
+        client_revision: u64
+    ) -> Result<()> {
+        if client_revision < DBMS_MIN_PROTOCOL_VERSION_WITH_INCREMENTAL_PROFILE_EVENTS {
+            return Ok(());
+        }
+
+        let host_name = hostname::get()?.to_string_lossy().into_owned();
+        let mut encoder = Encoder::new();
+        ProfileEventsResponse::write(&mut encoder, counters, &host_name);
+        self.write_bytes(encoder.get_buffer()).await
+    }
+
+    /// Describes the columns of the table a query reads or writes, as `(name, type)`
+    /// pairs. Clients older than the `TableColumns` packet get nothing.
+    pub async fn write_table_columns(
+        &mut self,
+        columns: &[(String, String)],
+        client_revision: u64
+    ) -> Result<()> {
+        if client_revision < DBMS_MIN_REVISION_WITH_COLUMN_DEFAULTS_METADATA {
+            return Ok(());
+        }
+
+        let mut encoder = Encoder::new();
+        TableColumnsResponse::write(&mut encoder, columns);
+        self.write_bytes(encoder.get_buffer()).await
+    }
+
+    /// Streams a server log line to the client, records below the client's
+    /// `send_logs_level` are dropped.
+    pub async fn write_log_line(&mut self, record: &LogRecord, client_revision: u64) -> Result<()> {
+        if client_revision < DBMS_MIN_REVISION_WITH_SERVER_LOGS
+            || !self.send_logs_level.accepts(record.priority)
+        {
+            return Ok(());
+        }
+
+        let host_name = hostname::get()?.to_string_lossy().into_owned();
+        let mut encoder = Encoder::new();
+        LogResponse::write(&mut encoder, std::slice::from_ref(record), &host_name);
+        self.write_bytes(encoder.get_buffer()).await?;
+        self.flush().await
+    }
+
+    /// Shows `text` to the client as a warning of the running query, e.g. about
+    /// deprecated syntax, without failing it. Like any log line it is only sent
+    /// if the client asked for logs of warnings.
+    pub async fn write_warning(&mut self, text: &str, client_revision: u64) -> Result<()> {
+        let mut record = LogRecord::new(LogsLevel::Warning, self.session.dbms_name(), text);
+        record.query_id = self.query_id.clone();
+        self.write_log_line(&record, client_revision).await
+    }
+
+    /// Answers a `Ping`, which the client may send before the handshake or
+    /// between queries.
+    pub async fn write_pong(&mut self) -> Result<()> {
+        let mut encoder = Encoder::new();
+        encoder.uvarint(SERVER_PONG);
+        self.write_bytes(encoder.get_buffer()).await?;
+        self.flush().await
     }
 
     pub async fn write_end_of_stream(&mut self) -> Result<()> {
         let mut encoder = Encoder::new();
         encoder.uvarint(SERVER_END_OF_STREAM);
         self.write_bytes(encoder.get_buffer()).await?;
-        Ok(())
+        self.flush().await
     }
 
-    pub async fn write_error(&mut self, err: &Error) -> Result<()> {
+    /// Sends `err` as an `Exception` packet, with a stack trace if the session
+    /// asks for it.
+    pub async fn write_exception(&mut self, err: &Error) -> Result<()> {
+        // The client would read it as the rest of the packet cut off.
+        if self.interrupted {
+            return Ok(());
+        }
         let mut encoder = Encoder::new();
         ExceptionResponse::write(&mut encoder, &err, self.with_stack_trace);
+        self.write_bytes(encoder.get_buffer()).await?;
+        self.flush().await
+    }
 
-        self.stream.write_all(&encoder.get_buffer()).await?;
-        self.stream.flush().await?;
-        Ok(())
+    pub async fn write_error(&mut self, err: &Error) -> Result<()> {
+        self.write_exception(err).await
     }
 
+    /// Queues `bytes` for the client. They are sent once the write buffer fills
+    /// up, on [`flush`](Connection::flush), or at the latest when the
+    /// connection waits for the next packet; progress, logs, `Pong`,
+    /// `EndOfStream` and exceptions are flushed right away.
+    ///
+    /// Fails once a query timed out while a packet was being written, only part
+    /// of it may have been sent.
     pub async fn write_bytes(&mut self, bytes: Vec<u8>) -> Result<()> {
+        if self.interrupted {
+            return Err(Error::IO(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "a packet was cut off by the query timeout"
+            )));
+        }
+        self.interrupted = true;
         self.stream.write_all(&bytes).await?;
+        self.interrupted = false;
+        self.stats.bytes_written.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Sends everything written so far to the client.
+    pub async fn flush(&mut self) -> Result<()> {
         self.stream.flush().await?;
         Ok(())
     }
 }
+
+/// Streams the result of a query to the client one block at a time.
+///
+/// Every block is encoded, compressed if the query asked for it, and written as
+/// soon as it is pushed, so a session only holds the block it is building.
+/// Blocks over the `max_block_size` setting of the query are sent in parts.
+pub struct BlockSink<'a> {
+    connection: &'a mut Connection,
+    client_revision: u64,
+    max_block_size: usize
+}
+
+impl<'a> BlockSink<'a> {
+    /// Sends `block` and flushes it to the transport. Once a slow client lets
+    /// the socket buffer fill up, `push` waits until it reads, so the producer
+    /// is held to the client's pace instead of queueing blocks in memory.
+    ///
+    /// The first block of a query is preceded by the header, its columns with
+    /// no rows, unless it has no rows itself or [`BlockSink::write_header`] was
+    /// called.
+    pub async fn push(&mut self, block: &Block) -> Result<()> {
+        if !self.connection.header_sent && block.row_count() > 0 {
+            self.write_header(block).await?;
+        }
+        self.connection.header_sent = true;
+
+        if block.row_count() <= self.max_block_size {
+            self.connection.write_block(block).await?;
+        } else {
+            for part in split_block(block, self.max_block_size) {
+                self.connection.write_data_packet(&part, SERVER_DATA).await?;
+            }
+        }
+        self.connection.flush().await
+    }
+
+    /// Sends the header of the result, the columns of `schema` with no rows,
+    /// e.g. before a query that may not find any row.
+    pub async fn write_header(&mut self, schema: &Block) -> Result<()> {
+        let header = schema.slice(0, 0);
+        self.connection
+            .write_data_packet(&header, SERVER_DATA)
+            .await?;
+        self.connection.header_sent = true;
+        Ok(())
+    }
+
+    /// Ends the result here with `EndOfStream`, e.g. once a `LIMIT` is reached,
+    /// while `execute_query` may still run. Blocks sent after it fail with
+    /// `DriverError::StreamFinished`, as does a second `finish`, and the query
+    /// is not ended a second time. Nor is it answered with the error that
+    /// `execute_query` may return afterwards.
+    pub async fn finish(&mut self) -> Result<()> {
+        if self.connection.finished {
+            return Err(Error::Driver(DriverError::StreamFinished));
+        }
+        self.connection.write_end_of_stream().await?;
+        self.connection.finished = true;
+        Ok(())
+    }
+
+    /// Reports progress to the client, usually after each pushed block.
+    pub async fn write_progress(&mut self, progress: Progress) -> Result<()> {
+        self.connection
+            .write_progress(progress, self.client_revision)
+            .await
+    }
+}
+
+/// Splits `block` into consecutive blocks of at most `max_block_size` rows.
+fn split_block(block: &Block, max_block_size: usize) -> Vec<Block<Complex>> {
+    (0..block.row_count())
+        .step_by(max_block_size)
+        .map(|offset| block.slice(offset, max_block_size))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_block() {
+        let block = Block::new().column("a", vec![7_u8; 200_000]);
+        let parts = split_block(&block, 65535);
+
+        let rows: Vec<usize> = parts.iter().map(|part| part.row_count()).collect();
+        assert_eq!(rows, vec![65535, 65535, 65535, 3395]);
+        assert_eq!(parts[3], block.slice(196_605, 3395));
+
+        let block = Block::new().column("a", vec![7_u8; 10]);
+        let parts = split_block(&block, 65535);
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0], block);
+    }
+}
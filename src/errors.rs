@@ -9,6 +9,8 @@ use thiserror::Error;
 use tokio::time::error::Elapsed;
 use url::ParseError;
 
+use crate::error_codes;
+
 /// Result type alias for this library.
 pub type Result<T> = result::Result<T, Error>;
 
@@ -86,6 +88,9 @@ pub enum DriverError {
     #[error("Varint overflows a 64-bit integer.")]
     Overflow,
 
+    #[error("Checksum doesn't match: corrupted data.")]
+    ChecksumMismatch,
+
     #[error("Unknown packet 0x{:x}.", packet)]
     UnknownPacket { packet: u64 },
 
@@ -99,7 +104,31 @@ pub enum DriverError {
     Utf8Error(Utf8Error),
 
     #[error("UnknownSetting name {}", name)]
-    UnknownSetting { name: String }
+    UnknownSetting { name: String },
+
+    #[error("Object of {} bytes exceeds the limit of {} bytes.", size, max_size)]
+    TooLargeObject { size: u64, max_size: usize },
+
+    #[error("Unknown query processing stage {}.", stage)]
+    UnknownStage { stage: u64 },
+
+    #[error("Connection closed with {} bytes of a packet read.", read)]
+    UnexpectedEof { read: usize },
+
+    /// A compression method the server can't decode, or a zstd frame with
+    /// blocks compressed by entropy coding.
+    #[error("Unsupported compression method 0x{:x}.", method)]
+    UnsupportedCompression { method: u8 },
+
+    #[error("The result of the query is already finished.")]
+    StreamFinished,
+
+    #[error("Invalid value `{}` of setting {}, expected {}.", value, name, expected)]
+    InvalidSetting {
+        name: String,
+        value: String,
+        expected: &'static str
+    }
 }
 
 /// This type enumerates cast from sql type errors.
@@ -114,8 +143,26 @@ pub enum FromSqlError {
     #[error("Out of range.")]
     OutOfRange,
 
+    #[error("Column `{}` not found.", name)]
+    MissingColumn { name: String },
+
+    #[error("Block columns ({}) do not match ({}).", src, dst)]
+    SchemaMismatch { src: String, dst: String },
+
+    #[error("Value of {} bytes does not fit into FixedString({}).", len, str_len)]
+    FixedStringOverflow { len: usize, str_len: usize },
+
+    #[error("Sizes of arrays in nested column `{}` differ at row {}.", name, row)]
+    NestedSizesMismatch { name: String, row: usize },
+
+    #[error("Unsupported column type \"{}\".", name)]
+    UnknownType { name: String },
+
     #[error("Unsupported operation.")]
-    UnsupportedOperation
+    UnsupportedOperation,
+
+    #[error("Inconsistent block: {}.", reason)]
+    InconsistentBlock { reason: String }
 }
 
 impl Error {
@@ -150,6 +197,11 @@ impl From<DriverError> for Error {
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
+        // Errors raised behind an `io::Read` adapter travel wrapped in `io::Error`.
+        if err.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            let inner = err.into_inner().unwrap();
+            return *inner.downcast::<Error>().unwrap();
+        }
         Error::IO(err)
     }
 }
@@ -158,7 +210,7 @@ impl From<Error> for io::Error {
     fn from(err: Error) -> Self {
         match err {
             Error::IO(error) => error,
-            e => io::Error::new(io::ErrorKind::Other, e.to_string())
+            e => io::Error::new(io::ErrorKind::Other, e)
         }
     }
 }
@@ -213,6 +265,49 @@ impl From<Utf8Error> for Error {
 }
 
 impl Error {
+    /// The ClickHouse error code sent to the client for this error.
+    pub fn code(&self) -> u32 {
+        match self {
+            Error::Driver(e) => match e {
+                DriverError::Overflow => error_codes::CANNOT_PARSE_INPUT_ASSERTION_FAILED,
+                DriverError::ChecksumMismatch => error_codes::CHECKSUM_DOESNT_MATCH,
+                DriverError::UnknownPacket { .. } => error_codes::UNKNOWN_PACKET_FROM_CLIENT,
+                DriverError::UnexpectedPacket => error_codes::UNEXPECTED_PACKET_FROM_CLIENT,
+                DriverError::Timeout => error_codes::TIMEOUT_EXCEEDED,
+                DriverError::Utf8Error(_) => error_codes::CANNOT_PARSE_TEXT,
+                DriverError::UnknownSetting { .. } => error_codes::UNKNOWN_SETTING,
+                DriverError::TooLargeObject { .. } => error_codes::TOO_LARGE_STRING_SIZE,
+                DriverError::UnknownStage { .. } => error_codes::BAD_ARGUMENTS,
+                DriverError::UnexpectedEof { .. } => error_codes::ATTEMPT_TO_READ_AFTER_EOF,
+                DriverError::UnsupportedCompression { .. } => {
+                    error_codes::UNKNOWN_COMPRESSION_METHOD
+                }
+                DriverError::StreamFinished => error_codes::LOGICAL_ERROR,
+                DriverError::InvalidSetting { .. } => error_codes::BAD_ARGUMENTS
+            },
+            Error::IO(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                error_codes::ATTEMPT_TO_READ_AFTER_EOF
+            }
+            Error::IO(_) | Error::Connection(_) => error_codes::NETWORK_ERROR,
+            Error::Other(_) => error_codes::UNKNOWN_EXCEPTION,
+            Error::Server(e) => e.code,
+            Error::Url(_) => error_codes::BAD_ARGUMENTS,
+            Error::FromSql(e) => match e {
+                FromSqlError::InvalidType { .. } => error_codes::TYPE_MISMATCH,
+                FromSqlError::OutOfRange => error_codes::ARGUMENT_OUT_OF_BOUND,
+                FromSqlError::MissingColumn { .. } => error_codes::NOT_FOUND_COLUMN_IN_BLOCK,
+                FromSqlError::SchemaMismatch { .. } => error_codes::TYPE_MISMATCH,
+                FromSqlError::FixedStringOverflow { .. } => error_codes::TOO_LARGE_STRING_SIZE,
+                FromSqlError::NestedSizesMismatch { .. } => {
+                    error_codes::SIZES_OF_ARRAYS_DOESNT_MATCH
+                }
+                FromSqlError::UnknownType { .. } => error_codes::UNKNOWN_TYPE,
+                FromSqlError::UnsupportedOperation => error_codes::NOT_IMPLEMENTED,
+                FromSqlError::InconsistentBlock { .. } => error_codes::LOGICAL_ERROR
+            }
+        }
+    }
+
     pub fn exception_name(&self) -> &str {
         match self {
             Error::Driver(_) => "DriverException",
@@ -228,6 +323,8 @@ impl Error {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn to_std_error_without_recursion() {
         let src_err: super::Error = From::from("Somth went wrong.");
@@ -241,4 +338,35 @@ mod tests {
         let dst_err: std::io::Error = src_err.into();
         assert_eq!(dst_err.to_string(), "Other error: `Somth went wrong.`");
     }
+
+    #[test]
+    fn from_io_error_keeps_driver_error() {
+        let src_err: super::Error = super::DriverError::ChecksumMismatch.into();
+        let io_err: std::io::Error = src_err.into();
+        let dst_err: super::Error = io_err.into();
+        assert!(matches!(
+            dst_err,
+            super::Error::Driver(super::DriverError::ChecksumMismatch)
+        ));
+    }
+
+    #[test]
+    fn maps_errors_to_codes() {
+        let err = Error::Driver(DriverError::UnexpectedPacket);
+        assert_eq!(err.code(), error_codes::UNEXPECTED_PACKET_FROM_CLIENT);
+
+        let err = Error::Driver(DriverError::ChecksumMismatch);
+        assert_eq!(err.code(), error_codes::CHECKSUM_DOESNT_MATCH);
+
+        let err = Error::FromSql(FromSqlError::UnknownType {
+            name: "Int1024".to_string()
+        });
+        assert_eq!(err.code(), error_codes::UNKNOWN_TYPE);
+
+        let err = Error::IO(io::ErrorKind::UnexpectedEof.into());
+        assert_eq!(err.code(), error_codes::ATTEMPT_TO_READ_AFTER_EOF);
+
+        let err: Error = "Somth went wrong.".into();
+        assert_eq!(err.code(), error_codes::UNKNOWN_EXCEPTION);
+    }
 }
@@ -1,17 +1,40 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
+use std::time::Duration;
 
+use errors::Error;
 use errors::Result;
+use errors::ServerError;
+use futures::FutureExt;
 use log::debug;
+use log::error;
 use protocols::Stage;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::net::TcpListener;
 use tokio::net::TcpStream;
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::Notify;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
 
 use crate::cmd::Cmd;
 use crate::connection::Connection;
+use crate::protocols::ClientInfo;
 use crate::protocols::HelloRequest;
+use crate::protocols::LogsLevel;
+use crate::protocols::Packet;
+use crate::protocols::QueryProcessingStage;
+use crate::protocols::TraceContext;
 use crate::types::Block;
 use crate::types::Progress;
+use crate::types::Settings;
 
 mod binary;
 pub mod cmd;
@@ -19,12 +42,52 @@ pub mod connection;
 pub mod error_codes;
 pub mod errors;
 pub mod protocols;
+pub mod recording;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod types;
 
 #[async_trait::async_trait]
 pub trait ClickHouseSession: Send + Sync {
     async fn execute_query(&self, ctx: &mut CHContext, connection: &mut Connection) -> Result<()>;
 
+    /// Receives the data of an INSERT that `execute_query` accepted with
+    /// [`Connection::start_insert`]. The exchange is:
+    ///
+    /// 1. the client sends `Query` followed by an empty `Data` block;
+    /// 2. the server sends the header block, the columns the client must send;
+    /// 3. the client sends any number of `Data` blocks, each passed here;
+    /// 4. the client sends an empty `Data` block, passed here as well to mark the
+    ///    end of the insert, and the server then answers with `EndOfStream`.
+    async fn on_insert_block(&self, _ctx: &mut CHContext, _block: Block) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks the credentials sent in `Hello`. `client_addr` is `None` when the
+    /// transport has no socket address, e.g. a Unix socket. A rejected client
+    /// gets an `AUTHENTICATION_FAILED` exception and is disconnected.
+    ///
+    /// The native protocol has no challenge/response step for passwords, clients
+    /// of every revision send the password as is. `sha256_password` and
+    /// `double_sha1_password` only describe how the server stores it, so a
+    /// session keeping such hashes compares them against the hashed `password`.
+    async fn authenticate(
+        &self,
+        _user: &str,
+        _password: &str,
+        _client_addr: Option<SocketAddr>
+    ) -> Result<bool> {
+        Ok(true)
+    }
+
+    /// The secret of `cluster`, shared by its servers in their `<secret>`. A
+    /// server of the cluster connects with it instead of a user and password,
+    /// each query it sends carries a hash of the secret that must match.
+    /// Interserver connections are refused while it returns `None`.
+    fn interserver_secret(&self, _cluster: &str) -> Option<&str> {
+        None
+    }
+
     fn with_stack_trace(&self) -> bool {
         false
     }
@@ -42,22 +105,30 @@ pub trait ClickHouseSession: Send + Sync {
         17
     }
 
+    /// The highest protocol revision the server speaks. Packets are encoded for
+    /// the lower of it and the client's revision, see `CHContext::client_revision`.
+    /// Values over `protocols::DBMS_MAX_SUPPORTED_PROTOCOL_VERSION` are lowered to it.
     fn dbms_tcp_protocol_version(&self) -> u64 {
-        54428
+        protocols::DBMS_TCP_PROTOCOL_VERSION
     }
 
     fn timezone(&self) -> &str {
         "UTC"
     }
 
+    /// Name the client shows for the server, sent to clients from revision 54372.
+    /// Like the version, it is asked for on each `Hello`.
     fn server_display_name(&self) -> &str {
         "clickhouse-server"
     }
 
+    /// Sent to clients from revision 54401, older ones only see major and minor.
     fn dbms_version_patch(&self) -> u64 {
         1
     }
 
+    /// Progress of the current query, sent before its `EndOfStream`. A known
+    /// `total_rows` lets the client show a percentage and an ETA.
     fn get_progress(&self) -> Progress {
         Progress::default()
     }
@@ -65,35 +136,93 @@ pub trait ClickHouseSession: Send + Sync {
 
 #[derive(Default)]
 pub struct QueryState {
+    /// Id sent with the query, or a random UUID when the client sent none. Use
+    /// it for `LogRecord::query_id`.
     pub query_id: String,
+    /// Id of the query that started the distributed query this one is part
+    /// of, the same as `query_id` unless another server sent the query.
+    pub initial_query_id: String,
     pub stage: Stage,
+    /// The stage the client asked the query to be processed up to.
+    pub processing_stage: QueryProcessingStage,
+    /// The compression byte of the query, set again by each `Query`. Data blocks
+    /// of the query are compressed both ways when it is not 0.
     pub compression: u64,
     pub query: String,
+    /// Settings sent with the query, e.g. `max_block_size`.
+    pub settings: Settings,
+    /// Values of the `{name:Type}` parameters of the query text, as quoted
+    /// literals. Sent by clients from revision 54459.
+    pub parameters: Settings,
+    /// Log lines at or above this priority are streamed to the client.
+    pub send_logs_level: LogsLevel,
     pub is_cancelled: bool,
     pub is_connection_closed: bool,
-    /// empty or not
+    /// The query text is empty or only whitespace, as some clients send to keep
+    /// the connection alive. It is answered with `EndOfStream` alone, without
+    /// calling `execute_query`.
     pub is_empty: bool,
+    /// Temporary tables the client sent after the query as named data blocks,
+    /// by table name. They are dropped once the query is answered.
+    pub external_tables: HashMap<String, Vec<Block>>,
+    /// Scalars the client sent after the query, the results of its subqueries
+    /// by name. They are dropped with the temporary tables.
+    pub scalars: HashMap<String, Block>,
 
     /// Data was sent.
     pub sent_all_data: Arc<Notify>,
-    pub out: Option<Sender<Block>>
+    pub out: Option<Sender<Block>>,
+
+    /// Fired when the client sends `Cancel` for the running query.
+    pub cancellation_token: CancellationToken,
+    /// Held while the query runs on a server with a `ConcurrencyLimiter`.
+    pub(crate) permit: Option<OwnedSemaphorePermit>
 }
 
 impl QueryState {
+    /// Whether the query is an INSERT that is still receiving data blocks.
+    pub fn is_inserting(&self) -> bool {
+        matches!(self.stage, Stage::InsertPrepare | Stage::InsertStarted)
+    }
+
     fn reset(&mut self) {
         self.stage = Stage::Default;
         self.is_cancelled = false;
         self.is_connection_closed = false;
         self.is_empty = false;
+        self.external_tables.clear();
+        self.scalars.clear();
         self.out = None;
+        self.permit = None;
     }
 }
 
 pub struct CHContext {
     pub state: QueryState,
 
+    /// Revision negotiated in `Hello`, 0 before it. Pass it wherever a packet is
+    /// encoded for the client.
     pub client_revision: u64,
-    pub hello: Option<HelloRequest>
+    pub hello: Option<HelloRequest>,
+    /// What `Hello` settled on, `None` before it.
+    pub handshake: Option<HandshakeResult>,
+    /// Quota key of the `Hello` addendum, empty for older clients.
+    pub quota_key: String,
+    /// Client info of the last `Query` packet.
+    pub client_info: ClientInfo,
+
+    /// Server shutdown, the cancellation token of every query is a child of it.
+    pub(crate) shutdown: CancellationToken,
+    /// Address of the connected client, `None` for transports without one such
+    /// as Unix sockets or in-memory streams.
+    pub client_addr: Option<SocketAddr>,
+    pub(crate) max_block_size: Option<usize>,
+    pub(crate) max_query_size: Option<usize>,
+    pub(crate) max_string_size: Option<usize>,
+    pub(crate) max_decompressed_size: Option<usize>,
+    pub(crate) max_execution_time: Option<Duration>,
+    pub(crate) min_accepted_revision: Option<u64>,
+    pub(crate) concurrency_limiter: Option<ConcurrencyLimiter>
 }
 
 impl CHContext {
@@ -101,48 +230,326 @@ impl CHContext {
         Self {
             state,
             client_revision: 0,
-            hello: None
+            hello: None,
+            handshake: None,
+            quota_key: String::new(),
+            client_info: ClientInfo::default(),
+            shutdown: CancellationToken::new(),
+            client_addr: None,
+            max_block_size: None,
+            max_query_size: None,
+            max_string_size: None,
+            max_decompressed_size: None,
+            max_execution_time: None,
+            min_accepted_revision: None,
+            concurrency_limiter: None
         }
     }
+    /// OpenTelemetry context the client sent with the last query, to continue
+    /// its trace. Only clients at `DBMS_MIN_REVISION_WITH_OPENTELEMETRY` or
+    /// later send one.
+    pub fn trace_context(&self) -> Option<&TraceContext> {
+        self.client_info.trace_context.as_ref()
+    }
+}
+
+/// The parameters of a connection negotiated by the `Hello` exchange.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HandshakeResult {
+    /// Revision both sides speak, the lower of the two.
+    pub client_revision: u64,
+    /// Revision the server advertised.
+    pub server_revision: u64,
+    pub client_name: String,
+    pub client_version_major: u64,
+    pub client_version_minor: u64,
+    pub user: String,
+    pub default_database: String,
+    /// Server timezone sent to the client, values of `DateTime` columns
+    /// without their own timezone are read in it.
+    pub timezone: String,
+    pub server_display_name: String,
+    /// Quota key of the addendum, empty for older clients.
+    pub quota_key: String
 }
 
 /// A server that speaks the ClickHouseprotocol, and can delegate client commands to a backend
 /// that implements [`ClickHouseSession`]
-pub struct ClickHouseServer {}
+///
+/// The associated `run_on*` functions serve with the default configuration, a
+/// server made by [`ClickHouseServer::builder`] serves with its own.
+#[derive(Clone, Default)]
+pub struct ClickHouseServer {
+    shutdown: CancellationToken,
+    read_timeout: Option<Duration>,
+    max_block_size: Option<usize>,
+    max_query_size: Option<usize>,
+    max_string_size: Option<usize>,
+    max_decompressed_size: Option<usize>,
+    max_execution_time: Option<Duration>,
+    min_accepted_revision: Option<u64>,
+    concurrency_limiter: Option<ConcurrencyLimiter>
+}
+
+/// Caps the number of queries running at once over every connection of the
+/// servers that share it. Clones share the same limit.
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimiter {
+    max_queries: usize,
+    permits: Arc<Semaphore>
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(max_queries: usize) -> Self {
+        Self {
+            max_queries,
+            permits: Arc::new(Semaphore::new(max_queries))
+        }
+    }
+
+    /// Queries running now.
+    pub fn running(&self) -> usize {
+        self.max_queries - self.permits.available_permits()
+    }
+
+    // A query over the limit fails at once instead of waiting for a slot.
+    pub(crate) fn try_acquire(&self) -> Result<OwnedSemaphorePermit> {
+        self.permits.clone().try_acquire_owned().map_err(|_| {
+            Error::Server(ServerError {
+                name: "TOO_MANY_SIMULTANEOUS_QUERIES".to_string(),
+                code: error_codes::TOO_MANY_SIMULTANEOUS_QUERIES,
+                message: format!("Too many simultaneous queries. Maximum: {}", self.max_queries),
+                stack_trace: "".to_string()
+            })
+        })
+    }
+}
+
+/// Configures a [`ClickHouseServer`], every option is off by default.
+#[derive(Default)]
+pub struct ClickHouseServerBuilder {
+    server: ClickHouseServer
+}
+
+impl ClickHouseServerBuilder {
+    /// Closes the connections once `shutdown` is cancelled. A running query is
+    /// cancelled as well, then the client gets `EndOfStream`.
+    pub fn shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.server.shutdown = shutdown;
+        self
+    }
+
+    /// Closes a connection with a `SOCKET_TIMEOUT` exception when the client
+    /// sends no packet for `timeout`.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.server.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Rows per [`BlockSink`](connection::BlockSink) block for queries that do
+    /// not set `max_block_size` themselves.
+    pub fn max_block_size(mut self, max_block_size: usize) -> Self {
+        self.server.max_block_size = Some(max_block_size);
+        self
+    }
+
+    /// Closes a connection with a `TOO_LARGE_STRING_SIZE` exception when the
+    /// client sends query text over `max_query_size` bytes, instead of the
+    /// default [`DEFAULT_MAX_QUERY_SIZE`](protocols::DEFAULT_MAX_QUERY_SIZE).
+    pub fn max_query_size(mut self, max_query_size: usize) -> Self {
+        self.server.max_query_size = Some(max_query_size);
+        self
+    }
+
+    /// Closes a connection with a `TOO_LARGE_STRING_SIZE` exception when the
+    /// client sends a `String` value over `max_string_size` bytes in a data
+    /// block, or a query id over it. The length comes before the value, so at
+    /// most this much of it is ever buffered. Off by default, then values are
+    /// limited to 16 MiB.
+    pub fn max_string_size(mut self, max_string_size: usize) -> Self {
+        self.server.max_string_size = Some(max_string_size);
+        self
+    }
+
+    /// Closes a connection with a `TOO_LARGE_STRING_SIZE` exception when a
+    /// compressed block of the client announces more than `max_decompressed_size`
+    /// bytes of data, 128 MiB by default. No buffer is allocated for such a block.
+    pub fn max_decompressed_size(mut self, max_decompressed_size: usize) -> Self {
+        self.server.max_decompressed_size = Some(max_decompressed_size);
+        self
+    }
+
+    /// Cancels a query still running after `timeout` and sends the client a
+    /// `TIMEOUT_EXCEEDED` exception, the connection stays open for the next
+    /// query. A query may ask for less with its own `max_execution_time`
+    /// setting, never for more.
+    pub fn max_execution_time(mut self, timeout: Duration) -> Self {
+        self.server.max_execution_time = Some(timeout);
+        self
+    }
+
+    /// Refuses the `Hello` of a client older than `revision` with an
+    /// `UNKNOWN_PROTOCOL` exception, before it is authenticated. Such a client
+    /// could otherwise claim an old revision to skip what newer ones must send,
+    /// e.g. the `Hello` addendum. Off by default, then every client is served.
+    pub fn min_accepted_revision(mut self, revision: u64) -> Self {
+        self.server.min_accepted_revision = Some(revision);
+        self
+    }
+
+    /// Answers a query with a `TOO_MANY_SIMULTANEOUS_QUERIES` exception instead
+    /// of running it while `limiter` is full. An INSERT holds its slot until
+    /// its last data block.
+    pub fn concurrency_limiter(mut self, limiter: ConcurrencyLimiter) -> Self {
+        self.server.concurrency_limiter = Some(limiter);
+        self
+    }
+
+    pub fn build(self) -> ClickHouseServer {
+        self.server
+    }
+}
 
 impl ClickHouseServer {
+    pub fn builder() -> ClickHouseServerBuilder {
+        ClickHouseServerBuilder::default()
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    /// Accepts clients until the server is shut down, serving each one on its own
+    /// task. A connection that fails, or whose session panics, is logged and
+    /// dropped without affecting the others.
+    pub async fn serve(
+        &self,
+        listener: TcpListener,
+        session: Arc<dyn ClickHouseSession>
+    ) -> Result<()> {
+        loop {
+            let accepted = tokio::select! {
+                biased;
+                _ = self.shutdown.cancelled() => return Ok(()),
+                res = listener.accept() => res,
+            };
+
+            let (stream, addr) = match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    error!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+
+            let server = self.clone();
+            let session = session.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.serve_tcp_stream(session, stream).await {
+                    error!("Connection from {} failed: {}", addr, e);
+                }
+            });
+        }
+    }
+
+    /// Serves a client connected over TCP until it disconnects or the server is
+    /// shut down.
+    pub async fn serve_tcp_stream(
+        &self,
+        session: Arc<dyn ClickHouseSession>,
+        stream: TcpStream
+    ) -> Result<()> {
+        let client_addr = stream.peer_addr().ok();
+        self.run(session, stream, client_addr).await
+    }
+
+    /// Serves a client over any bidirectional stream, the client address is
+    /// unknown to the session.
+    pub async fn serve_stream<S>(
+        &self,
+        session: Arc<dyn ClickHouseSession>,
+        stream: S
+    ) -> Result<()>
+    where S: AsyncRead + AsyncWrite + Unpin + Send + 'static {
+        self.run(session, stream, None).await
+    }
+
     pub async fn run_on_stream(
         session: Arc<dyn ClickHouseSession>,
         stream: TcpStream
     ) -> Result<()> {
-        ClickHouseServer::run_on(session, stream.into()).await
+        ClickHouseServer::default().serve_tcp_stream(session, stream).await
+    }
+
+    /// Serves a client connected to the secure native port, the TLS handshake
+    /// must already be completed.
+    #[cfg(feature = "tls")]
+    pub async fn run_on_tls_stream(
+        session: Arc<dyn ClickHouseSession>,
+        stream: tokio_native_tls::TlsStream<TcpStream>
+    ) -> Result<()> {
+        let client_addr = stream.get_ref().get_ref().get_ref().peer_addr().ok();
+        ClickHouseServer::default().run(session, stream, client_addr).await
+    }
+
+    #[cfg(unix)]
+    pub async fn run_on_unix_stream(
+        session: Arc<dyn ClickHouseSession>,
+        stream: UnixStream
+    ) -> Result<()> {
+        ClickHouseServer::run_on(session, stream).await
     }
 }
 
 impl ClickHouseServer {
-    async fn run_on(session: Arc<dyn ClickHouseSession>, stream: TcpStream) -> Result<()> {
-        let mut srv = ClickHouseServer {};
-        srv.run(session, stream).await?;
-        Ok(())
+    /// Serves a client over any bidirectional stream, e.g. an in-memory
+    /// `tokio::io::duplex` pipe.
+    pub async fn run_on<S>(session: Arc<dyn ClickHouseSession>, stream: S) -> Result<()>
+    where S: AsyncRead + AsyncWrite + Unpin + Send + 'static {
+        ClickHouseServer::default().serve_stream(session, stream).await
     }
 
-    async fn run(&mut self, session: Arc<dyn ClickHouseSession>, stream: TcpStream) -> Result<()> {
+    async fn run<S>(
+        &self,
+        session: Arc<dyn ClickHouseSession>,
+        stream: S,
+        client_addr: Option<SocketAddr>
+    ) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static
+    {
         debug!("Handle New session");
         let tz = session.timezone().to_string();
         let mut ctx = CHContext::new(QueryState::default());
+        ctx.shutdown = self.shutdown.clone();
+        ctx.client_addr = client_addr;
+        ctx.max_block_size = self.max_block_size;
+        ctx.max_query_size = self.max_query_size;
+        ctx.max_string_size = self.max_string_size;
+        ctx.max_decompressed_size = self.max_decompressed_size;
+        ctx.max_execution_time = self.max_execution_time;
+        ctx.min_accepted_revision = self.min_accepted_revision;
+        ctx.concurrency_limiter = self.concurrency_limiter.clone();
         let mut connection = Connection::new(stream, session, tz)?;
 
         loop {
             // signal.
             let maybe_packet = tokio::select! {
-               res = connection.read_packet(&mut ctx) => res,
+                biased;
+                _ = self.shutdown.cancelled() => {
+                    debug!("{:?}", "shutdown");
+                    ctx.state.reset();
+                    connection.write_end_of_stream().await?;
+                    return Ok(());
+                }
+                res = self.read_packet(&mut connection, &mut ctx) => res,
             };
 
             let packet = match maybe_packet {
                 Ok(Some(packet)) => packet,
                 Err(e) => {
                     ctx.state.reset();
-                    connection.write_error(&e).await?;
+                    connection.write_exception(&e).await?;
                     return Err(e);
                 }
                 Ok(None) => {
@@ -152,11 +559,65 @@ impl ClickHouseServer {
                 }
             };
             let cmd = Cmd::create(packet);
-            cmd.apply(&mut connection, &mut ctx).await?;
+            // A panicking session is reported to its client like any other error
+            // instead of dropping the socket.
+            let res = AssertUnwindSafe(cmd.apply(&mut connection, &mut ctx))
+                .catch_unwind()
+                .await
+                .unwrap_or_else(|payload| Err(panic_error(payload)));
+            if let Err(e) = res {
+                ctx.state.reset();
+                connection.write_exception(&e).await?;
+                return Err(e);
+            }
+
+            // The query has already been answered with its own `EndOfStream`.
+            if self.shutdown.is_cancelled() && !ctx.state.is_inserting() {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn read_packet(
+        &self,
+        connection: &mut Connection,
+        ctx: &mut CHContext
+    ) -> Result<Option<Packet>> {
+        let timeout = match self.read_timeout {
+            Some(timeout) => timeout,
+            None => return connection.read_packet(ctx).await
+        };
+
+        match tokio::time::timeout(timeout, connection.read_packet(ctx)).await {
+            Ok(res) => res,
+            Err(_) => Err(Error::Server(ServerError {
+                name: "SOCKET_TIMEOUT".to_string(),
+                code: error_codes::SOCKET_TIMEOUT,
+                message: format!("Timeout exceeded while reading from client ({:?})", timeout),
+                stack_trace: "".to_string()
+            }))
         }
     }
 }
 
+fn panic_error(payload: Box<dyn Any + Send>) -> Error {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    };
+
+    error!("Session panicked: {}", message);
+    Error::Server(ServerError {
+        name: "UNKNOWN_EXCEPTION".to_string(),
+        code: error_codes::UNKNOWN_EXCEPTION,
+        message: format!("Session panicked: {}", message),
+        stack_trace: "".to_string()
+    })
+}
+
 #[macro_export]
 macro_rules! row {
     () => { $crate::types::RNil };
@@ -184,8 +645,2343 @@ macro_rules! row {
 
 #[cfg(test)]
 mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::binary::Encoder;
+    use crate::binary::ReadEx;
+    use crate::connection::CompressionMethod;
+    use crate::connection::ConnectionStats;
+    use crate::errors::DriverError;
+    use crate::protocols::ExceptionResponse;
+    use crate::protocols::CLIENT_CANCEL;
+    use crate::protocols::CLIENT_DATA;
+    use crate::protocols::CLIENT_HELLO;
+    use crate::protocols::CLIENT_PING;
+    use crate::protocols::CLIENT_QUERY;
+    use crate::protocols::CLIENT_SCALAR;
+    use crate::protocols::SERVER_DATA;
+    use crate::protocols::SERVER_END_OF_STREAM;
+    use crate::protocols::SERVER_EXCEPTION;
+    use crate::protocols::SERVER_HELLO;
+    use crate::protocols::SERVER_LOG;
+    use crate::protocols::SERVER_PONG;
+    use crate::protocols::SERVER_TOTALS;
+    use crate::testing::requests::hello_request_at;
+    use crate::testing::requests::query_packet;
+    use crate::testing::requests::query_request_at;
+    use crate::testing::CollectInsertSession;
+    use crate::types::column::SerializationSettings;
+
+    struct TestSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for TestSession {
+        async fn execute_query(&self, _: &mut CHContext, _: &mut Connection) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    struct CancelSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for CancelSession {
+        async fn execute_query(
+            &self,
+            ctx: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            let block = Block::new().column("a", vec![1_u32]);
+            while !connection.poll_cancel()? {
+                connection.write_block(&block).await?;
+                tokio::task::yield_now().await;
+            }
+            ctx.state.cancellation_token.cancelled().await;
+            Ok(())
+        }
+    }
+
+    struct StreamSession {}
+
+    /// A `StreamSession` whose clients send their settings as strings.
+    struct StringSettingsSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for StringSettingsSession {
+        async fn execute_query(
+            &self,
+            ctx: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            StreamSession {}.execute_query(ctx, connection).await
+        }
+
+        fn dbms_tcp_protocol_version(&self) -> u64 {
+            protocols::DBMS_MIN_REVISION_WITH_SETTINGS_SERIALIZED_AS_STRINGS
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for StreamSession {
+        async fn execute_query(
+            &self,
+            ctx: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            let mut sink = connection.block_sink(ctx);
+            for i in 0..3 {
+                sink.push(&stream_block(i)).await?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Sends the header itself, then the rows of `i > 0` only.
+    struct HeaderSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for HeaderSession {
+        async fn execute_query(
+            &self,
+            ctx: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            let mut sink = connection.block_sink(ctx);
+            sink.write_header(&stream_block(0)).await?;
+            sink.push(&stream_block(1)).await
+        }
+    }
+
+    /// Ends the result after its first block and counts the pushes refused after.
+    #[derive(Default)]
+    struct FinishSession {
+        rejected: std::sync::atomic::AtomicUsize
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for FinishSession {
+        async fn execute_query(
+            &self,
+            ctx: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            let mut sink = connection.block_sink(ctx);
+            sink.push(&stream_block(0)).await?;
+            sink.finish().await?;
+            if let Err(Error::Driver(DriverError::StreamFinished)) =
+                sink.push(&stream_block(1)).await
+            {
+                self.rejected.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+            // Fails with `StreamFinished` too, which the client must not see.
+            sink.finish().await
+        }
+    }
+
+    /// Runs each query until the test releases it.
+    #[derive(Default)]
+    struct GateSession {
+        started: Notify,
+        release: Notify
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for GateSession {
+        async fn execute_query(&self, _: &mut CHContext, _: &mut Connection) -> Result<()> {
+            self.started.notify_one();
+            self.release.notified().await;
+            Ok(())
+        }
+    }
+
+    /// Pushes large blocks and records how far it got ahead of the client.
+    #[derive(Default)]
+    struct BackpressureSession {
+        client_read: Arc<std::sync::atomic::AtomicUsize>,
+        max_ahead: std::sync::Mutex<usize>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for BackpressureSession {
+        async fn execute_query(
+            &self,
+            ctx: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            let stats = connection.stats();
+            let mut sink = connection.block_sink(ctx);
+            for i in 0..32 {
+                sink.push(&Block::new().column("a", vec![i; 256])).await?;
+
+                let read = self.client_read.load(std::sync::atomic::Ordering::SeqCst);
+                let ahead = stats.bytes_written() as usize - read;
+                let mut max_ahead = self.max_ahead.lock().unwrap();
+                *max_ahead = (*max_ahead).max(ahead);
+            }
+            Ok(())
+        }
+    }
+
+    struct SleepSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for SleepSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            if ctx.state.query.contains("sleep") {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            Ok(())
+        }
+    }
+
+    struct LargeBlockSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for LargeBlockSession {
+        async fn execute_query(
+            &self,
+            _: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            let block = Block::new().column("a", vec![7_u32; 1 << 16]);
+            connection.write_block(&block).await
+        }
+    }
+
+    struct ManyBlocksSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for ManyBlocksSession {
+        async fn execute_query(
+            &self,
+            _: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            for i in 0..1000 {
+                connection.write_block(&stream_block(i)).await?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Counts the writes that reach the transport.
+    struct CountingStream {
+        inner: tokio::io::DuplexStream,
+        writes: Arc<std::sync::atomic::AtomicUsize>
+    }
+
+    impl AsyncRead for CountingStream {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::pin::Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for CountingStream {
+        fn poll_write(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &[u8]
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            self.writes.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            std::pin::Pin::new(&mut self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_flush(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::pin::Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::pin::Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    #[derive(Default)]
+    struct StatsSession {
+        stats: std::sync::Mutex<Option<Arc<ConnectionStats>>>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for StatsSession {
+        async fn execute_query(
+            &self,
+            ctx: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            *self.stats.lock().unwrap() = Some(connection.stats());
+            StreamSession {}.execute_query(ctx, connection).await
+        }
+    }
+
+    fn stream_block(i: u32) -> Block {
+        Block::new().column("a", vec![i; 2])
+    }
+
+    /// The header a `BlockSink` sends ahead of the first block of a result.
+    fn header_response(encoder: &mut Encoder, schema: &Block, compress: bool) {
+        encoder.uvarint(SERVER_DATA);
+        encoder.string("");
+        schema.slice(0, 0).write(encoder, compress);
+    }
+
+    struct TotalsSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for TotalsSession {
+        async fn execute_query(
+            &self,
+            _: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            connection.write_totals(&totals_block()).await
+        }
+    }
+
+    struct ProgressSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for ProgressSession {
+        async fn execute_query(&self, _: &mut CHContext, _: &mut Connection) -> Result<()> {
+            Ok(())
+        }
+
+        fn get_progress(&self) -> Progress {
+            progress()
+        }
+    }
+
+    fn progress() -> Progress {
+        Progress {
+            rows: 250,
+            bytes: 2000,
+            total_rows: 1000,
+            ..Progress::default()
+        }
+    }
+
+    struct WarningSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for WarningSession {
+        async fn execute_query(
+            &self,
+            ctx: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            connection
+                .write_warning("GLOBAL IN is deprecated", ctx.client_revision)
+                .await
+        }
+    }
+
+    struct RejectSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for RejectSession {
+        async fn execute_query(&self, _: &mut CHContext, _: &mut Connection) -> Result<()> {
+            Ok(())
+        }
+
+        async fn authenticate(&self, user: &str, _: &str, _: Option<SocketAddr>) -> Result<bool> {
+            Ok(user != "default")
+        }
+    }
+
+    #[derive(Default)]
+    struct AddrSession {
+        client_addr: std::sync::Mutex<Option<SocketAddr>>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for AddrSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            *self.client_addr.lock().unwrap() = ctx.client_addr;
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct QueryIdSession {
+        query_ids: std::sync::Mutex<Vec<String>>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for QueryIdSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            self.query_ids.lock().unwrap().push(ctx.state.query_id.clone());
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct ExternalTablesSession {
+        tables: std::sync::Mutex<Vec<HashMap<String, Vec<Block>>>>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for ExternalTablesSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            let tables = ctx.state.external_tables.clone();
+            self.tables.lock().unwrap().push(tables);
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct ScalarsSession {
+        scalars: std::sync::Mutex<Vec<HashMap<String, Block>>>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for ScalarsSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            self.scalars.lock().unwrap().push(ctx.state.scalars.clone());
+            Ok(())
+        }
+
+        fn dbms_tcp_protocol_version(&self) -> u64 {
+            protocols::DBMS_MIN_REVISION_WITH_SCALARS
+        }
+    }
+
+    struct VersionSession {
+        display_name: String,
+        version: (u64, u64, u64)
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for VersionSession {
+        async fn execute_query(&self, _: &mut CHContext, _: &mut Connection) -> Result<()> {
+            Ok(())
+        }
+
+        fn dbms_version_major(&self) -> u64 {
+            self.version.0
+        }
+
+        fn dbms_version_minor(&self) -> u64 {
+            self.version.1
+        }
+
+        fn server_display_name(&self) -> &str {
+            &self.display_name
+        }
+
+        fn dbms_version_patch(&self) -> u64 {
+            self.version.2
+        }
+    }
+
+    #[derive(Default)]
+    struct StageSession {
+        stage: std::sync::Mutex<Option<QueryProcessingStage>>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for StageSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            *self.stage.lock().unwrap() = Some(ctx.state.processing_stage);
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct RevisionSession {
+        client_revision: std::sync::Mutex<u64>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for RevisionSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            *self.client_revision.lock().unwrap() = ctx.client_revision;
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct HandshakeSession {
+        handshake: std::sync::Mutex<Option<HandshakeResult>>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for HandshakeSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            *self.handshake.lock().unwrap() = ctx.handshake.clone();
+            Ok(())
+        }
+
+        fn timezone(&self) -> &str {
+            "Asia/Shanghai"
+        }
+    }
+
+    #[derive(Default)]
+    struct QuotaSession {
+        quota_key: std::sync::Mutex<String>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for QuotaSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            *self.quota_key.lock().unwrap() = ctx.quota_key.clone();
+            Ok(())
+        }
+
+        fn dbms_tcp_protocol_version(&self) -> u64 {
+            protocols::DBMS_MIN_PROTOCOL_VERSION_WITH_QUOTA_KEY
+        }
+    }
+
+    #[derive(Default)]
+    struct InterserverSession {
+        initial_user: std::sync::Mutex<String>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for InterserverSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            *self.initial_user.lock().unwrap() = ctx.client_info.initial_user.clone();
+            Ok(())
+        }
+
+        fn dbms_tcp_protocol_version(&self) -> u64 {
+            protocols::DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET
+        }
+
+        fn interserver_secret(&self, cluster: &str) -> Option<&str> {
+            match cluster {
+                "test_cluster" => Some("cluster-secret"),
+                _ => None
+            }
+        }
+    }
+
+    struct SecretSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for SecretSession {
+        async fn execute_query(&self, _: &mut CHContext, _: &mut Connection) -> Result<()> {
+            Ok(())
+        }
+
+        fn interserver_secret(&self, _cluster: &str) -> Option<&str> {
+            Some("cluster-secret")
+        }
+    }
+
+    #[derive(Default)]
+    struct ParametersSession {
+        client_revision: std::sync::Mutex<u64>,
+        parameters: std::sync::Mutex<Settings>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for ParametersSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            *self.client_revision.lock().unwrap() = ctx.client_revision;
+            *self.parameters.lock().unwrap() = ctx.state.parameters.clone();
+            Ok(())
+        }
+
+        fn dbms_tcp_protocol_version(&self) -> u64 {
+            54470
+        }
+    }
+
+    struct ErrorSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for ErrorSession {
+        async fn execute_query(&self, _: &mut CHContext, _: &mut Connection) -> Result<()> {
+            Err(query_error())
+        }
+    }
+
+    fn query_error() -> Error {
+        Error::Server(ServerError {
+            code: error_codes::UNKNOWN_TABLE,
+            name: "DB::Exception".to_string(),
+            message: "Table default.t doesn't exist".to_string(),
+            stack_trace: "".to_string()
+        })
+    }
+
+    struct PanicSession {}
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for PanicSession {
+        async fn execute_query(&self, ctx: &mut CHContext, _: &mut Connection) -> Result<()> {
+            if ctx.state.query == "SELECT panic" {
+                panic!("boom");
+            }
+            Ok(())
+        }
+    }
+
+    /// Unlike `testing::CollectInsertSession`, also keeps the empty block that
+    /// ends the insert.
+    #[derive(Default)]
+    struct InsertSession {
+        blocks: std::sync::Mutex<Vec<Block>>
+    }
+
+    #[async_trait::async_trait]
+    impl ClickHouseSession for InsertSession {
+        async fn execute_query(
+            &self,
+            ctx: &mut CHContext,
+            connection: &mut Connection
+        ) -> Result<()> {
+            connection.start_insert(ctx, &insert_header()).await
+        }
+
+        async fn on_insert_block(&self, ctx: &mut CHContext, block: Block) -> Result<()> {
+            assert!(ctx.state.is_inserting());
+            self.blocks.lock().unwrap().push(block);
+            Ok(())
+        }
+    }
+
+    fn insert_header() -> Block {
+        Block::new().column("a", Vec::<u32>::new())
+    }
+
+    fn totals_block() -> Block {
+        Block::new().column("count", vec![42_u64])
+    }
+
+    #[test]
+    fn it_works() {
+        assert_eq!(2 + 2, 4);
+    }
+
+    /// Revision of the client in `hello_request`, below the one of the server.
+    const CLIENT_REVISION: u64 = 54405;
+
+    fn hello_request(encoder: &mut Encoder) {
+        hello_request_at(encoder, CLIENT_REVISION);
+    }
+
+    fn hello_response(encoder: &mut Encoder) {
+        encoder.uvarint(SERVER_HELLO);
+        encoder.string("clickhouse-server");
+        encoder.uvarint(19);
+        encoder.uvarint(17);
+        encoder.uvarint(54428);
+        encoder.string("UTC");
+        encoder.string("clickhouse-server");
+        encoder.uvarint(1);
+    }
+
+    fn query_request(encoder: &mut Encoder, query: &str) {
+        query_request_with_id(encoder, "", query);
+    }
+
+    fn query_request_with_id(encoder: &mut Encoder, query_id: &str, query: &str) {
+        query_request_with_compression(encoder, query_id, 0, query);
+    }
+
+    fn query_request_with_compression(
+        encoder: &mut Encoder,
+        query_id: &str,
+        compression: u64,
+        query: &str
+    ) {
+        query_packet(encoder, CLIENT_REVISION, query_id, &[], compression, query);
+        // Clients end every query with its temporary tables, none here.
+        Block::new().send_client_data(encoder, compression != 0).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hello_over_duplex() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(TestSession {}), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_query() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(CancelSession {}), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT a FROM t");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut data = vec![0_u8; 1];
+        client.read_exact(&mut data).await.unwrap();
+        client.write_all(&[CLIENT_CANCEL as u8]).await.unwrap();
+
+        // Blocks sent before the cancellation are followed by the end of stream.
+        let mut received = Vec::new();
+        let eos = SERVER_END_OF_STREAM as u8;
+        while received.last() != Some(&eos) {
+            let mut buf = vec![0_u8; 1024];
+            let n = client.read(&mut buf).await.unwrap();
+            assert_ne!(n, 0);
+            received.extend_from_slice(&buf[..n]);
+        }
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_block_sink() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(StreamSession {}), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT a FROM t");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        header_response(&mut expected, &stream_block(0), false);
+        for i in 0..3 {
+            expected.uvarint(SERVER_DATA);
+            expected.string("");
+            stream_block(i).write(&mut expected, false);
+        }
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_block_sink_write_header() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(HeaderSession {}), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT a FROM t WHERE i > 0");
+        client.write_all(&request.get_buffer()).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        // The header is sent once, ahead of the data.
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        header_response(&mut expected, &stream_block(0), false);
+        expected.uvarint(SERVER_DATA);
+        expected.string("");
+        stream_block(1).write(&mut expected, false);
+        expected.uvarint(SERVER_END_OF_STREAM);
+
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        handle.await.unwrap().unwrap();
+        assert_eq!(actual, expected.get_buffer());
+    }
+
+    #[tokio::test]
+    async fn test_block_sink_finish() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let session = Arc::new(FinishSession::default());
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT a FROM t LIMIT 2");
+        query_request(&mut request, "SELECT a FROM t LIMIT 2");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        // Each query ends once, where the session finished it.
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        for _ in 0..2 {
+            header_response(&mut expected, &stream_block(0), false);
+            expected.uvarint(SERVER_DATA);
+            expected.string("");
+            stream_block(0).write(&mut expected, false);
+            expected.uvarint(SERVER_END_OF_STREAM);
+        }
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        client.shutdown().await.unwrap();
+        let mut rest = Vec::new();
+        client.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+        handle.await.unwrap().unwrap();
+
+        assert_eq!(session.rejected.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_network_compression_method() {
+        for (setting, method) in &[
+            (Some("ZSTD"), CompressionMethod::ZSTD),
+            (Some("lz4"), CompressionMethod::LZ4),
+            (None, CompressionMethod::LZ4),
+        ] {
+            let (mut client, server) = tokio::io::duplex(4096);
+            let session = Arc::new(StreamSession {});
+            let handle = tokio::spawn(ClickHouseServer::run_on(session, server));
+
+            let mut request = Encoder::new();
+            hello_request(&mut request);
+            let settings: Vec<_> = setting
+                .iter()
+                .map(|method| ("network_compression_method", *method))
+                .collect();
+            let query = "SELECT a FROM t";
+            query_packet(&mut request, CLIENT_REVISION, "", &settings, 1, query);
+            Block::new().send_client_data(&mut request, true).unwrap();
+            client.write_all(&request.get_buffer()).await.unwrap();
+
+            let mut expected = Encoder::new();
+            hello_response(&mut expected);
+            expected.uvarint(SERVER_DATA);
+            expected.string("");
+            stream_block(0)
+                .slice(0, 0)
+                .write_compressed(&mut expected, *method, Default::default());
+            for i in 0..3 {
+                expected.uvarint(SERVER_DATA);
+                expected.string("");
+                stream_block(i).write_compressed(&mut expected, *method, Default::default());
+            }
+            expected.uvarint(SERVER_END_OF_STREAM);
+            let expected = expected.get_buffer();
+
+            let mut actual = vec![0_u8; expected.len()];
+            client.read_exact(&mut actual).await.unwrap();
+            assert_eq!(actual, expected);
+
+            let mut response = Encoder::new();
+            hello_response(&mut response);
+            // The packet type, the empty table name and the checksum precede it.
+            let method_byte = actual[response.get_buffer_ref().len() + 2 + 16];
+            let expected_byte = match method {
+                CompressionMethod::ZSTD => 0x90,
+                _ => 0x82
+            };
+            assert_eq!(method_byte, expected_byte);
+
+            drop(client);
+            handle.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compression_per_query() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(StreamSession {}), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        for compression in &[1_u64, 0, 1] {
+            let query = "SELECT a FROM t";
+            query_request_with_compression(&mut request, "", *compression, query);
+        }
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        for compression in &[true, false, true] {
+            header_response(&mut expected, &stream_block(0), *compression);
+            for i in 0..3 {
+                expected.uvarint(SERVER_DATA);
+                expected.string("");
+                stream_block(i).write(&mut expected, *compression);
+            }
+            expected.uvarint(SERVER_END_OF_STREAM);
+        }
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_block_sink_backpressure() {
+        const CAPACITY: usize = 1024;
+        const CHUNK: usize = 256;
+
+        let session = Arc::new(BackpressureSession::default());
+        let (mut client, server) = tokio::io::duplex(CAPACITY);
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT a FROM t");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        let header = Block::new().column("a", Vec::<i32>::new());
+        header_response(&mut expected, &header, false);
+        for i in 0..32 {
+            expected.uvarint(SERVER_DATA);
+            expected.string("");
+            Block::new().column("a", vec![i; 256]).write(&mut expected, false);
+        }
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        // Reads slowly, the 32 KiB of blocks take over a hundred reads.
+        let mut actual = Vec::new();
+        let mut chunk = [0_u8; CHUNK];
+        while actual.len() < expected.len() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            let n = client.read(&mut chunk).await.unwrap();
+            actual.extend_from_slice(&chunk[..n]);
+            session
+                .client_read
+                .fetch_add(n, std::sync::atomic::Ordering::SeqCst);
+        }
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+
+        // Only what fits in the pipe, and the read the client has not counted
+        // yet, is ever in flight.
+        let max_ahead = *session.max_ahead.lock().unwrap();
+        assert!(max_ahead <= CAPACITY + CHUNK, "{} bytes ahead", max_ahead);
+    }
+
+    #[tokio::test]
+    async fn test_connection_stats() {
+        let session = Arc::new(StatsSession::default());
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT a FROM t");
+        let request = request.get_buffer();
+        client.write_all(&request).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        handle.await.unwrap().unwrap();
+
+        let stats = session.stats.lock().unwrap().take().unwrap();
+        assert_eq!(stats.bytes_read(), request.len() as u64);
+        assert_eq!(stats.bytes_written(), actual.len() as u64);
+        // `Hello`, the query and the empty block ending its temporary tables.
+        assert_eq!(stats.packets_read(), 3);
+        // The header of the result and its three blocks.
+        assert_eq!(stats.blocks_sent(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_buffered_writes() {
+        let (mut client, server) = tokio::io::duplex(1 << 20);
+        let writes = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let server = CountingStream {
+            inner: server,
+            writes: writes.clone()
+        };
+        let session = Arc::new(ManyBlocksSession {});
+        let handle = tokio::spawn(ClickHouseServer::run_on(session, server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT a FROM t");
+        client.write_all(&request.get_buffer()).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        for i in 0..1000 {
+            expected.uvarint(SERVER_DATA);
+            expected.string("");
+            stream_block(i).write(&mut expected, false);
+        }
+        expected.uvarint(SERVER_END_OF_STREAM);
+
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        handle.await.unwrap().unwrap();
+        assert_eq!(actual, expected.get_buffer());
+
+        // One write per filled buffer rather than one per block.
+        let writes = writes.load(std::sync::atomic::Ordering::Relaxed);
+        assert!(writes < 100, "{} writes", writes);
+    }
+
+    #[tokio::test]
+    async fn test_write_totals() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(TotalsSession {}), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT count() FROM t WITH TOTALS");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        expected.uvarint(SERVER_TOTALS);
+        expected.string("");
+        totals_block().write(&mut expected, false);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_session_progress() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(ProgressSession {}), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT * FROM t");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        progress().write(&mut expected, 54405);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(TestSession {}), server));
+
+        let mut request = Encoder::new();
+        request.uvarint(CLIENT_PING);
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT 1");
+        request.uvarint(CLIENT_PING);
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        expected.uvarint(SERVER_PONG);
+        hello_response(&mut expected);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        expected.uvarint(SERVER_PONG);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_authentication_failed() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(RejectSession {}), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        assert_eq!(response[0], SERVER_EXCEPTION as u8);
+        assert_eq!(&response[1..5], &error_codes::AUTHENTICATION_FAILED.to_le_bytes());
+
+        let err = handle.await.unwrap().unwrap_err();
+        assert_eq!(err.code(), error_codes::AUTHENTICATION_FAILED);
+    }
+
+    #[tokio::test]
+    async fn test_min_accepted_revision() {
+        let srv = ClickHouseServer::builder().min_accepted_revision(54405).build();
+
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = {
+            let srv = srv.clone();
+            tokio::spawn(async move { srv.serve_stream(Arc::new(TestSession {}), server).await })
+        };
+        let mut request = Encoder::new();
+        hello_request_at(&mut request, 54300);
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        assert_eq!(response[0], SERVER_EXCEPTION as u8);
+        assert_eq!(&response[1..5], &error_codes::UNKNOWN_PROTOCOL.to_le_bytes());
+
+        let err = handle.await.unwrap().unwrap_err();
+        assert_eq!(err.code(), error_codes::UNKNOWN_PROTOCOL);
+        assert!(err.to_string().contains("54300"));
+
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle =
+            tokio::spawn(async move { srv.serve_stream(Arc::new(TestSession {}), server).await });
+        let mut request = Encoder::new();
+        hello_request_at(&mut request, 54405);
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut packet = [0_u8; 1];
+        client.read_exact(&mut packet).await.unwrap();
+        assert_eq!(packet[0], SERVER_HELLO as u8);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limiter() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let srv = ClickHouseServer::builder()
+            .concurrency_limiter(limiter.clone())
+            .build();
+        let session = Arc::new(GateSession::default());
+        let serve = |server| {
+            let srv = srv.clone();
+            let session = session.clone();
+            tokio::spawn(async move { srv.serve_stream(session, server).await })
+        };
+
+        let (mut slow, server) = tokio::io::duplex(4096);
+        let slow_handle = serve(server);
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT sleep(1)");
+        slow.write_all(&request.get_buffer()).await.unwrap();
+        session.started.notified().await;
+        assert_eq!(limiter.running(), 1);
+
+        let (mut rejected, server) = tokio::io::duplex(4096);
+        let rejected_handle = serve(server);
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT 1");
+        rejected.write_all(&request.get_buffer()).await.unwrap();
+
+        let err = limiter.try_acquire().unwrap_err();
+        assert_eq!(err.code(), error_codes::TOO_MANY_SIMULTANEOUS_QUERIES);
+        let mut hello = Encoder::new();
+        hello_response(&mut hello);
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        ExceptionResponse::write(&mut expected, &err, false);
+        let expected = expected.get_buffer();
+        let mut actual = vec![0_u8; expected.len()];
+        rejected.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        session.release.notify_one();
+        let mut expected = hello.get_buffer();
+        expected.push(SERVER_END_OF_STREAM as u8);
+        let mut actual = vec![0_u8; expected.len()];
+        slow.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+        assert_eq!(limiter.running(), 0);
+
+        drop(slow);
+        slow_handle.await.unwrap().unwrap();
+
+        // The rejected client may retry on the same connection once a slot is free.
+        let mut request = Encoder::new();
+        query_request(&mut request, "SELECT 1");
+        rejected.write_all(&request.get_buffer()).await.unwrap();
+        session.started.notified().await;
+        assert_eq!(limiter.running(), 1);
+        session.release.notify_one();
+
+        let mut packet = [0_u8; 1];
+        rejected.read_exact(&mut packet).await.unwrap();
+        assert_eq!(packet[0], SERVER_END_OF_STREAM as u8);
+        assert_eq!(limiter.running(), 0);
+
+        drop(rejected);
+        rejected_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_revision() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let session = Arc::new(RevisionSession::default());
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        // The client is newer than the server, so it must speak the server revision.
+        let mut request = Encoder::new();
+        hello_request_at(&mut request, 54460);
+        query_request(&mut request, "SELECT 1");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+        assert_eq!(
+            *session.client_revision.lock().unwrap(),
+            protocols::DBMS_TCP_PROTOCOL_VERSION
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handshake_result() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let session = Arc::new(HandshakeSession::default());
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        let mut request = Encoder::new();
+        hello_request_at(&mut request, 54460);
+        query_request(&mut request, "SELECT 1");
+        client.write_all(&request.get_buffer()).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        handle.await.unwrap().unwrap();
+
+        let handshake = session.handshake.lock().unwrap().take().unwrap();
+        assert_eq!(handshake.client_revision, protocols::DBMS_TCP_PROTOCOL_VERSION);
+        assert_eq!(handshake.server_revision, protocols::DBMS_TCP_PROTOCOL_VERSION);
+        assert_eq!(handshake.timezone, "Asia/Shanghai");
+        assert_eq!(handshake.server_display_name, "clickhouse-server");
+        assert_eq!(handshake.client_name, "clickhouse-client");
+        assert_eq!(handshake.user, "default");
+    }
+
+    #[tokio::test]
+    async fn test_hello_addendum() {
+        let revision = protocols::DBMS_MIN_PROTOCOL_VERSION_WITH_QUOTA_KEY;
+        let (mut client, server) = tokio::io::duplex(4096);
+        let session = Arc::new(QuotaSession::default());
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        let mut request = Encoder::new();
+        hello_request_at(&mut request, revision);
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        expected.uvarint(SERVER_HELLO);
+        expected.string("clickhouse-server");
+        expected.uvarint(19);
+        expected.uvarint(17);
+        expected.uvarint(revision);
+        expected.string("UTC");
+        expected.string("clickhouse-server");
+        expected.uvarint(1);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        let mut request = Encoder::new();
+        request.string("tenant-1"); // addendum
+        query_request_at(&mut request, revision, "SELECT 1");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut actual = [0_u8; 1];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual[0], SERVER_END_OF_STREAM as u8);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+        assert_eq!(*session.quota_key.lock().unwrap(), "tenant-1");
+    }
+
+    // The handshake of a shard of `test_cluster` forwarding a query, as
+    // `Connection::sendHello` and `Connection::sendQuery` lay it out.
+    fn interserver_request(encoder: &mut Encoder, salt: &[u8], hash: &[u8], query: &str) {
+        encoder.uvarint(CLIENT_HELLO);
+        encoder.string("ClickHouse server");
+        encoder.uvarint(21);
+        encoder.uvarint(8);
+        encoder.uvarint(protocols::DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET);
+        encoder.string("default");
+        encoder.string(protocols::USER_INTERSERVER_MARKER);
+        encoder.string(""); // password
+        encoder.string("test_cluster");
+        encoder.byte_string(salt);
+
+        encoder.uvarint(CLIENT_QUERY);
+        encoder.string("7e2c5d1a");
+        encoder.write(protocols::SECONDARY_QUERY);
+        encoder.string("alice"); // initial user
+        encoder.string("3b9f0c2e"); // initial query id
+        encoder.string("10.0.0.1:9000");
+        encoder.write(1_u8); // TCP
+        encoder.string("clickhouse");
+        encoder.string("shard-1");
+        encoder.string("ClickHouse server");
+        encoder.uvarint(21);
+        encoder.uvarint(8);
+        encoder.uvarint(protocols::DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET);
+        encoder.string(""); // quota key
+        encoder.uvarint(3); // version patch
+        encoder.string(""); // end of settings
+        encoder.byte_string(hash);
+        encoder.uvarint(2); // stage
+        encoder.uvarint(0); // compression
+        encoder.string(query);
+        Block::new().send_client_data(encoder, false).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_interserver_secret() {
+        let salt = [0x5a_u8; 32];
+        let query = "SELECT a FROM t";
+        let hash =
+            protocols::interserver_hash(&salt, "cluster-secret", query, "7e2c5d1a", "alice");
+        let mut wrong_hash = hash;
+        wrong_hash[0] ^= 1;
+
+        for (hash, accepted) in &[(hash, true), (wrong_hash, false)] {
+            let session = Arc::new(InterserverSession::default());
+            let (mut client, server) = tokio::io::duplex(4096);
+            let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+            let mut request = Encoder::new();
+            interserver_request(&mut request, &salt, hash, query);
+            client.write_all(&request.get_buffer()).await.unwrap();
+
+            let mut expected = Encoder::new();
+            expected.uvarint(SERVER_HELLO);
+            expected.string("clickhouse-server");
+            expected.uvarint(19);
+            expected.uvarint(17);
+            expected.uvarint(protocols::DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET);
+            expected.string("UTC");
+            expected.string("clickhouse-server");
+            expected.uvarint(1);
+
+            let err = Error::Server(ServerError {
+                name: "AUTHENTICATION_FAILED".to_string(),
+                code: error_codes::AUTHENTICATION_FAILED,
+                message: "Interserver authentication failed".to_string(),
+                stack_trace: "".to_string()
+            });
+            if *accepted {
+                expected.uvarint(SERVER_END_OF_STREAM);
+            } else {
+                ExceptionResponse::write(&mut expected, &err, false);
+            }
+            let expected = expected.get_buffer();
+
+            let mut actual = vec![0_u8; expected.len()];
+            client.read_exact(&mut actual).await.unwrap();
+            assert_eq!(actual, expected);
+
+            drop(client);
+            let res = handle.await.unwrap();
+            if *accepted {
+                res.unwrap();
+                assert_eq!(*session.initial_user.lock().unwrap(), "alice");
+            } else {
+                assert_eq!(res.unwrap_err().to_string(), err.to_string());
+                assert_eq!(*session.initial_user.lock().unwrap(), "");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interserver_without_secret() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(TestSession {}), server));
+
+        let mut request = Encoder::new();
+        interserver_request(&mut request, &[0x5a_u8; 32], &[0_u8; 32], "SELECT 1");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        assert_eq!(actual[0], SERVER_EXCEPTION as u8);
+
+        let err = handle.await.unwrap().unwrap_err();
+        assert_eq!(err.code(), error_codes::AUTHENTICATION_FAILED);
+    }
+
+    #[tokio::test]
+    async fn test_interserver_handshake_bytes() {
+        // Hello, Query and the empty Data block of a shard at revision 54441,
+        // laid out by hand after `Connection::sendHello` and `sendQuery` of
+        // ClickHouse 21.x rather than captured. The hash was computed with
+        // Python's hashlib from salt 00..1f and secret "cluster-secret".
+        let request: &[u8] = &[
+            0x00, 0x11, 0x43, 0x6c, 0x69, 0x63, 0x6b, 0x48, 0x6f, 0x75, 0x73, 0x65, 0x20, 0x73,
+            0x65, 0x72, 0x76, 0x65, 0x72, 0x15, 0x08, 0xa9, 0xa9, 0x03, 0x07, 0x64, 0x65, 0x66,
+            0x61, 0x75, 0x6c, 0x74, 0x14, 0x20, 0x49, 0x4e, 0x54, 0x45, 0x52, 0x53, 0x45, 0x52,
+            0x56, 0x45, 0x52, 0x20, 0x53, 0x45, 0x43, 0x52, 0x45, 0x54, 0x20, 0x00, 0x0c, 0x74,
+            0x65, 0x73, 0x74, 0x5f, 0x63, 0x6c, 0x75, 0x73, 0x74, 0x65, 0x72, 0x20, 0x00, 0x01,
+            0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+            0x1e, 0x1f,
+            0x01, 0x08, 0x37, 0x65, 0x32, 0x63, 0x35, 0x64, 0x31, 0x61, 0x02, 0x05, 0x61, 0x6c,
+            0x69, 0x63, 0x65, 0x08, 0x33, 0x62, 0x39, 0x66, 0x30, 0x63, 0x32, 0x65, 0x0d, 0x31,
+            0x30, 0x2e, 0x30, 0x2e, 0x30, 0x2e, 0x31, 0x3a, 0x39, 0x30, 0x30, 0x30, 0x01, 0x0a,
+            0x63, 0x6c, 0x69, 0x63, 0x6b, 0x68, 0x6f, 0x75, 0x73, 0x65, 0x07, 0x73, 0x68, 0x61,
+            0x72, 0x64, 0x2d, 0x31, 0x11, 0x43, 0x6c, 0x69, 0x63, 0x6b, 0x48, 0x6f, 0x75, 0x73,
+            0x65, 0x20, 0x73, 0x65, 0x72, 0x76, 0x65, 0x72, 0x15, 0x08, 0xa9, 0xa9, 0x03, 0x00,
+            0x03, 0x00, 0x20, 0x33, 0x93, 0xa9, 0xa4, 0x8a, 0x55, 0x41, 0x3d, 0x10, 0x1e, 0x28,
+            0x09, 0x2f, 0x20, 0xa8, 0x2d, 0xf4, 0x63, 0xce, 0x44, 0x28, 0xbd, 0xd6, 0xc8, 0xd4,
+            0x70, 0xf5, 0x21, 0xe4, 0x10, 0xec, 0x21, 0x02, 0x00, 0x0f, 0x53, 0x45, 0x4c, 0x45,
+            0x43, 0x54, 0x20, 0x61, 0x20, 0x46, 0x52, 0x4f, 0x4d, 0x20, 0x74, 0x02, 0x00, 0x01,
+            0x00, 0x02, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00,
+        ];
+
+        let session = Arc::new(InterserverSession::default());
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+        client.write_all(request).await.unwrap();
+
+        let mut expected = Encoder::new();
+        expected.uvarint(SERVER_HELLO);
+        expected.string("clickhouse-server");
+        expected.uvarint(19);
+        expected.uvarint(17);
+        expected.uvarint(protocols::DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET);
+        expected.string("UTC");
+        expected.string("clickhouse-server");
+        expected.uvarint(1);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+        assert_eq!(*session.initial_user.lock().unwrap(), "alice");
+    }
+
+    #[tokio::test]
+    async fn test_interserver_old_revision() {
+        // The session speaks the default revision, which has no query hash.
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(SecretSession {}), server));
+
+        let mut request = Encoder::new();
+        interserver_request(&mut request, &[0x5a_u8; 32], &[0_u8; 32], "SELECT 1");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        assert_eq!(actual[0], SERVER_EXCEPTION as u8);
+
+        let expected = Error::Server(ServerError {
+            name: "AUTHENTICATION_FAILED".to_string(),
+            code: error_codes::AUTHENTICATION_FAILED,
+            message: format!(
+                "Interserver authentication needs protocol revision 54441, the connection uses {}",
+                protocols::DBMS_TCP_PROTOCOL_VERSION
+            ),
+            stack_trace: "".to_string()
+        });
+        let err = handle.await.unwrap().unwrap_err();
+        assert_eq!(err.to_string(), expected.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_query_parameters() {
+        let session = Arc::new(ParametersSession::default());
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        let mut request = Encoder::new();
+        hello_request_at(&mut request, 54470);
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        // The session advertises a revision newer than the server speaks.
+        let mut expected = Encoder::new();
+        expected.uvarint(SERVER_HELLO);
+        expected.string("clickhouse-server");
+        expected.uvarint(19);
+        expected.uvarint(17);
+        expected.uvarint(protocols::DBMS_MAX_SUPPORTED_PROTOCOL_VERSION);
+        expected.string("UTC");
+        expected.string("clickhouse-server");
+        expected.uvarint(1);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        let mut request = Encoder::new();
+        request.string(""); // addendum
+        query_packet(&mut request, 54460, "", &[], 0, "SELECT {n:UInt8}");
+        request.string("n");
+        request.uvarint(2); // flags, custom
+        request.string("'7'");
+        request.string(""); // end of parameters
+        Block::new().send_client_data(&mut request, false).unwrap();
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut actual = [0_u8; 1];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual[0], SERVER_END_OF_STREAM as u8);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+        assert_eq!(
+            *session.client_revision.lock().unwrap(),
+            protocols::DBMS_MAX_SUPPORTED_PROTOCOL_VERSION
+        );
+        let parameters = session.parameters.lock().unwrap();
+        assert_eq!(parameters.len(), 1);
+        assert_eq!(parameters.get_str("n"), Some("'7'"));
+    }
+
+    #[tokio::test]
+    async fn test_empty_query() {
+        let session = Arc::new(QueryIdSession::default());
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "");
+        query_request(&mut request, " \n\t");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+        assert!(session.query_ids.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_server_version_overrides() {
+        for client_revision in &[54405_u64, 54380, 54300] {
+            let session = Arc::new(VersionSession {
+                display_name: format!("proxy-{}", std::process::id()),
+                version: (23, 8, 4)
+            });
+            let (mut client, server) = tokio::io::duplex(4096);
+            let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+            let mut request = Encoder::new();
+            hello_request_at(&mut request, *client_revision);
+            client.write_all(&request.get_buffer()).await.unwrap();
+
+            let mut expected = Encoder::new();
+            expected.uvarint(SERVER_HELLO);
+            expected.string("clickhouse-server");
+            expected.uvarint(23);
+            expected.uvarint(8);
+            expected.uvarint(54428);
+            expected.string("UTC");
+            if *client_revision >= protocols::DBMS_MIN_REVISION_WITH_SERVER_DISPLAY_NAME {
+                expected.string(&session.display_name);
+            }
+            if *client_revision >= protocols::DBMS_MIN_REVISION_WITH_VERSION_PATCH {
+                expected.uvarint(4);
+            }
+            let expected = expected.get_buffer();
+
+            client.shutdown().await.unwrap();
+            let mut actual = Vec::new();
+            client.read_to_end(&mut actual).await.unwrap();
+            assert_eq!(actual, expected, "client revision {}", client_revision);
+            handle.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_external_tables() {
+        let session = Arc::new(ExternalTablesSession::default());
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_packet(&mut request, CLIENT_REVISION, "", &[], 0, "SELECT * FROM tmp");
+        for block in &[
+            Block::new().column("id", vec![1_u32, 2]),
+            Block::new().column("id", vec![3_u32]),
+        ] {
+            request.uvarint(CLIENT_DATA);
+            request.string("tmp");
+            block.write(&mut request, false);
+        }
+        Block::new().send_client_data(&mut request, false).unwrap();
+        query_request(&mut request, "SELECT 1");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+
+        let tables = session.tables.lock().unwrap();
+        assert_eq!(tables.len(), 2);
+        let rows: Vec<usize> = tables[0]["tmp"].iter().map(|block| block.row_count()).collect();
+        assert_eq!(rows, vec![2, 1]);
+        assert_eq!(tables[0]["tmp"][1].get::<u32, _>(0, "id").unwrap(), 3);
+        assert_eq!(tables[0].len(), 1);
+        assert!(tables[1].is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scalars() {
+        for (revision, accepted) in &[(54429, true), (54428, false)] {
+            let session = Arc::new(ScalarsSession::default());
+            let (mut client, server) = tokio::io::duplex(4096);
+            let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+            let mut request = Encoder::new();
+            hello_request_at(&mut request, *revision);
+            let query = "SELECT 7 IN _subquery1";
+            query_packet(&mut request, *revision, "", &[], 0, query);
+            request.uvarint(CLIENT_SCALAR);
+            request.string("_subquery1");
+            Block::new()
+                .column("_subquery1", vec![7_u64])
+                .write(&mut request, false);
+            Block::new().send_client_data(&mut request, false).unwrap();
+            query_request(&mut request, "SELECT 1");
+            client.write_all(&request.get_buffer()).await.unwrap();
+
+            if !accepted {
+                let err = handle.await.unwrap().unwrap_err();
+                assert!(matches!(
+                    err,
+                    Error::Driver(DriverError::UnknownPacket { packet: CLIENT_SCALAR })
+                ));
+                assert!(session.scalars.lock().unwrap().is_empty());
+                continue;
+            }
+
+            client.shutdown().await.unwrap();
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+            assert_eq!(response.last(), Some(&(SERVER_END_OF_STREAM as u8)));
+            handle.await.unwrap().unwrap();
+
+            let scalars = session.scalars.lock().unwrap();
+            assert_eq!(scalars.len(), 2);
+            assert_eq!(scalars[0].len(), 1);
+            assert_eq!(scalars[0]["_subquery1"].get::<u64, _>(0, 0).unwrap(), 7);
+            assert!(scalars[1].is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_truncated_packet() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(TestSession {}), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        client.write_all(request.get_buffer_ref()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        let mut actual = vec![0_u8; expected.get_buffer_ref().len()];
+        client.read_exact(&mut actual).await.unwrap();
+
+        // Half of the two byte varint of 300.
+        client.write_all(&[0xac]).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let err = handle.await.unwrap().unwrap_err();
+        assert!(matches!(err, Error::Driver(DriverError::UnexpectedEof { read: 1 })));
+        assert_eq!(err.code(), error_codes::ATTEMPT_TO_READ_AFTER_EOF);
+
+        // A close between packets is not an error.
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(TestSession {}), server));
+        client.write_all(&request.get_buffer()).await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        assert_eq!(actual, expected.get_buffer());
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_query_error() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(ErrorSession {}), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT a FROM t");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        ExceptionResponse::write(&mut expected, &query_error(), false);
+        let expected = expected.get_buffer();
+
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        let err = handle.await.unwrap().unwrap_err();
+        assert_eq!(err.to_string(), query_error().to_string());
+    }
+
+    #[tokio::test]
+    async fn test_session_panic() {
+        let session: Arc<dyn ClickHouseSession> = Arc::new(PanicSession {});
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+        let (mut other, other_server) = tokio::io::duplex(4096);
+        let other_handle = tokio::spawn(ClickHouseServer::run_on(session, other_server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT panic");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let panic_error = Error::Server(ServerError {
+            name: "UNKNOWN_EXCEPTION".to_string(),
+            code: error_codes::UNKNOWN_EXCEPTION,
+            message: "Session panicked: boom".to_string(),
+            stack_trace: "".to_string()
+        });
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        ExceptionResponse::write(&mut expected, &panic_error, false);
+
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        assert_eq!(actual, expected.get_buffer());
+        let err = handle.await.unwrap().unwrap_err();
+        assert_eq!(err.code(), error_codes::UNKNOWN_EXCEPTION);
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT 1");
+        other.write_all(&request.get_buffer()).await.unwrap();
+        other.shutdown().await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        expected.uvarint(SERVER_END_OF_STREAM);
+
+        let mut actual = Vec::new();
+        other.read_to_end(&mut actual).await.unwrap();
+        assert_eq!(actual, expected.get_buffer());
+        other_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_query_size() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let srv = ClickHouseServer::builder().max_query_size(8).build();
+        let handle =
+            tokio::spawn(async move { srv.serve_stream(Arc::new(TestSession {}), server).await });
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT 10");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let err = handle.await.unwrap().unwrap_err();
+        assert_eq!(err.code(), error_codes::TOO_LARGE_STRING_SIZE);
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        ExceptionResponse::write(&mut expected, &err, false);
+
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        assert_eq!(actual, expected.get_buffer());
+    }
+
+    #[tokio::test]
+    async fn test_max_string_size() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let session = Arc::new(CollectInsertSession::new(insert_header()));
+        let blocks = session.blocks();
+        let srv = ClickHouseServer::builder().max_string_size(16).build();
+        let handle = {
+            let session = session.clone();
+            tokio::spawn(async move { srv.serve_stream(session, server).await })
+        };
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "INSERT INTO t VALUES");
+        let values = vec!["x".repeat(16)];
+        Block::new().column("s", values).send_client_data(&mut request, false).unwrap();
+
+        // Only the length of the oversized value is sent, the guard must not wait
+        // for its bytes.
+        let mut data = Encoder::new();
+        Block::new().column("s", vec!["abc"]).send_client_data(&mut data, false).unwrap();
+        let mut data = data.get_buffer();
+        data.truncate(data.len() - 4);
+        let mut request = request.get_buffer();
+        request.extend(data);
+        let mut length = Encoder::new();
+        length.uvarint(1 << 20);
+        request.extend(length.get_buffer());
+        client.write_all(&request).await.unwrap();
+
+        let err = handle.await.unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Driver(DriverError::TooLargeObject { size, max_size: 16 }) if size == 1 << 20
+        ));
+        assert_eq!(err.code(), error_codes::TOO_LARGE_STRING_SIZE);
+        let blocks = blocks.lock().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].get::<String, _>(0, "s").unwrap(), "x".repeat(16));
+    }
+
+    #[tokio::test]
+    async fn test_max_execution_time() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let srv = ClickHouseServer::builder()
+            .max_execution_time(Duration::from_millis(100))
+            .build();
+        let handle =
+            tokio::spawn(async move { srv.serve_stream(Arc::new(SleepSession {}), server).await });
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT sleep(10)");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let err = Error::Server(ServerError {
+            name: "TIMEOUT_EXCEEDED".to_string(),
+            code: error_codes::TIMEOUT_EXCEEDED,
+            message: "Timeout exceeded: elapsed 100ms".to_string(),
+            stack_trace: "".to_string()
+        });
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        ExceptionResponse::write(&mut expected, &err, false);
+        let expected = expected.get_buffer();
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        // The connection outlives the timed out query.
+        let mut request = Encoder::new();
+        query_request(&mut request, "SELECT 1");
+        client.write_all(&request.get_buffer()).await.unwrap();
+        let mut packet = [0_u8; 1];
+        client.read_exact(&mut packet).await.unwrap();
+        assert_eq!(packet[0], SERVER_END_OF_STREAM as u8);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_execution_time_inside_packet() {
+        // The client reads nothing, so the query blocks in its first packet.
+        let (mut client, server) = tokio::io::duplex(64);
+        let srv = ClickHouseServer::builder()
+            .max_execution_time(Duration::from_millis(100))
+            .build();
+        let handle = tokio::spawn(async move {
+            srv.serve_stream(Arc::new(LargeBlockSession {}), server).await
+        });
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT a");
+        client.write_all(&request.get_buffer()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        let hello = expected.get_buffer();
+        let mut packet = Encoder::new();
+        let settings = SerializationSettings {
+            client_revision: CLIENT_REVISION,
+            ..Default::default()
+        };
+        Block::new()
+            .column("a", vec![7_u32; 1 << 16])
+            .send_data(&mut packet, SERVER_DATA, CompressionMethod::None, settings)
+            .unwrap();
+        let packet = packet.get_buffer();
+
+        // The part of the packet sent is all the client gets, no exception.
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        assert_eq!(&actual[..hello.len()], &hello[..]);
+        let sent = &actual[hello.len()..];
+        assert!(sent.len() < packet.len());
+        assert_eq!(sent, &packet[..sent.len()]);
+
+        let err = handle.await.unwrap().unwrap_err();
+        assert_eq!(err.code(), error_codes::TIMEOUT_EXCEEDED);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_setting() {
+        // Only settings sent as strings can fail to parse.
+        let revision = protocols::DBMS_MIN_REVISION_WITH_SETTINGS_SERIALIZED_AS_STRINGS;
+        let (mut client, server) = tokio::io::duplex(4096);
+        let session = Arc::new(StringSettingsSession {});
+        let handle = tokio::spawn(ClickHouseServer::run_on(session, server));
+
+        let mut request = Encoder::new();
+        hello_request_at(&mut request, revision);
+        for max_block_size in &["many", "1"] {
+            let settings = [("max_block_size", *max_block_size)];
+            query_packet(&mut request, revision, "", &settings, 0, "SELECT a");
+            Block::new().send_client_data(&mut request, false).unwrap();
+        }
+        client.write_all(&request.get_buffer()).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let mut response = Vec::new();
+        let read = client.read_to_end(&mut response);
+        tokio::time::timeout(Duration::from_secs(5), read).await.unwrap().unwrap();
+        handle.await.unwrap().unwrap();
+
+        let err = Error::Driver(DriverError::InvalidSetting {
+            name: "max_block_size".to_string(),
+            value: "many".to_string(),
+            expected: "an unsigned integer"
+        });
+        assert_eq!(err.code(), error_codes::BAD_ARGUMENTS);
+        let mut exception = Encoder::new();
+        ExceptionResponse::write(&mut exception, &err, false);
+        let exception = exception.get_buffer();
+
+        // The first query is not run, the connection serves the next one.
+        let mut result = Encoder::new();
+        header_response(&mut result, &stream_block(0), false);
+        for i in 0..6_u32 {
+            result.uvarint(SERVER_DATA);
+            result.string("");
+            Block::new().column("a", vec![i / 2]).write(&mut result, false);
+        }
+        result.uvarint(SERVER_END_OF_STREAM);
+        let mut expected = exception;
+        expected.extend(result.get_buffer());
+        assert!(response.ends_with(&expected));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_packet() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(Arc::new(TestSession {}), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        request.uvarint(0x63);
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let err = handle.await.unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Driver(DriverError::UnknownPacket { packet: 0x63 })
+        ));
+        assert_eq!(err.code(), error_codes::UNKNOWN_PACKET_FROM_CLIENT);
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        ExceptionResponse::write(&mut expected, &err, false);
+
+        let mut actual = Vec::new();
+        client.read_to_end(&mut actual).await.unwrap();
+        assert_eq!(actual, expected.get_buffer());
+    }
+
+    #[tokio::test]
+    async fn test_write_warning() {
+        for send_logs_level in &["warning", "none"] {
+            let (mut client, server) = tokio::io::duplex(4096);
+            let session = Arc::new(WarningSession {});
+            let handle = tokio::spawn(ClickHouseServer::run_on(session, server));
+
+            let mut request = Encoder::new();
+            hello_request_at(&mut request, 54428);
+            let settings = [("send_logs_level", *send_logs_level)];
+            let query = "SELECT * FROM t WHERE a GLOBAL IN (1)";
+            query_packet(&mut request, 54428, "q1", &settings, 0, query);
+            Block::new().send_client_data(&mut request, false).unwrap();
+            client.write_all(&request.get_buffer()).await.unwrap();
+            client.shutdown().await.unwrap();
+
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+            handle.await.unwrap().unwrap();
+
+            let mut hello = Encoder::new();
+            hello_response(&mut hello);
+            let mut cursor = std::io::Cursor::new(&response[hello.get_buffer_ref().len()..]);
+
+            if *send_logs_level == "warning" {
+                assert_eq!(cursor.read_uvarint().unwrap(), SERVER_LOG);
+                assert_eq!(cursor.read_string().unwrap(), "");
+                let block = Block::load(&mut cursor, chrono_tz::Tz::UTC, false).unwrap();
+                assert_eq!(block.get::<i8, _>(0, "priority").unwrap(), LogsLevel::Warning as i8);
+                assert_eq!(block.get::<String, _>(0, "query_id").unwrap(), "q1");
+                assert_eq!(block.get::<String, _>(0, "text").unwrap(), "GLOBAL IN is deprecated");
+            }
+            assert_eq!(cursor.read_uvarint().unwrap(), SERVER_END_OF_STREAM);
+            assert_eq!(cursor.position() as usize, cursor.get_ref().len());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_idle_connection() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let shutdown = CancellationToken::new();
+        let srv = ClickHouseServer::builder().shutdown(shutdown.clone()).build();
+        let handle =
+            tokio::spawn(async move { srv.serve_stream(Arc::new(TestSession {}), server).await });
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        shutdown.cancel();
+        handle.await.unwrap().unwrap();
+
+        let mut rest = Vec::new();
+        client.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, vec![SERVER_END_OF_STREAM as u8]);
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let timeout = Duration::from_millis(50);
+        let srv = ClickHouseServer::builder().read_timeout(timeout).build();
+        assert_eq!(srv.read_timeout(), Some(timeout));
+        assert_eq!(ClickHouseServer::default().read_timeout(), None);
+        let handle =
+            tokio::spawn(async move { srv.serve_stream(Arc::new(TestSession {}), server).await });
+
+        let err = handle.await.unwrap().unwrap_err();
+        match err {
+            Error::Server(e) => assert_eq!(e.code, error_codes::SOCKET_TIMEOUT),
+            _ => panic!("unexpected error {}", err)
+        }
+
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        assert_eq!(response.first(), Some(&(SERVER_EXCEPTION as u8)));
+    }
+
+    #[tokio::test]
+    async fn test_insert_blocks() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let session = Arc::new(InsertSession::default());
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "INSERT INTO t VALUES");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        expected.uvarint(SERVER_DATA);
+        expected.string("");
+        insert_header().write(&mut expected, false);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        let mut request = Encoder::new();
+        Block::new().column("a", vec![1_u32, 2]).send_client_data(&mut request, false).unwrap();
+        Block::new().column("a", vec![3_u32]).send_client_data(&mut request, false).unwrap();
+        Block::new().send_client_data(&mut request, false).unwrap();
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut actual = [0_u8; 1];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual[0], SERVER_END_OF_STREAM as u8);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+
+        let blocks = session.blocks.lock().unwrap();
+        let rows: Vec<usize> = blocks.iter().map(|block| block.row_count()).collect();
+        assert_eq!(rows, vec![2, 1, 0]);
+        assert_eq!(blocks[1].get::<u32, _>(0, "a").unwrap(), 3);
+        assert!(blocks[2].is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_generated_query_id() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let session = Arc::new(QueryIdSession::default());
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT 1");
+        query_request(&mut request, "SELECT 2");
+        query_request_with_id(&mut request, "q1", "SELECT 3");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        for _ in 0..3 {
+            expected.uvarint(SERVER_END_OF_STREAM);
+        }
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+
+        let query_ids = session.query_ids.lock().unwrap();
+        for query_id in &query_ids[..2] {
+            let uuid = uuid::Uuid::parse_str(query_id).unwrap();
+            assert_eq!(uuid.get_version(), Some(uuid::Version::Random));
+        }
+        assert_ne!(query_ids[0], query_ids[1]);
+        assert_eq!(query_ids[2], "q1");
+    }
+
+    #[tokio::test]
+    async fn test_max_query_id_size() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let session = Arc::new(QueryIdSession::default());
+        let srv = ClickHouseServer::builder().max_string_size(16).build();
+        let handle = {
+            let session = session.clone();
+            tokio::spawn(async move { srv.serve_stream(session, server).await })
+        };
+
+        // Ids up to the limit are kept as sent, however they look.
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request_with_id(&mut request, "job 7/retry #2", "SELECT 1");
+        query_request_with_id(&mut request, "", "SELECT 2");
+        query_request_with_id(&mut request, &"x".repeat(17), "SELECT 3");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let err = handle.await.unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Driver(DriverError::TooLargeObject { size: 17, max_size: 16 })
+        ));
+        let query_ids = session.query_ids.lock().unwrap();
+        assert_eq!(query_ids.len(), 2);
+        assert_eq!(query_ids[0], "job 7/retry #2");
+        // An empty id is replaced by a generated UUID, longer than the limit.
+        let generated = uuid::Uuid::parse_str(&query_ids[1]).unwrap();
+        assert_eq!(generated.get_version(), Some(uuid::Version::Random));
+    }
+
+    #[tokio::test]
+    async fn test_processing_stage() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let session = Arc::new(StageSession::default());
+        let handle = tokio::spawn(ClickHouseServer::run_on(session.clone(), server));
+
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        request.uvarint(CLIENT_QUERY);
+        request.string("");
+        request.write(0_u8); // client info, no query kind
+        request.string(""); // end of settings
+        request.uvarint(1); // stage
+        request.uvarint(0); // compression
+        request.string("SELECT sum(x) FROM t");
+        Block::new().send_client_data(&mut request, false).unwrap();
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+        assert_eq!(
+            *session.stage.lock().unwrap(),
+            Some(QueryProcessingStage::WithMergeableState)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_addr() {
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap();
+        let session = Arc::new(AddrSession::default());
+
+        let server_session = session.clone();
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            ClickHouseServer::run_on_stream(server_session, stream).await
+        });
+
+        let mut client = TcpStream::connect(server_addr).await.unwrap();
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT 1");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        let client_addr = client.local_addr().unwrap();
+        drop(client);
+        handle.await.unwrap().unwrap();
+        assert_eq!(*session.client_addr.lock().unwrap(), Some(client_addr));
+    }
+
+    #[tokio::test]
+    async fn test_serve_listener() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shutdown = CancellationToken::new();
+        let srv = ClickHouseServer::builder().shutdown(shutdown.clone()).build();
+        let handle =
+            tokio::spawn(async move { srv.serve(listener, Arc::new(TestSession {})).await });
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        let mut first = TcpStream::connect(addr).await.unwrap();
+        let mut second = TcpStream::connect(addr).await.unwrap();
+        for client in [&mut first, &mut second].iter_mut() {
+            let mut request = Encoder::new();
+            hello_request(&mut request);
+            query_request(&mut request, "SELECT 1");
+            client.write_all(&request.get_buffer()).await.unwrap();
+        }
+        for client in [&mut first, &mut second].iter_mut() {
+            let mut actual = vec![0_u8; expected.len()];
+            client.read_exact(&mut actual).await.unwrap();
+            assert_eq!(actual, expected);
+        }
+
+        shutdown.cancel();
+        handle.await.unwrap().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_query_over_unix_socket() {
+        use tokio::net::UnixListener;
+
+        let path = std::env::temp_dir().join(format!("clickhouse-srv-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            ClickHouseServer::run_on_unix_stream(Arc::new(TestSession {}), stream).await
+        });
+
+        let mut client = UnixStream::connect(&path).await.unwrap();
+        let mut request = Encoder::new();
+        hello_request(&mut request);
+        query_request(&mut request, "SELECT 1");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        let mut expected = Encoder::new();
+        hello_response(&mut expected);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        let expected = expected.get_buffer();
+
+        let mut actual = vec![0_u8; expected.len()];
+        client.read_exact(&mut actual).await.unwrap();
+        assert_eq!(actual, expected);
+
+        drop(client);
+        handle.await.unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
     }
 }
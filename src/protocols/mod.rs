@@ -1,11 +1,19 @@
 mod protocol_exception;
 mod protocol_hello;
+mod protocol_interserver;
+mod protocol_log;
+mod protocol_profile_events;
 mod protocol_query;
+mod protocol_table_columns;
 mod protocol_type;
 
 pub use protocol_exception::*;
 pub use protocol_hello::*;
+pub use protocol_interserver::*;
+pub use protocol_log::*;
+pub use protocol_profile_events::*;
 pub use protocol_query::*;
+pub use protocol_table_columns::*;
 pub use protocol_type::*;
 
 use crate::types::Block;
@@ -15,8 +23,13 @@ pub enum Packet {
     Ping,
     Cancel,
     Hello(HelloRequest),
-    Query(QueryRequest),
-    Data(Block)
+    Query(Box<QueryRequest>),
+    /// A block and the name of the temporary table it belongs to, empty for the
+    /// blocks of the query itself.
+    Data(String, Block),
+    /// A named single-row block holding the result of a subquery, e.g. of an
+    /// `IN` or a `WITH (SELECT ...) AS x`.
+    Scalar(String, Block)
 }
 
 #[derive(Debug)]
@@ -32,6 +45,10 @@ impl Default for Stage {
     }
 }
 
+// Revision advertised by `ClickHouseSession::dbms_tcp_protocol_version` unless
+// overridden, the connection uses the lower of it and the client's revision.
+pub const DBMS_TCP_PROTOCOL_VERSION: u64 = 54428;
+
 pub const DBMS_MIN_REVISION_WITH_CLIENT_INFO: u64 = 54032;
 pub const DBMS_MIN_REVISION_WITH_SERVER_TIMEZONE: u64 = 54058;
 pub const DBMS_MIN_REVISION_WITH_QUOTA_KEY_IN_CLIENT_INFO: u64 = 54060;
@@ -53,6 +70,9 @@ pub const DBMS_MIN_REVISION_WITH_CLIENT_WRITE_INFO: u64 = 54420;
 // Minimum revision supporting SettingsBinaryFormat::STRINGS.
 pub const DBMS_MIN_REVISION_WITH_SETTINGS_SERIALIZED_AS_STRINGS: u64 = 54429;
 
+// Minimum revision sending scalars, the results of subqueries, with a query.
+pub const DBMS_MIN_REVISION_WITH_SCALARS: u64 = 54429;
+
 // Minimum revision supporting OpenTelemetry
 pub const DBMS_MIN_REVISION_WITH_OPENTELEMETRY: u64 = 54442;
 
@@ -61,3 +81,19 @@ pub const DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET: u64 = 54441;
 
 pub const DBMS_MIN_REVISION_WITH_X_FORWARDED_FOR_IN_CLIENT_INFO: u64 = 54443;
 pub const DBMS_MIN_REVISION_WITH_REFERER_IN_CLIENT_INFO: u64 = 54447;
+
+pub const DBMS_MIN_PROTOCOL_VERSION_WITH_DISTRIBUTED_DEPTH: u64 = 54448;
+pub const DBMS_MIN_PROTOCOL_VERSION_WITH_INITIAL_QUERY_START_TIME: u64 = 54449;
+pub const DBMS_MIN_PROTOCOL_VERSION_WITH_INCREMENTAL_PROFILE_EVENTS: u64 = 54451;
+pub const DBMS_MIN_PROTOCOL_VERSION_WITH_PARALLEL_REPLICAS: u64 = 54453;
+// The client sends an addendum right after receiving the server `Hello`.
+pub const DBMS_MIN_PROTOCOL_VERSION_WITH_ADDENDUM: u64 = 54458;
+pub const DBMS_MIN_PROTOCOL_VERSION_WITH_QUOTA_KEY: u64 = 54458;
+// Query parameters follow the query text, in the format of settings.
+pub const DBMS_MIN_PROTOCOL_VERSION_WITH_PARAMETERS: u64 = 54459;
+pub const DBMS_MIN_PROTOCOL_VERSION_WITH_SERVER_QUERY_TIME_IN_PROGRESS: u64 = 54460;
+
+// Highest revision the server speaks, whatever the session advertises. 54461
+// adds password rules to the server `Hello` and 54462 a nonce to the
+// interserver hash, neither is implemented.
+pub const DBMS_MAX_SUPPORTED_PROTOCOL_VERSION: u64 = 54460;
@@ -1,34 +1,150 @@
+use std::error::Error as StdError;
+
 use crate::binary::Encoder;
-use crate::error_codes::UNKNOWN_EXCEPTION;
 use crate::errors::Error;
 use crate::protocols::*;
 
 pub struct ExceptionResponse {}
 
 impl ExceptionResponse {
+    /// Writes an `Exception` packet. Errors raised by this crate have no stack
+    /// trace of their own, their debug representation is sent instead.
+    ///
+    /// The sources of the error follow as nested exceptions, down to the root
+    /// cause. A source whose message the level above already shows, as every
+    /// variant of `Error` does with the error it wraps, is left out.
     pub fn write(encoder: &mut Encoder, error: &Error, with_stack_trace: bool) {
-        let mut code = UNKNOWN_EXCEPTION;
+        let code = error.code();
         let name = error.exception_name();
         let mut stack_trace = "".to_string();
         let mut message = error.to_string();
 
         if let Error::Server(e) = error {
-            code = e.code;
             if with_stack_trace {
                 stack_trace = e.stack_trace.clone();
             }
             message = e.message.clone();
+        } else if with_stack_trace {
+            stack_trace = format!("{:?}", error);
+        }
+
+        let mut causes = Vec::new();
+        let mut shown = error.to_string();
+        let mut source = error.source();
+        while let Some(cause) = source {
+            let cause_message = cause.to_string();
+            if !shown.contains(&cause_message) {
+                causes.push(cause);
+            }
+            shown = cause_message;
+            source = cause.source();
         }
+
         encoder.uvarint(SERVER_EXCEPTION);
+        write_level(encoder, code, name, message, stack_trace, !causes.is_empty());
+
+        for (i, cause) in causes.iter().enumerate() {
+            // Only errors of this crate have a code of their own.
+            let (code, name) = match cause.downcast_ref::<Error>() {
+                Some(e) => (e.code(), e.exception_name()),
+                None => (code, name)
+            };
+            let stack_trace = if with_stack_trace {
+                format!("{:?}", cause)
+            } else {
+                "".to_string()
+            };
+            let has_nested = i + 1 < causes.len();
+            write_level(encoder, code, name, cause.to_string(), stack_trace, has_nested);
+        }
+    }
+}
+
+fn write_level(
+    encoder: &mut Encoder,
+    code: u32,
+    name: &str,
+    message: String,
+    stack_trace: String,
+    has_nested: bool
+) {
+    encoder.write(code);
+    //Name
+    encoder.string(name);
+    // Message
+    encoder.string(message);
+    // StackTrace
+    encoder.string(stack_trace);
+    // Nested.
+    encoder.write(has_nested);
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use super::*;
+    use crate::error_codes::UNKNOWN_TABLE;
+    use crate::errors::ServerError;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("Cannot read table t")]
+    struct ReadTableError(#[source] io::Error);
+
+    fn unknown_table() -> Error {
+        Error::Server(ServerError {
+            code: UNKNOWN_TABLE,
+            name: "DB::Exception".to_string(),
+            message: "Table default.t doesn't exist".to_string(),
+            stack_trace: "0. main".to_string()
+        })
+    }
+
+    #[test]
+    fn test_write() {
+        let mut encoder = Encoder::new();
+        ExceptionResponse::write(&mut encoder, &unknown_table(), false);
+
+        let mut expected = vec![SERVER_EXCEPTION as u8];
+        expected.extend_from_slice(&UNKNOWN_TABLE.to_le_bytes());
+        expected.push(13);
+        expected.extend_from_slice(b"DB::Exception");
+        expected.push(29);
+        expected.extend_from_slice(b"Table default.t doesn't exist");
+        expected.push(0); // stack trace
+        expected.push(0); // has nested
+        assert_eq!(encoder.get_buffer(), expected);
+    }
+
+    #[test]
+    fn test_write_stack_trace() {
+        let mut encoder = Encoder::new();
+        ExceptionResponse::write(&mut encoder, &unknown_table(), true);
+
+        let buffer = encoder.get_buffer();
+        assert_eq!(&buffer[buffer.len() - 9..], b"\x070. main\x00");
+    }
+
+    #[test]
+    fn test_write_nested() {
+        let missing = io::Error::new(io::ErrorKind::NotFound, "File data.bin is missing");
+        let error = Error::IO(io::Error::other(ReadTableError(missing)));
+
+        let mut encoder = Encoder::new();
+        ExceptionResponse::write(&mut encoder, &error, false);
 
-        encoder.write(code);
-        //Name
-        encoder.string(name);
-        // Message
-        encoder.string(message);
-        // StackTrace
-        encoder.string(stack_trace);
-        // Nested.
-        encoder.write(false);
+        let mut expected = Encoder::new();
+        expected.uvarint(SERVER_EXCEPTION);
+        expected.write(error.code());
+        expected.string("IOException");
+        expected.string("Input/output error: `Cannot read table t`");
+        expected.string("");
+        expected.write(true);
+        expected.write(error.code());
+        expected.string("IOException");
+        expected.string("File data.bin is missing");
+        expected.string("");
+        expected.write(false);
+        assert_eq!(encoder.get_buffer(), expected.get_buffer());
     }
 }
@@ -19,13 +19,18 @@ pub struct HelloRequest {
     pub user: String,
     pub password: String,
 
+    // Sent instead of credentials by another server of the cluster, see
+    // `HelloRequest::is_interserver`.
+    pub cluster: String,
+    pub salt: Vec<u8>,
+
     // Not set currently
     pub client_version_patch: u64
 }
 
 impl HelloRequest {
     pub fn read_from<R: Read>(reader: &mut R) -> Result<HelloRequest> {
-        let request = HelloRequest {
+        let mut request = HelloRequest {
             client_name: reader.read_string()?,
             client_version_major: reader.read_uvarint()?,
             client_version_minor: reader.read_uvarint()?,
@@ -34,7 +39,8 @@ impl HelloRequest {
             user: reader.read_string()?,
             password: reader.read_string()?,
 
-            client_version_patch: 0
+            client_version_patch: 0,
+            ..Default::default()
         };
 
         if request.user.is_empty() {
@@ -46,12 +52,19 @@ impl HelloRequest {
             }));
         }
 
-        // TODO
-        // if request.user != " INTERSERVER SECRET " {
-        // } else {
-        // }
+        if request.is_interserver() {
+            request.cluster = reader.read_string()?;
+            request.salt = reader.read_bounded_bytes(INTERSERVER_HASH_SIZE)?;
+        }
+
         Ok(request)
     }
+
+    /// Returns `true` for a connection from another server of the cluster, which
+    /// signs each query with the cluster secret instead of sending a password.
+    pub fn is_interserver(&self) -> bool {
+        self.user == USER_INTERSERVER_MARKER
+    }
 }
 
 pub struct HelloResponse {
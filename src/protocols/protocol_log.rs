@@ -0,0 +1,174 @@
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use chrono::TimeZone;
+use chrono_tz::Tz;
+
+use crate::binary::Encoder;
+use crate::protocols::*;
+use crate::types::column::new_column;
+use crate::types::column::ArcColumnWrapper;
+use crate::types::column::ColumnFrom;
+use crate::types::Block;
+use crate::types::DateTimeType;
+use crate::types::Simple;
+use crate::types::SqlType;
+
+/// Log priorities, in the order of the client's `send_logs_level` setting.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogsLevel {
+    #[default]
+    None = 0,
+    Fatal = 1,
+    Error = 3,
+    Warning = 4,
+    Information = 6,
+    Debug = 7,
+    Trace = 8,
+    Test = 9
+}
+
+impl LogsLevel {
+    /// Returns `true` if the client asked for records of `priority`.
+    pub fn accepts(self, priority: LogsLevel) -> bool {
+        priority != LogsLevel::None && priority <= self
+    }
+}
+
+impl From<&str> for LogsLevel {
+    fn from(level: &str) -> Self {
+        match level.to_ascii_lowercase().as_str() {
+            "fatal" => LogsLevel::Fatal,
+            "error" => LogsLevel::Error,
+            "warning" => LogsLevel::Warning,
+            "information" => LogsLevel::Information,
+            "debug" => LogsLevel::Debug,
+            "trace" => LogsLevel::Trace,
+            "test" => LogsLevel::Test,
+            _ => LogsLevel::None
+        }
+    }
+}
+
+/// A single server log line, shaped like a row of `system.text_log`.
+#[derive(Clone, Debug)]
+pub struct LogRecord {
+    pub event_time: SystemTime,
+    pub query_id: String,
+    pub thread_id: u64,
+    pub priority: LogsLevel,
+    pub source: String,
+    pub text: String
+}
+
+impl LogRecord {
+    pub fn new(priority: LogsLevel, source: &str, text: &str) -> Self {
+        Self {
+            event_time: SystemTime::now(),
+            query_id: String::new(),
+            thread_id: 0,
+            priority,
+            source: source.to_string(),
+            text: text.to_string()
+        }
+    }
+}
+
+pub struct LogResponse {}
+
+impl LogResponse {
+    /// Encodes `records` as a single `Log` packet, which is never compressed.
+    pub fn write(encoder: &mut Encoder, records: &[LogRecord], host_name: &str) {
+        let block = Self::block(records, host_name);
+
+        encoder.uvarint(SERVER_LOG);
+        encoder.string(""); // temporary table
+        block.write(encoder, false);
+    }
+
+    fn block(records: &[LogRecord], host_name: &str) -> Block {
+        let n = records.len();
+        let mut times = Vec::with_capacity(n);
+        let mut microseconds = Vec::with_capacity(n);
+        for record in records {
+            let elapsed = record.event_time.duration_since(UNIX_EPOCH).unwrap_or_default();
+            times.push(Tz::UTC.timestamp_opt(elapsed.as_secs() as i64, 0).unwrap());
+            microseconds.push(elapsed.subsec_micros());
+        }
+
+        let times = new_column::<Simple>("event_time", Vec::column_from::<ArcColumnWrapper>(times))
+            .cast_to(SqlType::DateTime(DateTimeType::DateTime32))
+            .unwrap();
+
+        Block::new()
+            .column("event_time", times)
+            .column("event_time_microseconds", microseconds)
+            .column("host_name", vec![host_name.to_string(); n])
+            .column(
+                "query_id",
+                records.iter().map(|r| r.query_id.clone()).collect::<Vec<_>>()
+            )
+            .column(
+                "thread_id",
+                records.iter().map(|r| r.thread_id).collect::<Vec<_>>()
+            )
+            .column(
+                "priority",
+                records.iter().map(|r| r.priority as i8).collect::<Vec<_>>()
+            )
+            .column(
+                "source",
+                records.iter().map(|r| r.source.clone()).collect::<Vec<_>>()
+            )
+            .column(
+                "text",
+                records.iter().map(|r| r.text.clone()).collect::<Vec<_>>()
+            )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::binary::ReadEx;
+
+    #[test]
+    fn test_logs_level() {
+        let level = LogsLevel::from("warning");
+        assert!(level.accepts(LogsLevel::Error));
+        assert!(level.accepts(LogsLevel::Warning));
+        assert!(!level.accepts(LogsLevel::Debug));
+        assert!(!LogsLevel::from("none").accepts(LogsLevel::Fatal));
+    }
+
+    #[test]
+    fn test_write_log() {
+        let mut record = LogRecord::new(LogsLevel::Information, "executeQuery", "Read 1 rows");
+        record.query_id = "q1".to_string();
+
+        let mut encoder = Encoder::new();
+        LogResponse::write(&mut encoder, &[record], "localhost");
+
+        let mut cursor = Cursor::new(encoder.get_buffer_ref());
+        assert_eq!(cursor.read_uvarint().unwrap(), SERVER_LOG);
+        assert_eq!(cursor.read_string().unwrap(), "");
+        let block = Block::load(&mut cursor, Tz::UTC, false).unwrap();
+
+        let names: Vec<&str> = block.columns().iter().map(|c| c.name()).collect();
+        assert_eq!(names, vec![
+            "event_time",
+            "event_time_microseconds",
+            "host_name",
+            "query_id",
+            "thread_id",
+            "priority",
+            "source",
+            "text",
+        ]);
+        assert_eq!(block.get::<String, _>(0, "query_id").unwrap(), "q1");
+        assert_eq!(block.get::<i8, _>(0, "priority").unwrap(), 6);
+        assert_eq!(block.get::<String, _>(0, "text").unwrap(), "Read 1 rows");
+    }
+}
@@ -0,0 +1,105 @@
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use chrono::TimeZone;
+use chrono_tz::Tz;
+
+use crate::binary::Encoder;
+use crate::protocols::*;
+use crate::types::column::new_column;
+use crate::types::column::ArcColumnWrapper;
+use crate::types::column::ColumnFrom;
+use crate::types::Block;
+use crate::types::DateTimeType;
+use crate::types::Enum8;
+use crate::types::Simple;
+use crate::types::SqlType;
+
+const INCREMENT: i8 = 1;
+const GAUGE: i8 = 2;
+
+pub struct ProfileEventsResponse {}
+
+impl ProfileEventsResponse {
+    /// Encodes named increment counters as a `ProfileEvents` packet.
+    pub fn write(encoder: &mut Encoder, counters: &[(&str, i64)], host_name: &str) {
+        let block = Self::block(counters, host_name);
+
+        encoder.uvarint(SERVER_PROFILE_EVENTS);
+        encoder.string(""); // temporary table
+        block.write(encoder, false);
+    }
+
+    fn block(counters: &[(&str, i64)], host_name: &str) -> Block {
+        let n = counters.len();
+        let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let now = Tz::UTC.timestamp_opt(elapsed.as_secs() as i64, 0).unwrap();
+        let times = Vec::column_from::<ArcColumnWrapper>(vec![now; n]);
+        let times = new_column::<Simple>("current_time", times)
+            .cast_to(SqlType::DateTime(DateTimeType::DateTime32))
+            .unwrap();
+
+        let kinds = vec![Enum8::of(INCREMENT); n];
+        let kinds = new_column::<Simple>("type", Vec::column_from::<ArcColumnWrapper>(kinds))
+            .cast_to(SqlType::Enum8(vec![
+                ("increment".to_string(), INCREMENT),
+                ("gauge".to_string(), GAUGE),
+            ]))
+            .unwrap();
+
+        Block::new()
+            .column("host_name", vec![host_name.to_string(); n])
+            .column("current_time", times)
+            .column("thread_id", vec![0_u64; n])
+            .column("type", kinds)
+            .column(
+                "name",
+                counters.iter().map(|(name, _)| *name).collect::<Vec<_>>()
+            )
+            .column(
+                "value",
+                counters.iter().map(|(_, value)| *value).collect::<Vec<_>>()
+            )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::binary::ReadEx;
+
+    #[test]
+    fn test_write_profile_events() {
+        let mut encoder = Encoder::new();
+        ProfileEventsResponse::write(
+            &mut encoder,
+            &[("SelectedRows", 10), ("SelectedBytes", 80)],
+            "localhost"
+        );
+
+        let mut cursor = Cursor::new(encoder.get_buffer_ref());
+        assert_eq!(cursor.read_uvarint().unwrap(), SERVER_PROFILE_EVENTS);
+        assert_eq!(cursor.read_string().unwrap(), "");
+        let block = Block::load(&mut cursor, Tz::UTC, false).unwrap();
+
+        let layout: Vec<(&str, String)> = block
+            .columns()
+            .iter()
+            .map(|column| (column.name(), column.sql_type().to_string().into_owned()))
+            .collect();
+        assert_eq!(layout, vec![
+            ("host_name", "String".to_string()),
+            ("current_time", "DateTime".to_string()),
+            ("thread_id", "UInt64".to_string()),
+            ("type", "Enum8('increment' = 1,'gauge' = 2)".to_string()),
+            ("name", "String".to_string()),
+            ("value", "Int64".to_string()),
+        ]);
+
+        assert_eq!(block.row_count(), 2);
+        assert_eq!(block.get::<String, _>(1, "name").unwrap(), "SelectedBytes");
+        assert_eq!(block.get::<i64, _>(1, "value").unwrap(), 80);
+    }
+}
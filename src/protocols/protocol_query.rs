@@ -2,20 +2,113 @@ use std::io::Read;
 
 use super::*;
 use crate::binary::ReadEx;
-use crate::errors::DriverError::UnknownSetting;
+use crate::errors::DriverError;
 use crate::errors::Error;
 use crate::errors::Result;
+use crate::types::Settings;
 
 const TCP: u8 = 1;
 const HTTP: u8 = 2;
 
-#[derive(Default, Debug)]
-pub struct QueryClientInfo {
+// Settings the pre-54429 binary format writes as strings rather than varints:
+// those of string, enum, char and float type in ClickHouse 19.x and 20.x.
+//
+// The list is a known limit. That format does not say how long a value is, so
+// a string setting missing here is read as a varint and the rest of the query
+// packet is misread. The client is only safe if all the settings it changes are
+// listed or numeric. Signed integer settings such as `os_thread_priority` are
+// written zigzag encoded and come out as their unsigned encoding.
+const STRING_SETTINGS: &[&str] = &[
+    "send_logs_level",
+    "load_balancing",
+    "totals_mode",
+    "totals_auto_threshold",
+    "distributed_product_mode",
+    "count_distinct_implementation",
+    "join_default_strictness",
+    "join_algorithm",
+    "special_sort",
+    "read_overflow_mode",
+    "result_overflow_mode",
+    "group_by_overflow_mode",
+    "sort_overflow_mode",
+    "timeout_overflow_mode",
+    "set_overflow_mode",
+    "join_overflow_mode",
+    "transfer_overflow_mode",
+    "distinct_overflow_mode",
+    "max_streams_to_max_threads_ratio",
+    "max_streams_multiplier_for_merge_tables",
+    "input_format_allow_errors_ratio",
+    "date_time_input_format",
+    "date_time_output_format",
+    "format_csv_delimiter",
+    "format_schema",
+    "format_template_resultset",
+    "format_template_row",
+    "format_template_rows_between_delimiter",
+    "format_custom_escaping_rule",
+    "format_custom_field_delimiter",
+    "format_custom_row_before_delimiter",
+    "format_custom_row_after_delimiter",
+    "format_custom_row_between_delimiter",
+    "format_custom_result_before_delimiter",
+    "format_custom_result_after_delimiter",
+    "format_regexp",
+    "format_regexp_escaping_rule",
+    "format_avro_schema_registry_url",
+    "output_format_avro_codec",
+    "output_format_pretty_grid_charset",
+    "log_queries_min_type",
+    "network_compression_method",
+    "default_database_engine",
+    "union_default_mode"
+];
+
+/// Reads the `name, [flags,] value` settings list up to the empty name.
+///
+/// Clients at `DBMS_MIN_REVISION_WITH_SETTINGS_SERIALIZED_AS_STRINGS` or later
+/// send every value as a string preceded by flags, older clients write each
+/// value in its native binary form.
+fn read_settings<R: Read>(reader: &mut R, client_revision: u64) -> Result<Settings> {
+    let strings = client_revision >= DBMS_MIN_REVISION_WITH_SETTINGS_SERIALIZED_AS_STRINGS;
+    let mut settings = Settings::new();
+
+    loop {
+        let name = reader.read_string()?;
+
+        if name.is_empty() {
+            break;
+        }
+
+        let value = if strings {
+            let _flags = reader.read_uvarint()?;
+            reader.read_string()?
+        } else if STRING_SETTINGS.contains(&name.as_str()) {
+            reader.read_string()?
+        } else {
+            reader.read_uvarint()?.to_string()
+        };
+        settings.insert(name, value);
+    }
+
+    Ok(settings)
+}
+
+/// Who sent the query and through which client, as carried by the `Query` packet.
+///
+/// The struct grew across protocol revisions, fields sent only by newer clients
+/// are left at their defaults for older ones.
+#[derive(Clone, Default, Debug)]
+pub struct ClientInfo {
     pub query_kind: u8,
+    /// User and id of the query the client runs this one for, e.g. the query a
+    /// distributed table fans out into its shards. Empty for direct clients.
     pub initial_user: String,
     pub initial_query_id: String,
 
     pub initial_address: String,
+    pub initial_query_start_time_microseconds: u64,
     pub interface: u8,
 
     // TCP
@@ -31,13 +124,53 @@ pub struct QueryClientInfo {
     // HTTP
     pub http_method: u8,
     pub http_user_agent: String,
+    pub forwarded_for: String,
+    pub http_referer: String,
+
+    pub quota_key: String,
+    pub distributed_depth: u64,
+
+    // OpenTelemetry
+    pub trace_context: Option<TraceContext>,
+
+    // Parallel replicas
+    pub collaborate_with_initiator: u64,
+    pub count_participating_replicas: u64,
+    pub number_of_current_replica: u64
+}
 
-    pub quota_key: String
+/// OpenTelemetry context of the client's span, the parent of the query.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct TraceContext {
+    pub trace_id: u128,
+    pub span_id: u64,
+    pub tracestate: String,
+    pub trace_flags: u8
 }
 
-impl QueryClientInfo {
-    pub fn read_from<R: Read>(reader: &mut R) -> Result<QueryClientInfo> {
-        let mut client_info = QueryClientInfo {
+impl TraceContext {
+    fn read_from<R: Read>(reader: &mut R) -> Result<TraceContext> {
+        // The trace id is a UUID, sent as its low and then its high half.
+        let low: u64 = reader.read_scalar()?;
+        let high: u64 = reader.read_scalar()?;
+        Ok(TraceContext {
+            trace_id: u128::from(high) << 64 | u128::from(low),
+            span_id: reader.read_scalar()?,
+            tracestate: reader.read_string()?,
+            trace_flags: reader.read_scalar()?
+        })
+    }
+}
+
+impl ClientInfo {
+    /// An initial query id longer than `max_string_size` fails with
+    /// `DriverError::TooLargeObject`, like the id of the query itself.
+    pub fn read_from<R: Read>(
+        reader: &mut R,
+        revision: u64,
+        max_string_size: usize
+    ) -> Result<ClientInfo> {
+        let mut client_info = ClientInfo {
             query_kind: reader.read_scalar()?,
             ..Default::default()
         };
@@ -47,8 +180,13 @@ impl QueryClientInfo {
         }
 
         client_info.initial_user = reader.read_string()?;
-        client_info.initial_query_id = reader.read_string()?;
+        client_info.initial_query_id = reader.read_bounded_string(max_string_size)?;
         client_info.initial_address = reader.read_string()?;
+
+        if revision >= DBMS_MIN_PROTOCOL_VERSION_WITH_INITIAL_QUERY_START_TIME {
+            client_info.initial_query_start_time_microseconds = reader.read_scalar()?;
+        }
+
         client_info.interface = reader.read_scalar()?;
 
         match client_info.interface {
@@ -67,50 +205,109 @@ impl QueryClientInfo {
             HTTP => {
                 client_info.http_method = reader.read_scalar()?;
                 client_info.http_user_agent = reader.read_string()?;
+
+                if revision >= DBMS_MIN_REVISION_WITH_X_FORWARDED_FOR_IN_CLIENT_INFO {
+                    client_info.forwarded_for = reader.read_string()?;
+                }
+                if revision >= DBMS_MIN_REVISION_WITH_REFERER_IN_CLIENT_INFO {
+                    client_info.http_referer = reader.read_string()?;
+                }
             }
             _ => {}
         }
 
-        if client_info.client_revision >= DBMS_MIN_REVISION_WITH_QUOTA_KEY_IN_CLIENT_INFO {
+        if revision >= DBMS_MIN_REVISION_WITH_QUOTA_KEY_IN_CLIENT_INFO {
             client_info.quota_key = reader.read_string()?;
         }
 
-        if client_info.interface == TCP
-            && client_info.client_revision >= DBMS_MIN_REVISION_WITH_VERSION_PATCH
-        {
+        if revision >= DBMS_MIN_PROTOCOL_VERSION_WITH_DISTRIBUTED_DEPTH {
+            client_info.distributed_depth = reader.read_uvarint()?;
+        }
+
+        if client_info.interface == TCP && revision >= DBMS_MIN_REVISION_WITH_VERSION_PATCH {
             client_info.client_version_patch = reader.read_uvarint()?;
         }
 
-        // TODO
-        // if client_info.client_revision >= DBMS_MIN_REVISION_WITH_OPENTELEMETRY {
-        //     let trace_id: u8 = reader.read_scalar()?;
-        //     if trace_id > 0 {
-        //     }
-        // }
+        if revision >= DBMS_MIN_REVISION_WITH_OPENTELEMETRY {
+            let have_trace_id: u8 = reader.read_scalar()?;
+            if have_trace_id != 0 {
+                client_info.trace_context = Some(TraceContext::read_from(reader)?);
+            }
+        }
+
+        if revision >= DBMS_MIN_PROTOCOL_VERSION_WITH_PARALLEL_REPLICAS {
+            client_info.collaborate_with_initiator = reader.read_uvarint()?;
+            client_info.count_participating_replicas = reader.read_uvarint()?;
+            client_info.number_of_current_replica = reader.read_uvarint()?;
+        }
 
         Ok(client_info)
     }
 }
 
+/// How far the client wants the query processed, e.g. a distributed table asks
+/// its shards for `WithMergeableState` and merges the states itself.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum QueryProcessingStage {
+    /// Only the columns are read.
+    FetchColumns = 0,
+    /// Up to the states that results from several servers can be merged from.
+    WithMergeableState = 1,
+    /// The final result.
+    #[default]
+    Complete = 2,
+    /// Like `WithMergeableState`, but after the aggregation.
+    WithMergeableStateAfterAggregation = 3,
+    /// Like `WithMergeableStateAfterAggregation`, with `LIMIT` applied too.
+    WithMergeableStateAfterAggregationAndLimit = 4
+}
+
+impl QueryProcessingStage {
+    fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(match reader.read_uvarint()? {
+            0 => QueryProcessingStage::FetchColumns,
+            1 => QueryProcessingStage::WithMergeableState,
+            2 => QueryProcessingStage::Complete,
+            3 => QueryProcessingStage::WithMergeableStateAfterAggregation,
+            4 => QueryProcessingStage::WithMergeableStateAfterAggregationAndLimit,
+            stage => return Err(Error::Driver(DriverError::UnknownStage { stage }))
+        })
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct QueryRequest {
     pub(crate) query_id: String,
-    pub(crate) client_info: QueryClientInfo,
-    pub(crate) stage: u64,
+    pub(crate) client_info: ClientInfo,
+    pub(crate) stage: QueryProcessingStage,
     pub(crate) compression: u64,
-    pub(crate) query: String
+    pub(crate) query: String,
+    pub(crate) settings: Settings,
+    pub(crate) parameters: Settings,
+    pub(crate) send_logs_level: LogsLevel,
+    pub(crate) interserver_hash: Vec<u8>
 }
 
+/// Longest query text accepted when the server does not set its own limit,
+/// the default `max_query_size` of ClickHouse.
+pub const DEFAULT_MAX_QUERY_SIZE: usize = 256 << 10;
+
 impl QueryRequest {
+    /// Query text longer than `max_query_size` bytes, or a query id longer than
+    /// `max_string_size`, fails with `DriverError::TooLargeObject` before it is
+    /// buffered. Any other query id is kept as sent, a UUID or not.
     pub fn read_from<R: Read>(
         reader: &mut R,
-        hello_request: &HelloRequest
+        hello_request: &HelloRequest,
+        client_revision: u64,
+        max_query_size: usize,
+        max_string_size: usize
     ) -> Result<QueryRequest> {
-        let query_id = reader.read_string()?;
+        let query_id = reader.read_bounded_string(max_string_size)?;
 
         let mut client_info = Default::default();
-        if hello_request.client_revision >= DBMS_MIN_REVISION_WITH_CLIENT_INFO {
-            client_info = QueryClientInfo::read_from(reader)?;
+        if client_revision >= DBMS_MIN_REVISION_WITH_CLIENT_INFO {
+            client_info = ClientInfo::read_from(reader, client_revision, max_string_size)?;
         }
 
         if client_info.query_kind == 0 {
@@ -124,31 +321,281 @@ impl QueryRequest {
 
         client_info.interface = TCP;
 
-        loop {
-            let name = reader.read_string()?;
+        let settings = read_settings(reader, client_revision)?;
 
-            if name.is_empty() {
-                break;
-            }
+        // The hash of the cluster secret, only sent by other servers. Direct
+        // clients send it empty.
+        let mut interserver_hash = Vec::new();
+        if client_revision >= DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET {
+            interserver_hash = reader.read_bounded_bytes(INTERSERVER_HASH_SIZE)?;
+        }
 
-            match name.as_str() {
-                "max_block_size" | "max_threads" => {
-                    let _ = reader.read_uvarint()?;
-                }
-                _ => {
-                    return Err(Error::Driver(UnknownSetting { name }));
-                }
-            }
+        let send_logs_level = settings
+            .get_str("send_logs_level")
+            .map_or(LogsLevel::None, |level| level.into());
+
+        let stage = QueryProcessingStage::read_from(reader)?;
+        let compression = reader.read_uvarint()?;
+        let query = reader.read_bounded_string(max_query_size)?;
+
+        let mut parameters = Settings::new();
+        if client_revision >= DBMS_MIN_PROTOCOL_VERSION_WITH_PARAMETERS {
+            parameters = read_settings(reader, client_revision)?;
         }
 
         let query_protocol = QueryRequest {
             query_id,
             client_info,
-            stage: reader.read_uvarint()?,
-            compression: reader.read_uvarint()?,
-            query: reader.read_string()?
+            stage,
+            compression,
+            query,
+            settings,
+            parameters,
+            send_logs_level,
+            interserver_hash
         };
 
         Ok(query_protocol)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::binary::Encoder;
+    use crate::binary::MAX_STRING_SIZE;
+
+    fn hello(client_revision: u64) -> HelloRequest {
+        HelloRequest {
+            client_revision,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_read_client_info() {
+        let mut encoder = Encoder::new();
+        encoder.write(INITIAL_QUERY);
+        encoder.string("default"); // initial user
+        encoder.string("f1b6e0c4"); // initial query id
+        encoder.string("[::ffff:127.0.0.1]:50414");
+        encoder.write(1_700_000_000_000_000_u64); // start time
+        encoder.write(TCP);
+        encoder.string("alice");
+        encoder.string("laptop");
+        encoder.string("ClickHouse client");
+        encoder.uvarint(22);
+        encoder.uvarint(8);
+        encoder.uvarint(54453);
+        encoder.string("tenant-42"); // quota key
+        encoder.uvarint(0); // distributed depth
+        encoder.uvarint(3); // version patch
+        encoder.write(0_u8); // no trace
+        encoder.uvarint(0);
+        encoder.uvarint(0);
+        encoder.uvarint(0);
+
+        let mut cursor = Cursor::new(encoder.get_buffer());
+        let client_info = ClientInfo::read_from(&mut cursor, 54453, MAX_STRING_SIZE).unwrap();
+        assert_eq!(client_info.query_kind, INITIAL_QUERY);
+        assert_eq!(client_info.quota_key, "tenant-42");
+        assert_eq!(client_info.os_user, "alice");
+        assert_eq!(client_info.client_revision, 54453);
+        assert_eq!(client_info.client_version_patch, 3);
+        assert_eq!(client_info.trace_context, None);
+        assert_eq!(cursor.position() as usize, cursor.get_ref().len());
+    }
+
+    #[test]
+    fn test_read_trace_context() {
+        // Synthetic client info, laid out by hand after `ClientInfo::write` of
+        // ClickHouse at revision 54442 for the traceparent
+        // 00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01.
+        let blob: &[u8] = &[
+            0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x15, 0x08, 0xaa, 0xa9, 0x03, 0x00,
+            0x03, 0x01, 0x36, 0x47, 0x0e, 0x0e, 0x9d, 0x92, 0xce, 0xa3, 0xa6, 0x4d, 0xb3, 0x77,
+            0x35, 0x2f, 0xf9, 0x4b, 0xb7, 0x02, 0xa9, 0x0b, 0xaa, 0x67, 0xf0, 0x00, 0x11, 0x63,
+            0x6f, 0x6e, 0x67, 0x6f, 0x3d, 0x74, 0x36, 0x31, 0x72, 0x63, 0x57, 0x6b, 0x67, 0x4d,
+            0x7a, 0x45, 0x01,
+        ];
+
+        let mut cursor = Cursor::new(blob);
+        let client_info = ClientInfo::read_from(&mut cursor, 54442, MAX_STRING_SIZE).unwrap();
+        assert_eq!(client_info.client_revision, 54442);
+        assert_eq!(
+            client_info.trace_context,
+            Some(TraceContext {
+                trace_id: 0x4bf92f3577b34da6a3ce929d0e0e4736,
+                span_id: 0x00f067aa0ba902b7,
+                tracestate: "congo=t61rcWkgMzE".to_string(),
+                trace_flags: 1
+            })
+        );
+        assert_eq!(cursor.position() as usize, blob.len());
+
+        // Older clients send no trace context at all.
+        let mut cursor = Cursor::new(&blob[..15]);
+        let client_info = ClientInfo::read_from(&mut cursor, 54441, MAX_STRING_SIZE).unwrap();
+        assert_eq!(client_info.trace_context, None);
+        assert_eq!(cursor.position(), 15);
+    }
+
+    #[test]
+    fn test_read_initial_query_id() {
+        let mut encoder = Encoder::new();
+        encoder.string("a7c0-shard-2");
+        encoder.write(SECONDARY_QUERY);
+        encoder.string("default"); // initial user
+        encoder.string("a7c0"); // initial query id
+        encoder.string("[::ffff:10.0.0.1]:9000");
+        encoder.write(1_700_000_000_000_000_u64); // start time
+        encoder.write(TCP);
+        encoder.string("clickhouse");
+        encoder.string("node-1");
+        encoder.string("ClickHouse server");
+        encoder.uvarint(22);
+        encoder.uvarint(8);
+        encoder.uvarint(54453);
+        encoder.string(""); // quota key
+        encoder.uvarint(1); // distributed depth
+        encoder.uvarint(3); // version patch
+        encoder.write(0_u8); // no trace
+        encoder.uvarint(0);
+        encoder.uvarint(0);
+        encoder.uvarint(0);
+        encoder.string(""); // end of settings
+        encoder.string(""); // interserver hash
+        encoder.uvarint(1);
+        encoder.uvarint(0);
+        encoder.string("SELECT count() FROM t_local");
+
+        let mut cursor = Cursor::new(encoder.get_buffer());
+        let query = QueryRequest::read_from(
+            &mut cursor,
+            &hello(54453),
+            54453,
+            DEFAULT_MAX_QUERY_SIZE,
+            MAX_STRING_SIZE
+        )
+        .unwrap();
+        assert_eq!(query.query_id, "a7c0-shard-2");
+        assert_eq!(query.client_info.query_kind, SECONDARY_QUERY);
+        assert_eq!(query.client_info.initial_query_id, "a7c0");
+        assert_eq!(query.client_info.distributed_depth, 1);
+        assert_eq!(query.query, "SELECT count() FROM t_local");
+
+        // The initial query id is bounded like the query id.
+        let mut encoder = Encoder::new();
+        encoder.write(SECONDARY_QUERY);
+        encoder.string("default");
+        encoder.string("x".repeat(17));
+        let mut cursor = Cursor::new(encoder.get_buffer());
+        let err = ClientInfo::read_from(&mut cursor, 54453, 16).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Driver(DriverError::TooLargeObject { size: 17, max_size: 16 })
+        ));
+    }
+
+    #[test]
+    fn test_read_binary_settings() {
+        let mut encoder = Encoder::new();
+        encoder.string("q1");
+        encoder.write(0_u8); // client info
+        encoder.string("max_block_size");
+        encoder.uvarint(1024);
+        encoder.string("send_logs_level");
+        encoder.string("trace");
+        encoder.string("read_overflow_mode");
+        encoder.string("break");
+        encoder.string("");
+        encoder.uvarint(2);
+        encoder.uvarint(0);
+        encoder.string("SELECT 1");
+
+        let mut cursor = Cursor::new(encoder.get_buffer());
+        let query = QueryRequest::read_from(
+            &mut cursor,
+            &hello(54428),
+            54428,
+            DEFAULT_MAX_QUERY_SIZE,
+            MAX_STRING_SIZE
+        )
+        .unwrap();
+        assert_eq!(query.settings.get_u64("max_block_size").unwrap(), Some(1024));
+        assert_eq!(query.settings.get_str("send_logs_level"), Some("trace"));
+        assert_eq!(query.settings.get_str("read_overflow_mode"), Some("break"));
+        assert_eq!(query.send_logs_level, LogsLevel::Trace);
+        assert_eq!(query.query, "SELECT 1");
+    }
+
+    #[test]
+    fn test_read_stage() {
+        let query = |stage: u64| {
+            let mut encoder = Encoder::new();
+            encoder.string("q1");
+            encoder.write(0_u8); // client info
+            encoder.string("");
+            encoder.uvarint(stage);
+            encoder.uvarint(0);
+            encoder.string("SELECT sum(x) FROM t");
+
+            let mut cursor = Cursor::new(encoder.get_buffer());
+            QueryRequest::read_from(
+                &mut cursor,
+                &hello(54428),
+                54428,
+                DEFAULT_MAX_QUERY_SIZE,
+                MAX_STRING_SIZE
+            )
+        };
+
+        assert_eq!(query(1).unwrap().stage, QueryProcessingStage::WithMergeableState);
+        assert_eq!(query(2).unwrap().stage, QueryProcessingStage::Complete);
+        assert_eq!(
+            query(4).unwrap().stage,
+            QueryProcessingStage::WithMergeableStateAfterAggregationAndLimit
+        );
+        assert!(matches!(
+            query(9),
+            Err(Error::Driver(DriverError::UnknownStage { stage: 9 }))
+        ));
+    }
+
+    #[test]
+    fn test_read_string_settings() {
+        let mut encoder = Encoder::new();
+        encoder.string("q1");
+        encoder.write(0_u8); // client info
+        encoder.string("max_result_rows");
+        encoder.uvarint(1); // important
+        encoder.string("100");
+        encoder.string("max_block_size");
+        encoder.uvarint(0);
+        encoder.string("65536");
+        encoder.string("extremes");
+        encoder.uvarint(0);
+        encoder.string("1");
+        encoder.string("");
+        encoder.uvarint(2);
+        encoder.uvarint(1);
+        encoder.string("SELECT 1");
+
+        let mut cursor = Cursor::new(encoder.get_buffer());
+        let query = QueryRequest::read_from(
+            &mut cursor,
+            &hello(54429),
+            54429,
+            DEFAULT_MAX_QUERY_SIZE,
+            MAX_STRING_SIZE
+        )
+        .unwrap();
+        assert_eq!(query.settings.len(), 3);
+        assert_eq!(query.settings.get_u64("max_result_rows").unwrap(), Some(100));
+        assert_eq!(query.settings.get_u64("max_block_size").unwrap(), Some(65536));
+        assert_eq!(query.settings.get_bool("extremes").unwrap(), Some(true));
+        assert_eq!(query.compression, 1);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::binary::Encoder;
+use crate::protocols::*;
+
+pub struct TableColumnsResponse {}
+
+impl TableColumnsResponse {
+    /// Encodes `(name, type)` pairs as a `TableColumns` packet, in the text
+    /// format of ClickHouse `ColumnsDescription`.
+    pub fn write(encoder: &mut Encoder, columns: &[(String, String)]) {
+        encoder.uvarint(SERVER_TABLE_COLUMNS);
+        encoder.string(""); // external table
+        encoder.string(Self::description(columns));
+    }
+
+    fn description(columns: &[(String, String)]) -> String {
+        let mut description = format!("columns format version: 1\n{} columns:\n", columns.len());
+        for (name, type_name) in columns {
+            let name = name.replace('\\', "\\\\").replace('`', "\\`");
+            description.push_str(&format!("`{}` {}\n", name, type_name));
+        }
+        description
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let columns = vec![
+            ("id".to_string(), "UInt64".to_string()),
+            ("we`ird".to_string(), "Nullable(String)".to_string()),
+        ];
+        let mut encoder = Encoder::new();
+        TableColumnsResponse::write(&mut encoder, &columns);
+
+        let description = "columns format version: 1\n\
+                           2 columns:\n\
+                           `id` UInt64\n\
+                           `we\\`ird` Nullable(String)\n";
+        let mut expected = vec![SERVER_TABLE_COLUMNS as u8, 0];
+        expected.push(description.len() as u8);
+        expected.extend_from_slice(description.as_bytes());
+        assert_eq!(encoder.get_buffer(), expected);
+    }
+}
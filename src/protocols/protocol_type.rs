@@ -1,35 +1,149 @@
-// Name, version, revision, default DB
-pub const CLIENT_HELLO: u64 = 0;
-// Query id, query settings, stage up to which the query must be executed,
-// whether the compression must be used,
-// query text (without data for INSERTs).
-// A block of data (compressed or not).
-pub const CLIENT_QUERY: u64 = 1;
-// A block of data (compressed or not).
-pub const CLIENT_DATA: u64 = 2;
-// Cancel the query execution.
-pub const CLIENT_CANCEL: u64 = 3;
-// Check that connection to the server is alive.
-pub const CLIENT_PING: u64 = 4;
-// Check status of tables on the server
-pub const CLIENT_TABLES_STATUS_REQUEST: u64 = 5;
-// Keep the connection alive
-pub const CLIENT_KEEP_ALIVE: u64 = 6;
-// A block of data (compressed or not)
-pub const CLIENT_SCALAR: u64 = 7;
-// List of unique parts ids to exclude from query processing
-pub const CLIENT_INGORED_PART_UUIDS: u64 = 8;
-
-pub const SERVER_HELLO: u64 = 0;
-pub const SERVER_DATA: u64 = 1;
-pub const SERVER_EXCEPTION: u64 = 2;
-pub const SERVER_PROGRESS: u64 = 3;
-pub const SERVER_PONG: u64 = 4;
-pub const SERVER_END_OF_STREAM: u64 = 5;
-pub const SERVER_PROFILE_INFO: u64 = 6;
-pub const SERVER_TOTALS: u64 = 7;
-pub const SERVER_EXTREMES: u64 = 8;
+use std::convert::TryFrom;
+
+use crate::errors::DriverError;
+use crate::errors::Error;
+
+/// Packet types a client sends, the code precedes every packet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ClientPacket {
+    // Name, version, revision, default DB
+    Hello = 0,
+    // Query id, query settings, stage up to which the query must be executed,
+    // whether the compression must be used,
+    // query text (without data for INSERTs).
+    Query = 1,
+    // A block of data (compressed or not).
+    Data = 2,
+    // Cancel the query execution.
+    Cancel = 3,
+    // Check that connection to the server is alive.
+    Ping = 4,
+    // Check status of tables on the server
+    TablesStatusRequest = 5,
+    // Keep the connection alive
+    KeepAlive = 6,
+    // A block of data (compressed or not)
+    Scalar = 7,
+    // List of unique parts ids to exclude from query processing
+    IgnoredPartUuids = 8
+}
+
+/// Packet types the server sends.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ServerPacket {
+    Hello = 0,
+    Data = 1,
+    Exception = 2,
+    Progress = 3,
+    Pong = 4,
+    EndOfStream = 5,
+    ProfileInfo = 6,
+    Totals = 7,
+    Extremes = 8,
+    TablesStatusResponse = 9,
+    Log = 10,
+    TableColumns = 11,
+    PartUuids = 12,
+    ReadTaskRequest = 13,
+    ProfileEvents = 14
+}
+
+impl TryFrom<u64> for ClientPacket {
+    type Error = Error;
+
+    fn try_from(packet: u64) -> Result<Self, Error> {
+        Ok(match packet {
+            0 => ClientPacket::Hello,
+            1 => ClientPacket::Query,
+            2 => ClientPacket::Data,
+            3 => ClientPacket::Cancel,
+            4 => ClientPacket::Ping,
+            5 => ClientPacket::TablesStatusRequest,
+            6 => ClientPacket::KeepAlive,
+            7 => ClientPacket::Scalar,
+            8 => ClientPacket::IgnoredPartUuids,
+            _ => return Err(Error::Driver(DriverError::UnknownPacket { packet }))
+        })
+    }
+}
+
+impl TryFrom<u64> for ServerPacket {
+    type Error = Error;
+
+    fn try_from(packet: u64) -> Result<Self, Error> {
+        Ok(match packet {
+            0 => ServerPacket::Hello,
+            1 => ServerPacket::Data,
+            2 => ServerPacket::Exception,
+            3 => ServerPacket::Progress,
+            4 => ServerPacket::Pong,
+            5 => ServerPacket::EndOfStream,
+            6 => ServerPacket::ProfileInfo,
+            7 => ServerPacket::Totals,
+            8 => ServerPacket::Extremes,
+            9 => ServerPacket::TablesStatusResponse,
+            10 => ServerPacket::Log,
+            11 => ServerPacket::TableColumns,
+            12 => ServerPacket::PartUuids,
+            13 => ServerPacket::ReadTaskRequest,
+            14 => ServerPacket::ProfileEvents,
+            _ => return Err(Error::Driver(DriverError::UnknownPacket { packet }))
+        })
+    }
+}
+
+pub const CLIENT_HELLO: u64 = ClientPacket::Hello as u64;
+pub const CLIENT_QUERY: u64 = ClientPacket::Query as u64;
+pub const CLIENT_DATA: u64 = ClientPacket::Data as u64;
+pub const CLIENT_CANCEL: u64 = ClientPacket::Cancel as u64;
+pub const CLIENT_PING: u64 = ClientPacket::Ping as u64;
+pub const CLIENT_TABLES_STATUS_REQUEST: u64 = ClientPacket::TablesStatusRequest as u64;
+pub const CLIENT_KEEP_ALIVE: u64 = ClientPacket::KeepAlive as u64;
+pub const CLIENT_SCALAR: u64 = ClientPacket::Scalar as u64;
+pub const CLIENT_INGORED_PART_UUIDS: u64 = ClientPacket::IgnoredPartUuids as u64;
+
+pub const SERVER_HELLO: u64 = ServerPacket::Hello as u64;
+pub const SERVER_DATA: u64 = ServerPacket::Data as u64;
+pub const SERVER_EXCEPTION: u64 = ServerPacket::Exception as u64;
+pub const SERVER_PROGRESS: u64 = ServerPacket::Progress as u64;
+pub const SERVER_PONG: u64 = ServerPacket::Pong as u64;
+pub const SERVER_END_OF_STREAM: u64 = ServerPacket::EndOfStream as u64;
+pub const SERVER_PROFILE_INFO: u64 = ServerPacket::ProfileInfo as u64;
+pub const SERVER_TOTALS: u64 = ServerPacket::Totals as u64;
+pub const SERVER_EXTREMES: u64 = ServerPacket::Extremes as u64;
+pub const SERVER_TABLES_STATUS_RESPONSE: u64 = ServerPacket::TablesStatusResponse as u64;
+pub const SERVER_LOG: u64 = ServerPacket::Log as u64;
+pub const SERVER_TABLE_COLUMNS: u64 = ServerPacket::TableColumns as u64;
+pub const SERVER_PART_UUIDS: u64 = ServerPacket::PartUuids as u64;
+pub const SERVER_READ_TASK_REQUEST: u64 = ServerPacket::ReadTaskRequest as u64;
+pub const SERVER_PROFILE_EVENTS: u64 = ServerPacket::ProfileEvents as u64;
 
 pub const NO_QUERY: u8 = 0;
 pub const INITIAL_QUERY: u8 = 1;
 pub const SECONDARY_QUERY: u8 = 2;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_try_from() {
+        for code in 0..=8 {
+            assert_eq!(ClientPacket::try_from(code).unwrap() as u64, code);
+        }
+        for code in 0..=14 {
+            assert_eq!(ServerPacket::try_from(code).unwrap() as u64, code);
+        }
+        assert_eq!(ClientPacket::try_from(CLIENT_PING).unwrap(), ClientPacket::Ping);
+        assert_eq!(ServerPacket::try_from(SERVER_PONG).unwrap(), ServerPacket::Pong);
+
+        assert!(matches!(
+            ClientPacket::try_from(9),
+            Err(Error::Driver(DriverError::UnknownPacket { packet: 9 }))
+        ));
+        assert!(matches!(
+            ServerPacket::try_from(15),
+            Err(Error::Driver(DriverError::UnknownPacket { packet: 15 }))
+        ));
+    }
+}
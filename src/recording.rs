@@ -0,0 +1,340 @@
+//! Recording of the raw traffic of a connection, and its replay against a
+//! session.
+//!
+//! Serve a client through a [`RecordingStream`] to capture what it sends and
+//! what the server answers, e.g. a real `clickhouse-client`, save it with
+//! [`Recording::write_to`], then [`replay`] the client side against a session
+//! in a test and compare the answer with [`Recording::server_bytes`].
+
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
+
+use byteorder::LittleEndian;
+use byteorder::ReadBytesExt;
+use byteorder::WriteBytesExt;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use tokio::io::ReadBuf;
+
+use crate::errors::Result;
+use crate::ClickHouseServer;
+use crate::ClickHouseSession;
+
+/// Which side of the connection sent a chunk of bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Client,
+    Server
+}
+
+impl Direction {
+    fn tag(self) -> u8 {
+        match self {
+            Direction::Client => b'C',
+            Direction::Server => b'S'
+        }
+    }
+}
+
+/// The bytes of a connection in the order they went through it, consecutive
+/// bytes of the same side form one chunk.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+    chunks: Vec<(Direction, Vec<u8>)>
+}
+
+impl Recording {
+    pub fn chunks(&self) -> &[(Direction, Vec<u8>)] {
+        &self.chunks
+    }
+
+    /// Everything the client sent.
+    pub fn client_bytes(&self) -> Vec<u8> {
+        self.bytes(Direction::Client)
+    }
+
+    /// Everything the server answered.
+    pub fn server_bytes(&self) -> Vec<u8> {
+        self.bytes(Direction::Server)
+    }
+
+    fn bytes(&self, direction: Direction) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .filter(|(side, _)| *side == direction)
+            .flat_map(|(_, bytes)| bytes.iter().copied())
+            .collect()
+    }
+
+    fn push(&mut self, direction: Direction, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        match self.chunks.last_mut() {
+            Some((side, chunk)) if *side == direction => chunk.extend_from_slice(bytes),
+            _ => self.chunks.push((direction, bytes.to_vec()))
+        }
+    }
+
+    /// Saves the recording, each chunk as its side (`C` or `S`), its length as a
+    /// little-endian `u32` and its bytes.
+    pub fn write_to<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        for (direction, bytes) in &self.chunks {
+            writer.write_u8(direction.tag())?;
+            writer.write_u32::<LittleEndian>(bytes.len() as u32)?;
+            writer.write_all(bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Loads a recording saved with [`Recording::write_to`].
+    pub fn read_from<R: io::Read>(reader: &mut R) -> Result<Recording> {
+        let mut recording = Recording::default();
+        loop {
+            let direction = match reader.read_u8() {
+                Ok(b'C') => Direction::Client,
+                Ok(b'S') => Direction::Server,
+                Ok(tag) => return Err(format!("unknown recording chunk `{}`", tag).into()),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(recording),
+                Err(e) => return Err(e.into())
+            };
+            // Grown as the bytes come, a corrupt length can't allocate them all.
+            let len = reader.read_u32::<LittleEndian>()?;
+            let mut bytes = Vec::new();
+            let mut chunk = io::Read::take(&mut *reader, u64::from(len));
+            io::Read::read_to_end(&mut chunk, &mut bytes)?;
+            if bytes.len() != len as usize {
+                return Err(format!(
+                    "recording chunk of {} bytes is truncated to {}",
+                    len,
+                    bytes.len()
+                )
+                .into());
+            }
+            recording.chunks.push((direction, bytes));
+        }
+    }
+}
+
+/// Transparent wrapper around the transport of a connection that records the
+/// bytes read from the client and written to it.
+///
+/// ```rust,no_run
+/// # use std::sync::Arc;
+/// # use clickhouse_srv::ClickHouseServer;
+/// # use clickhouse_srv::ClickHouseSession;
+/// # use clickhouse_srv::recording::RecordingStream;
+/// # async fn serve(session: Arc<dyn ClickHouseSession>, stream: tokio::net::TcpStream) {
+/// let stream = RecordingStream::new(stream);
+/// let recording = stream.recording();
+/// let _ = ClickHouseServer::run_on(session, stream).await;
+///
+/// let mut file = std::fs::File::create("handshake.bin").unwrap();
+/// recording.lock().unwrap().write_to(&mut file).unwrap();
+/// # }
+/// ```
+pub struct RecordingStream<S> {
+    inner: S,
+    recording: Arc<Mutex<Recording>>
+}
+
+impl<S> RecordingStream<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            recording: Arc::default()
+        }
+    }
+
+    /// The traffic so far, it keeps growing while the stream is in use.
+    pub fn recording(&self) -> Arc<Mutex<Recording>> {
+        self.recording.clone()
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for RecordingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            let read = &buf.filled()[before..];
+            self.recording.lock().unwrap().push(Direction::Client, read);
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for RecordingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8]
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.recording.lock().unwrap().push(Direction::Server, &buf[..written]);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Plays the client side of `recording` against `session` and returns what the
+/// server answered, which matches [`Recording::server_bytes`] when the session
+/// behaves as the recorded one did.
+///
+/// Each client chunk is sent once the server has answered everything recorded
+/// before it, as the client waited for it then. The error of the server, if it
+/// ends the connection with one, is returned instead, as is an error if the
+/// server stays silent for 5 seconds while less than recorded was answered.
+pub async fn replay(session: Arc<dyn ClickHouseSession>, recording: &Recording) -> Result<Vec<u8>> {
+    replay_with_timeout(session, recording, Duration::from_secs(5)).await
+}
+
+/// [`replay`] waiting at most `timeout` for each read from the server.
+pub async fn replay_with_timeout(
+    session: Arc<dyn ClickHouseSession>,
+    recording: &Recording,
+    timeout: Duration
+) -> Result<Vec<u8>> {
+    let (mut client, server) = tokio::io::duplex(64 * 1024);
+    let handle = tokio::spawn(ClickHouseServer::run_on(session, server));
+
+    let mut output = Vec::new();
+    for (direction, bytes) in recording.chunks() {
+        match direction {
+            Direction::Client => client.write_all(bytes).await?,
+            Direction::Server => {
+                let start = output.len();
+                let end = start + bytes.len();
+                output.resize(end, 0);
+                let mut read = start;
+                while read < end {
+                    let pending = &mut output[read..end];
+                    let n = match tokio::time::timeout(timeout, client.read(pending)).await {
+                        Ok(n) => n?,
+                        Err(_) => {
+                            handle.abort();
+                            return Err(format!(
+                                "server answered {} of the {} bytes recorded after byte {}",
+                                read - start,
+                                bytes.len(),
+                                start
+                            )
+                            .into());
+                        }
+                    };
+                    if n == 0 {
+                        break;
+                    }
+                    read += n;
+                }
+                output.truncate(read);
+                // The server closed the connection.
+                if read < end {
+                    break;
+                }
+            }
+        }
+    }
+
+    client.shutdown().await?;
+    client.read_to_end(&mut output).await?;
+    handle.await.map_err(|e| e.to_string())??;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::binary::Encoder;
+    use crate::testing::requests::hello_request_at;
+    use crate::testing::requests::query_request_at;
+    use crate::testing::EchoSession;
+    use crate::types::Block;
+
+    fn one_session() -> Arc<EchoSession> {
+        Arc::new(EchoSession::new(Block::new().column("x", vec![1_u8])))
+    }
+
+    fn hello_request() -> Vec<u8> {
+        let mut request = Encoder::new();
+        hello_request_at(&mut request, 54405);
+        request.get_buffer()
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let stream = RecordingStream::new(server);
+        let recording = stream.recording();
+        let handle = tokio::spawn(ClickHouseServer::run_on(one_session(), stream));
+
+        client.write_all(&hello_request()).await.unwrap();
+
+        let mut hello = vec![0_u8; 64];
+        let n = client.read(&mut hello).await.unwrap();
+        assert!(n > 0);
+
+        let mut request = Encoder::new();
+        query_request_at(&mut request, 54405, "SELECT 1");
+        client.write_all(&request.get_buffer()).await.unwrap();
+
+        client.shutdown().await.unwrap();
+        let mut rest = Vec::new();
+        client.read_to_end(&mut rest).await.unwrap();
+        handle.await.unwrap().unwrap();
+
+        let recording = recording.lock().unwrap().clone();
+        let served: Vec<u8> = hello[..n].iter().chain(rest.iter()).copied().collect();
+        assert_eq!(recording.server_bytes(), served);
+        assert!(recording.chunks().len() >= 3);
+
+        let mut file = Vec::new();
+        recording.write_to(&mut file).unwrap();
+        let loaded = Recording::read_from(&mut &file[..]).unwrap();
+        assert_eq!(loaded, recording);
+
+        let replayed = replay(one_session(), &loaded).await.unwrap();
+        assert_eq!(replayed, loaded.server_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_replay_short_answer() {
+        // The server answers the `Hello` with far less than recorded.
+        let mut recording = Recording::default();
+        recording.push(Direction::Client, &hello_request());
+        recording.push(Direction::Server, &[0_u8; 1024]);
+
+        let timeout = Duration::from_millis(100);
+        let err = replay_with_timeout(one_session(), &recording, timeout)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("of the 1024 bytes recorded after byte 0"));
+    }
+
+    #[test]
+    fn test_read_truncated_chunk() {
+        let file = [b'S', 0xff, 0xff, 0xff, 0xff, 1, 2, 3];
+        let err = Recording::read_from(&mut &file[..]).unwrap_err();
+        assert!(err.to_string().contains("4294967295 bytes is truncated to 3"));
+    }
+}
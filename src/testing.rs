@@ -0,0 +1,219 @@
+//! Ready-made sessions for tests of code built on this crate, enabled with the
+//! `testing` feature.
+//!
+//! ```rust,no_run
+//! # use std::sync::Arc;
+//! # use clickhouse_srv::testing::EchoSession;
+//! # use clickhouse_srv::types::Block;
+//! # use clickhouse_srv::ClickHouseServer;
+//! # async fn serve() {
+//! let (client, server) = tokio::io::duplex(4096);
+//! let session = Arc::new(EchoSession::new(Block::new().column("x", vec![1_u8])));
+//! tokio::spawn(ClickHouseServer::run_on(session, server));
+//! # }
+//! ```
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use crate::connection::Connection;
+use crate::errors::Result;
+use crate::types::Block;
+use crate::CHContext;
+use crate::ClickHouseSession;
+
+/// Answers every query with the same block.
+pub struct EchoSession {
+    block: Block
+}
+
+impl EchoSession {
+    pub fn new(block: Block) -> Self {
+        Self { block }
+    }
+}
+
+/// Answers with a single `dummy UInt8` row of 0, like `system.one`.
+impl Default for EchoSession {
+    fn default() -> Self {
+        Self::new(Block::new().column("dummy", vec![0_u8]))
+    }
+}
+
+#[async_trait::async_trait]
+impl ClickHouseSession for EchoSession {
+    async fn execute_query(&self, ctx: &mut CHContext, connection: &mut Connection) -> Result<()> {
+        connection.block_sink(ctx).push(&self.block).await
+    }
+}
+
+/// Accepts every query as an INSERT with columns of `header` and keeps the
+/// data blocks the client sends, in order, without the final empty one.
+pub struct CollectInsertSession {
+    header: Block,
+    blocks: Arc<Mutex<Vec<Block>>>
+}
+
+impl CollectInsertSession {
+    pub fn new(header: Block) -> Self {
+        Self {
+            header,
+            blocks: Arc::default()
+        }
+    }
+
+    /// The blocks inserted so far, it still fills up once the session is
+    /// handed to a server.
+    pub fn blocks(&self) -> Arc<Mutex<Vec<Block>>> {
+        self.blocks.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl ClickHouseSession for CollectInsertSession {
+    async fn execute_query(&self, ctx: &mut CHContext, connection: &mut Connection) -> Result<()> {
+        connection.start_insert(ctx, &self.header).await
+    }
+
+    async fn on_insert_block(&self, _: &mut CHContext, block: Block) -> Result<()> {
+        if !block.is_empty() {
+            self.blocks.lock().unwrap().push(block);
+        }
+        Ok(())
+    }
+}
+
+/// Client packets as `clickhouse-client` lays them out, for the tests of the
+/// crate.
+#[cfg(test)]
+pub(crate) mod requests {
+    use crate::binary::Encoder;
+    use crate::protocols::CLIENT_HELLO;
+    use crate::protocols::CLIENT_QUERY;
+    use crate::protocols::DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET;
+    use crate::protocols::DBMS_MIN_REVISION_WITH_SETTINGS_SERIALIZED_AS_STRINGS;
+    use crate::types::Block;
+
+    /// The `Hello` of a 21.8 client speaking `client_revision`, as user `default`
+    /// without a password.
+    pub(crate) fn hello_request_at(encoder: &mut Encoder, client_revision: u64) {
+        encoder.uvarint(CLIENT_HELLO);
+        encoder.string("clickhouse-client");
+        encoder.uvarint(21);
+        encoder.uvarint(8);
+        encoder.uvarint(client_revision);
+        encoder.string("default");
+        encoder.string("default");
+        encoder.string("");
+    }
+
+    // Settings are laid out for `client_revision`. Values are always written as
+    // strings, so before `DBMS_MIN_REVISION_WITH_SETTINGS_SERIALIZED_AS_STRINGS`
+    // only the names in `STRING_SETTINGS` can be sent. Query parameters of
+    // revision 54459 and later are not written.
+    pub(crate) fn query_packet(
+        encoder: &mut Encoder,
+        client_revision: u64,
+        query_id: &str,
+        settings: &[(&str, &str)],
+        compression: u64,
+        query: &str
+    ) {
+        encoder.uvarint(CLIENT_QUERY);
+        encoder.string(query_id);
+        encoder.write(0_u8); // client info, no query kind
+        for (name, value) in settings {
+            encoder.string(name);
+            if client_revision >= DBMS_MIN_REVISION_WITH_SETTINGS_SERIALIZED_AS_STRINGS {
+                encoder.uvarint(0); // flags
+            }
+            encoder.string(value);
+        }
+        encoder.string(""); // end of settings
+        if client_revision >= DBMS_MIN_REVISION_WITH_INTERSERVER_SECRET {
+            encoder.string(""); // interserver hash
+        }
+        encoder.uvarint(2); // stage
+        encoder.uvarint(compression);
+        encoder.string(query);
+    }
+
+    /// A `Query` packet without settings, followed by the empty block that ends
+    /// its temporary tables.
+    pub(crate) fn query_request_at(encoder: &mut Encoder, client_revision: u64, query: &str) {
+        query_packet(encoder, client_revision, "", &[], 0, query);
+        Block::new().send_client_data(encoder, false).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+
+    use super::requests::hello_request_at;
+    use super::requests::query_request_at;
+    use super::*;
+    use crate::binary::Encoder;
+    use crate::protocols::SERVER_DATA;
+    use crate::protocols::SERVER_END_OF_STREAM;
+    use crate::ClickHouseServer;
+
+    fn request(query: &str) -> Encoder {
+        let mut request = Encoder::new();
+        hello_request_at(&mut request, 54405);
+        query_request_at(&mut request, 54405, query);
+        request
+    }
+
+    #[tokio::test]
+    async fn test_echo_session() {
+        let block = Block::new()
+            .column("x", vec![1_u32, 2])
+            .column("s", vec!["a", "b"]);
+        let session = Arc::new(EchoSession::new(block.clone()));
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(session, server));
+
+        let request = request("SELECT x, s FROM t");
+        client.write_all(&request.get_buffer()).await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        handle.await.unwrap().unwrap();
+
+        let mut expected = Encoder::new();
+        expected.uvarint(SERVER_DATA);
+        expected.string("");
+        block.write(&mut expected, false);
+        expected.uvarint(SERVER_END_OF_STREAM);
+        assert!(response.ends_with(expected.get_buffer_ref()));
+    }
+
+    #[tokio::test]
+    async fn test_collect_insert_session() {
+        let session = Arc::new(CollectInsertSession::new(
+            Block::new().column("a", Vec::<u32>::new())
+        ));
+        let blocks = session.blocks();
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(ClickHouseServer::run_on(session, server));
+
+        let mut request = request("INSERT INTO t VALUES");
+        Block::new()
+            .column("a", vec![1_u32, 2])
+            .send_client_data(&mut request, false)
+            .unwrap();
+        Block::new().send_client_data(&mut request, false).unwrap();
+        client.write_all(&request.get_buffer()).await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        handle.await.unwrap().unwrap();
+
+        assert_eq!(response.last(), Some(&(SERVER_END_OF_STREAM as u8)));
+        let blocks = blocks.lock().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].get::<u32, _>(1, "a").unwrap(), 2);
+    }
+}
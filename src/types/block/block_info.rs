@@ -1,38 +1,42 @@
 use crate::binary::Encoder;
 use crate::binary::ReadEx;
+use crate::errors::Error;
 use crate::errors::Result;
 
-#[allow(dead_code)]
-#[derive(Copy, Clone)]
+/// Extra block properties sent before the columns, as numbered fields ended by
+/// field `0`.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct BlockInfo {
-    num1: u64,
-    is_overflows: bool,
-    num2: u64,
-    bucket_num: i32,
-    num3: u64
+    /// The block holds the rows of keys over `max_rows_to_group_by`, computed
+    /// with `group_by_overflow_mode = 'any'`.
+    pub is_overflows: bool,
+    /// Bucket of a two-level aggregation, `-1` for an ordinary block.
+    pub bucket_num: i32
 }
 
 impl Default for BlockInfo {
     fn default() -> Self {
         Self {
-            num1: 0,
             is_overflows: false,
-            num2: 0,
-            bucket_num: -1,
-            num3: 0
+            bucket_num: -1
         }
     }
 }
 
 impl BlockInfo {
     pub(crate) fn read<R: ReadEx>(reader: &mut R) -> Result<Self> {
-        let block_info = Self {
-            num1: reader.read_uvarint()?,
-            is_overflows: reader.read_scalar()?,
-            num2: reader.read_uvarint()?,
-            bucket_num: reader.read_scalar()?,
-            num3: reader.read_uvarint()?
-        };
+        let mut block_info = Self::default();
+        loop {
+            match reader.read_uvarint()? {
+                0 => break,
+                1 => block_info.is_overflows = reader.read_scalar()?,
+                2 => block_info.bucket_num = reader.read_scalar()?,
+                field => {
+                    let message = format!("Unknown block info field {}.", field);
+                    return Err(Error::Other(message.into()));
+                }
+            }
+        }
         Ok(block_info)
     }
 
@@ -44,3 +48,33 @@ impl BlockInfo {
         encoder.uvarint(0);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_write_and_read() {
+        let info = BlockInfo {
+            is_overflows: true,
+            bucket_num: 3
+        };
+        let mut encoder = Encoder::new();
+        info.write(&mut encoder);
+        assert_eq!(encoder.get_buffer_ref(), &[1_u8, 1, 2, 3, 0, 0, 0, 0]);
+
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        assert_eq!(BlockInfo::read(&mut reader).unwrap(), info);
+
+        // Fields may be missing or come in any order.
+        let mut reader = Cursor::new(&[2_u8, 7, 0, 0, 0, 0][..]);
+        let info = BlockInfo::read(&mut reader).unwrap();
+        assert_eq!(info.bucket_num, 7);
+        assert!(!info.is_overflows);
+
+        let mut reader = Cursor::new(&[3_u8, 0][..]);
+        assert!(BlockInfo::read(&mut reader).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 use std::marker;
+use std::mem;
 
 use chrono_tz::Tz;
 
@@ -12,6 +13,7 @@ use crate::types::column::ColumnData;
 use crate::types::column::Either;
 use crate::types::Column;
 use crate::types::ColumnType;
+use crate::types::DateTimeType;
 use crate::types::SqlType;
 use crate::types::Value;
 use crate::Block;
@@ -78,6 +80,24 @@ impl RowBuilder for Vec<(String, Value)> {
     }
 }
 
+/// The values of a row in column order, for a block whose columns are already
+/// there, e.g. made by `Block::empty_with_schema`. The row is only added when
+/// each value fits the type of its column.
+impl RowBuilder for Vec<Value> {
+    fn apply<K: ColumnType>(self, block: &mut Block<K>) -> Result<()> {
+        if self.len() != block.columns.len() {
+            return Err(Error::FromSql(FromSqlError::OutOfRange));
+        }
+        for (column, value) in block.columns.iter().zip(&self) {
+            check_type(column, value)?;
+        }
+        for (column, value) in block.columns.iter_mut().zip(self) {
+            column.push_unchecked(value);
+        }
+        Ok(())
+    }
+}
+
 fn put_param<K: ColumnType>(
     key: Cow<'static, str>,
     value: Value,
@@ -85,7 +105,7 @@ fn put_param<K: ColumnType>(
 ) -> Result<()> {
     let col_index = match key.as_ref().get_index(&block.columns) {
         Ok(col_index) => col_index,
-        Err(Error::FromSql(FromSqlError::OutOfRange)) => {
+        Err(Error::FromSql(FromSqlError::MissingColumn { .. })) => {
             if block.row_count() <= 1 {
                 let sql_type: SqlType = From::from(value.clone());
 
@@ -110,14 +130,53 @@ fn put_param<K: ColumnType>(
         Err(err) => return Err(err)
     };
 
-    block.columns[col_index].push(value);
+    check_type(&block.columns[col_index], &value)?;
+    block.columns[col_index].push_unchecked(value);
     Ok(())
 }
 
-fn extract_timezone(value: &Value) -> Tz {
+pub(crate) fn check_type<K: ColumnType>(column: &Column<K>, value: &Value) -> Result<()> {
+    let column_type = column.sql_type();
+    let value_type = SqlType::from(value.clone());
+    if accepts(&column_type, &value_type) {
+        Ok(())
+    } else {
+        Err(Error::FromSql(FromSqlError::InvalidType {
+            src: value_type.to_string().into_owned().into(),
+            dst: column_type.to_string().into_owned().into()
+        }))
+    }
+}
+
+/// Whether a column of `column_type` can store a value of `value_type`.
+fn accepts(column_type: &SqlType, value_type: &SqlType) -> bool {
+    match (column_type, value_type) {
+        (SqlType::Nullable(column_type), SqlType::Nullable(value_type)) => {
+            accepts(column_type, value_type)
+        }
+        (SqlType::Nullable(column_type), _) | (SqlType::LowCardinality(column_type), _) => {
+            accepts(column_type, value_type)
+        }
+        (SqlType::FixedString(_), SqlType::String)
+        | (SqlType::Json, SqlType::String)
+        | (SqlType::Object(_), SqlType::String) => true,
+        (SqlType::DateTime(column_type), SqlType::DateTime(value_type)) => {
+            let is_datetime64 = |t: &DateTimeType| matches!(t, DateTimeType::DateTime64(..));
+            is_datetime64(column_type) == is_datetime64(value_type)
+        }
+        (SqlType::Array(column_type), SqlType::Array(value_type)) => {
+            accepts(column_type, value_type)
+        }
+        _ => mem::discriminant(column_type) == mem::discriminant(value_type)
+    }
+}
+
+pub(crate) fn extract_timezone(value: &Value) -> Tz {
     match value {
         Value::Date(_, tz) => *tz,
+        Value::Date32(_, tz) => *tz,
         Value::DateTime(_, tz) => *tz,
+        Value::DateTime64(_, (_, tz)) => *tz,
         Value::Nullable(Either::Right(d)) => extract_timezone(&&d),
         Value::Array(_, data) => {
             if let Some(v) = data.first() {
@@ -138,7 +197,6 @@ mod test {
 
     use super::*;
     use crate::row;
-    use crate::types::DateTimeType;
     use crate::types::Decimal;
     use crate::types::Simple;
     use crate::types::SqlType;
@@ -209,4 +267,34 @@ mod test {
         );
         assert_eq!(block.columns[15].sql_type(), SqlType::Decimal(18, 4));
     }
+
+    #[test]
+    fn test_push_values() {
+        let schema = vec![("id", "UInt32"), ("name", "Nullable(String)"), ("at", "DateTime")];
+        let mut block = Block::<Simple>::empty_with_schema(schema).unwrap();
+        let at = UTC.with_ymd_and_hms(2014, 7, 8, 14, 0, 0).unwrap();
+        let rows: Vec<Vec<Value>> = vec![
+            vec![1_u32.into(), Some("a").into(), at.into()],
+            vec![2_u32.into(), Option::<&str>::None.into(), at.into()],
+        ];
+        for row in rows {
+            block.push(row).unwrap();
+        }
+        assert_eq!(block.row_count(), 2);
+        assert_eq!(block.get::<Option<String>, _>(0, "name").unwrap(), Some("a".to_string()));
+        assert_eq!(block.get::<Option<String>, _>(1, "name").unwrap(), None);
+
+        let row: Vec<Value> = vec!["3".into(), Some("c").into(), at.into()];
+        match block.push(row) {
+            Err(Error::FromSql(FromSqlError::InvalidType { src, dst })) => {
+                assert_eq!(src, "String");
+                assert_eq!(dst, "UInt32");
+            }
+            other => panic!("unexpected result {:?}", other)
+        }
+        let row: Vec<Value> = vec![3_u32.into()];
+        assert!(block.push(row).is_err());
+        assert!(block.push(row! { id: 3_i64 }).is_err());
+        assert!(block.columns.iter().all(|column| column.len() == 2));
+    }
 }
@@ -17,7 +17,7 @@ impl<'a, K: ColumnType> Iterator for ChunkIterator<'a, K> {
 
         if m == 0 && self.position == 0 {
             self.position += 1;
-            let mut result = Block::default();
+            let mut result = Block::default().with_info(self.block.info());
 
             for column in self.block.columns().iter() {
                 let data = column.slice(0..0);
@@ -31,7 +31,7 @@ impl<'a, K: ColumnType> Iterator for ChunkIterator<'a, K> {
             return None;
         }
 
-        let mut result = Block::new();
+        let mut result = Block::new().with_info(self.block.info());
         let size = cmp::min(self.size, m - self.position);
 
         for column in self.block.columns().iter() {
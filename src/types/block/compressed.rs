@@ -1,3 +1,4 @@
+use std::cmp;
 use std::io;
 use std::io::Read;
 use std::mem;
@@ -11,20 +12,42 @@ use clickhouse_rs_cityhash_sys::UInt128;
 use lz4::liblz4::LZ4_decompress_safe;
 
 use crate::binary::ReadEx;
+use crate::errors::DriverError;
 use crate::errors::Error;
 use crate::errors::Result;
 
 const DBMS_MAX_COMPRESSED_SIZE: u32 = 0x4000_0000; // 1GB
 
+/// Largest block a frame may decompress to unless the server sets its own
+/// limit, checked before the buffer for decompressed data is allocated.
+pub(crate) const MAX_DECOMPRESSED_SIZE: usize = 128 << 20;
+
+// LZ4 output is at most this many times larger than its input.
+const LZ4_MAX_RATIO: u64 = 255;
+
+// Method byte and both sizes, the checksum is not part of it.
+const HEADER_SIZE: usize = 9;
+
+pub(crate) const NONE_METHOD: u8 = 0x02;
+pub(crate) const LZ4_METHOD: u8 = 0x82;
+pub(crate) const ZSTD_METHOD: u8 = 0x90;
+
+const ZSTD_MAGIC: u32 = 0xFD2F_B528;
+
+// Largest block a zstd frame may hold.
+const ZSTD_MAX_BLOCK_SIZE: usize = 128 * 1024;
+
 pub(crate) struct CompressedReader<'a, R> {
     reader: &'a mut R,
-    cursor: io::Cursor<Vec<u8>>
+    cursor: io::Cursor<Vec<u8>>,
+    max_decompressed_size: usize
 }
 
-pub(crate) fn make<R>(reader: &mut R) -> CompressedReader<R> {
+pub(crate) fn make<R>(reader: &mut R, max_decompressed_size: usize) -> CompressedReader<R> {
     CompressedReader {
         reader,
-        cursor: io::Cursor::new(Vec::new())
+        cursor: io::Cursor::new(Vec::new()),
+        max_decompressed_size
     }
 }
 
@@ -41,7 +64,7 @@ where R: Read + ReadEx
         let cursor = mem::replace(&mut self.cursor, io::Cursor::new(Vec::new()));
         let buffer = cursor.into_inner();
 
-        let tmp = decompress_buffer(&mut self.reader, buffer)?;
+        let tmp = decompress_buffer(&mut self.reader, buffer, self.max_decompressed_size)?;
         self.cursor = io::Cursor::new(tmp);
         Ok(())
     }
@@ -59,45 +82,155 @@ where R: Read + ReadEx
     }
 }
 
-fn decompress_buffer<R>(reader: &mut R, mut buffer: Vec<u8>) -> Result<Vec<u8>>
-where R: ReadEx {
+fn decompress_buffer<R>(
+    reader: &mut R,
+    mut buffer: Vec<u8>,
+    max_decompressed_size: usize
+) -> Result<Vec<u8>>
+where
+    R: ReadEx
+{
     let h = UInt128 {
         lo: reader.read_scalar()?,
         hi: reader.read_scalar()?
     };
 
     let method: u8 = reader.read_scalar()?;
-    if method != 0x82 {
-        let message: String = format!("unsupported compression method {}", method);
-        return Err(raise_error(message));
+    if method != NONE_METHOD && method != LZ4_METHOD && method != ZSTD_METHOD {
+        return Err(Error::Driver(DriverError::UnsupportedCompression { method }));
     }
 
     let compressed: u32 = reader.read_scalar()?;
     let original: u32 = reader.read_scalar()?;
 
+    if compressed < HEADER_SIZE as u32 {
+        return Err(raise_error("compressed data too small".to_string()));
+    }
+
     if compressed > DBMS_MAX_COMPRESSED_SIZE {
         return Err(raise_error("compressed data too big".to_string()));
     }
 
+    if original as usize > max_decompressed_size
+        || (method == LZ4_METHOD
+            && u64::from(original) > u64::from(compressed - HEADER_SIZE as u32) * LZ4_MAX_RATIO)
+    {
+        return Err(Error::Driver(DriverError::TooLargeObject {
+            size: u64::from(original),
+            max_size: max_decompressed_size
+        }));
+    }
+
     buffer.resize(compressed as usize, 0_u8);
     {
         let mut cursor = io::Cursor::new(&mut buffer);
-        cursor.write_u8(0x82)?;
+        cursor.write_u8(method)?;
         cursor.write_u32::<LittleEndian>(compressed)?;
         cursor.write_u32::<LittleEndian>(original)?;
     }
-    reader.read_bytes(&mut buffer[9..])?;
+    reader.read_bytes(&mut buffer[HEADER_SIZE..])?;
 
     if h != city_hash_128(&buffer) {
-        return Err(raise_error("data was corrupted".to_string()));
+        return Err(Error::Driver(DriverError::ChecksumMismatch));
+    }
+
+    match method {
+        NONE_METHOD => {
+            if compressed - HEADER_SIZE as u32 != original {
+                return Err(raise_error("can't decompress data".to_string()));
+            }
+            buffer.drain(..HEADER_SIZE);
+            Ok(buffer)
+        }
+        LZ4_METHOD => decompress_lz4(&mut buffer, compressed, original),
+        _ => {
+            // The frame is all read, running out of it means it is corrupt.
+            let data = decompress_zstd_frame(&buffer[HEADER_SIZE..], original as usize)
+                .map_err(|err| match err {
+                    err if err.is_would_block() => raise_error("can't decompress data".to_string()),
+                    err => err
+                })?;
+            if data.len() != original as usize {
+                return Err(raise_error("can't decompress data".to_string()));
+            }
+            Ok(data)
+        }
+    }
+}
+
+/// Decodes a zstd frame made of raw and RLE blocks, as `write_zstd_frame` and
+/// other encoders produce for incompressible data. Blocks compressed with
+/// entropy coding fail with `DriverError::UnsupportedCompression`, no zstd
+/// decoder is linked. Decoding stops at `original` bytes.
+fn decompress_zstd_frame(frame: &[u8], original: usize) -> Result<Vec<u8>> {
+    let mut reader = io::Cursor::new(frame);
+    if reader.read_scalar::<u32>()? != ZSTD_MAGIC {
+        return Err(raise_error("can't decompress data".to_string()));
+    }
+
+    let descriptor: u8 = reader.read_scalar()?;
+    let single_segment = descriptor & 0x20 != 0;
+    let has_checksum = descriptor & 0x04 != 0;
+    let dictionary_id_size = [0, 1, 2, 4][(descriptor & 0x03) as usize];
+    let content_size_size = match descriptor >> 6 {
+        0 if single_segment => 1,
+        0 => 0,
+        1 => 2,
+        2 => 4,
+        _ => 8
+    };
+    // The window descriptor, dictionary id and content size are not needed to
+    // copy raw blocks.
+    let skipped = !single_segment as u64 + dictionary_id_size + content_size_size;
+    reader.set_position(reader.position() + skipped);
+
+    let mut data = Vec::with_capacity(original);
+    loop {
+        let mut header = [0_u8; 3];
+        reader.read_bytes(&mut header)?;
+        let header = u32::from_le_bytes([header[0], header[1], header[2], 0]);
+        let size = (header >> 3) as usize;
+        if data.len() + size > original {
+            return Err(raise_error("can't decompress data".to_string()));
+        }
+
+        match (header >> 1) & 0x03 {
+            0 => {
+                let start = data.len();
+                data.resize(start + size, 0);
+                reader.read_bytes(&mut data[start..])?;
+            }
+            1 => {
+                let byte: u8 = reader.read_scalar()?;
+                data.resize(data.len() + size, byte);
+            }
+            _ => {
+                return Err(Error::Driver(DriverError::UnsupportedCompression {
+                    method: ZSTD_METHOD
+                }))
+            }
+        }
+
+        if header & 1 == 1 {
+            break;
+        }
+    }
+
+    // The checksum is not verified, the frame has its own.
+    let trailer = if has_checksum { 4 } else { 0 };
+    if reader.position() + trailer != frame.len() as u64 {
+        return Err(raise_error("can't decompress data".to_string()));
     }
+    Ok(data)
+}
 
+fn decompress_lz4(buffer: &mut [u8], compressed: u32, original: u32) -> Result<Vec<u8>> {
     let data = vec![0_u8; original as usize];
     let status = unsafe {
         LZ4_decompress_safe(
-            (buffer.as_mut_ptr() as *const c_char).add(9),
+            (buffer.as_mut_ptr() as *const c_char).add(HEADER_SIZE),
             data.as_ptr() as *mut c_char,
-            (compressed - 9) as c_int,
+            (compressed - HEADER_SIZE as u32) as c_int,
             original as c_int
         )
     };
@@ -109,6 +242,25 @@ where R: ReadEx {
     Ok(data)
 }
 
+/// Appends `data` to `buf` as a zstd frame of raw blocks. No zstd encoder is
+/// linked, so the data is stored as is, which every zstd decoder accepts.
+pub(crate) fn write_zstd_frame(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.write_u32::<LittleEndian>(ZSTD_MAGIC).unwrap();
+    // Single segment, followed by the content size in 8 bytes.
+    buf.write_u8(0xe0).unwrap();
+    buf.write_u64::<LittleEndian>(data.len() as u64).unwrap();
+
+    let block_count = cmp::max(1, data.len().div_ceil(ZSTD_MAX_BLOCK_SIZE));
+    for index in 0..block_count {
+        let start = index * ZSTD_MAX_BLOCK_SIZE;
+        let end = cmp::min(start + ZSTD_MAX_BLOCK_SIZE, data.len());
+        let last = (index + 1 == block_count) as u32;
+        // The block type of raw blocks is 0.
+        buf.write_u24::<LittleEndian>((((end - start) as u32) << 3) | last).unwrap();
+        buf.extend_from_slice(&data[start..end]);
+    }
+}
+
 fn raise_error(message: String) -> Error {
     message.into()
 }
@@ -131,8 +283,173 @@ mod test {
         ];
 
         let mut cursor = io::Cursor::new(&source[..]);
-        let actual = decompress_buffer(&mut cursor, Vec::new()).unwrap();
+        let actual = decompress_buffer(&mut cursor, Vec::new(), MAX_DECOMPRESSED_SIZE).unwrap();
 
         assert_eq!(actual, expected);
     }
+
+    fn make_frame(method: u8, original: u32, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        frame.write_u8(method).unwrap();
+        frame
+            .write_u32::<LittleEndian>((HEADER_SIZE + payload.len()) as u32)
+            .unwrap();
+        frame.write_u32::<LittleEndian>(original).unwrap();
+        frame.extend_from_slice(payload);
+
+        let hash = city_hash_128(&frame);
+        let mut source = Vec::new();
+        source.write_u64::<LittleEndian>(hash.lo).unwrap();
+        source.write_u64::<LittleEndian>(hash.hi).unwrap();
+        source.extend(frame);
+        source
+    }
+
+    #[test]
+    fn test_decompress_checksum_mismatch() {
+        let mut source = vec![
+            245_u8, 5, 222, 235, 225, 158, 59, 108, 225, 31, 65, 215, 66, 66, 36, 92, 130, 34, 0,
+            0, 0, 23, 0, 0, 0, 240, 8, 1, 0, 2, 255, 255, 255, 255, 0, 1, 1, 1, 115, 6, 83, 116,
+            114, 105, 110, 103, 3, 97, 98, 99,
+        ];
+        let last = source.len() - 1;
+        source[last] = b'd';
+
+        let mut cursor = io::Cursor::new(&source[..]);
+        let err = decompress_buffer(&mut cursor, Vec::new(), MAX_DECOMPRESSED_SIZE).unwrap_err();
+        assert!(matches!(err, Error::Driver(DriverError::ChecksumMismatch)));
+
+        let mut cursor = io::Cursor::new(&source[..]);
+        let err = crate::types::Block::load(&mut cursor, chrono_tz::Tz::UTC, true).unwrap_err();
+        assert!(matches!(err, Error::Driver(DriverError::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn test_decompress_none_method() {
+        let expected = vec![1_u8, 2, 3];
+        let source = make_frame(NONE_METHOD, 3, &expected);
+
+        let mut cursor = io::Cursor::new(&source[..]);
+        let actual = decompress_buffer(&mut cursor, Vec::new(), MAX_DECOMPRESSED_SIZE).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_decompress_zstd_method() {
+        // Hand-built frame: no content size, a raw block of 2 bytes, then a
+        // last RLE block repeating 7 three times and a checksum.
+        let payload = [
+            0x28, 0xb5, 0x2f, 0xfd, 0x04, 0x40, 0x10, 0x00, 0x00, 0x01, 0x02, 0x1b, 0x00, 0x00,
+            0x07, 0xaa, 0xbb, 0xcc, 0xdd,
+        ];
+        let source = make_frame(ZSTD_METHOD, 5, &payload);
+
+        let mut cursor = io::Cursor::new(&source[..]);
+        let actual = decompress_buffer(&mut cursor, Vec::new(), MAX_DECOMPRESSED_SIZE).unwrap();
+        assert_eq!(actual, vec![1_u8, 2, 7, 7, 7]);
+
+        let block = crate::types::Block::new()
+            .column("a", vec![1_u32, 2, 3])
+            .column("b", vec!["x", "yz", ""]);
+        let mut encoder = crate::binary::Encoder::new();
+        block.write_compressed(
+            &mut encoder,
+            crate::connection::CompressionMethod::ZSTD,
+            Default::default()
+        );
+        let buf = encoder.get_buffer();
+        assert_eq!(buf[16], ZSTD_METHOD);
+
+        let mut cursor = io::Cursor::new(&buf[..]);
+        let actual = crate::types::Block::load(&mut cursor, chrono_tz::Tz::UTC, true).unwrap();
+        assert_eq!(actual, block);
+    }
+
+    #[test]
+    fn test_decompress_zstd_compressed_block() {
+        // A last compressed block of 1 byte, which needs a real zstd decoder.
+        let payload = [0x28, 0xb5, 0x2f, 0xfd, 0x20, 0x01, 0x0d, 0x00, 0x00, 0x00];
+        let source = make_frame(ZSTD_METHOD, 1, &payload);
+
+        let mut cursor = io::Cursor::new(&source[..]);
+        let err = decompress_buffer(&mut cursor, Vec::new(), MAX_DECOMPRESSED_SIZE).unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::Driver(DriverError::UnsupportedCompression { method: ZSTD_METHOD })
+        ));
+    }
+
+    #[test]
+    fn test_decompress_unknown_method() {
+        let source = make_frame(0x91, 1, &[0]);
+
+        let mut cursor = io::Cursor::new(&source[..]);
+        let err = decompress_buffer(&mut cursor, Vec::new(), MAX_DECOMPRESSED_SIZE).unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::Driver(DriverError::UnsupportedCompression { method: 0x91 })
+        ));
+    }
+
+    #[test]
+    fn test_write_zstd_frame() {
+        let data: Vec<u8> = (0..ZSTD_MAX_BLOCK_SIZE + 3).map(|i| i as u8).collect();
+        let mut buf = Vec::new();
+        write_zstd_frame(&mut buf, &data);
+
+        let mut cursor = io::Cursor::new(&buf[..]);
+        assert_eq!(cursor.read_scalar::<u32>().unwrap(), ZSTD_MAGIC);
+        assert_eq!(cursor.read_scalar::<u8>().unwrap(), 0xe0);
+        assert_eq!(cursor.read_scalar::<u64>().unwrap(), data.len() as u64);
+
+        let mut content = Vec::new();
+        let mut blocks = Vec::new();
+        loop {
+            let mut header = [0_u8; 3];
+            cursor.read_exact(&mut header).unwrap();
+            let header = u32::from_le_bytes([header[0], header[1], header[2], 0]);
+            let size = (header >> 3) as usize;
+            assert_eq!((header >> 1) & 3, 0);
+            blocks.push(size);
+
+            let mut block = vec![0_u8; size];
+            cursor.read_exact(&mut block).unwrap();
+            content.extend(block);
+            if header & 1 == 1 {
+                break;
+            }
+        }
+
+        assert_eq!(blocks, vec![ZSTD_MAX_BLOCK_SIZE, 3]);
+        assert_eq!(content, data);
+        assert_eq!(cursor.position() as usize, buf.len());
+    }
+
+    #[test]
+    fn test_decompress_too_big() {
+        let source = make_frame(NONE_METHOD, 1025, &[0]);
+
+        let mut cursor = io::Cursor::new(&source[..]);
+        let err = decompress_buffer(&mut cursor, Vec::new(), 1024).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Driver(DriverError::TooLargeObject {
+                size: 1025,
+                max_size: 1024
+            })
+        ));
+
+        // One byte of LZ4 data can't decompress to 1000 bytes.
+        let source = make_frame(LZ4_METHOD, 1000, &[0]);
+
+        let mut cursor = io::Cursor::new(&source[..]);
+        let err = decompress_buffer(&mut cursor, Vec::new(), 1024).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Driver(DriverError::TooLargeObject { size: 1000, .. })
+        ));
+    }
 }
@@ -1,6 +1,8 @@
 use std::cmp;
+use std::collections::HashSet;
 use std::default::Default;
 use std::fmt;
+use std::io;
 use std::io::Cursor;
 use std::io::Read;
 use std::marker::PhantomData;
@@ -14,34 +16,45 @@ use lz4::liblz4::LZ4_compressBound;
 use lz4::liblz4::LZ4_compress_default;
 
 pub use self::block_info::BlockInfo;
+pub(crate) use self::builder::check_type;
+pub(crate) use self::builder::extract_timezone;
 pub use self::builder::RCons;
 pub use self::builder::RNil;
 pub use self::builder::RowBuilder;
 use self::chunk_iterator::ChunkIterator;
+pub(crate) use self::compressed::MAX_DECOMPRESSED_SIZE;
+pub use self::nested::Nested;
+use self::nested::NestedColumn;
 pub(crate) use self::row::BlockRef;
 pub use self::row::Row;
 pub use self::row::Rows;
 use crate::binary::Encoder;
 use crate::binary::ReadEx;
+use crate::connection::CompressionMethod;
 use crate::errors::Error;
 use crate::errors::FromSqlError;
 use crate::errors::Result;
 use crate::protocols;
 use crate::types::column::ArcColumnWrapper;
 use crate::types::column::Column;
+use crate::types::column::ColumnData;
 use crate::types::column::ColumnFrom;
+use crate::types::column::SerializationSettings;
 use crate::types::column::{self};
 use crate::types::ColumnType;
 use crate::types::Complex;
 use crate::types::FromSql;
 use crate::types::Simple;
 use crate::types::SqlType;
+use crate::types::Value;
 
 mod block_info;
 mod builder;
 mod chunk_iterator;
 mod compressed;
+mod nested;
 mod row;
+mod sort;
 
 const INSERT_BLOCK_SIZE: usize = 1_048_576;
 
@@ -72,11 +85,27 @@ sliceable! {
     u16: UInt16,
     u32: UInt32,
     u64: UInt64,
+    u128: UInt128,
 
     i8: Int8,
     i16: Int16,
     i32: Int32,
-    i64: Int64
+    i64: Int64,
+    i128: Int128
+}
+
+/// Types whose values can be written into a `Block` as rows, one column per field.
+///
+/// With the `derive` feature this can be derived with `#[derive(IntoBlock)]`.
+pub trait IntoBlock: Sized {
+    fn into_block(rows: Vec<Self>) -> Block;
+}
+
+/// Types that can be read back from the rows of a `Block`, matching columns by name.
+///
+/// With the `derive` feature this can be derived with `#[derive(FromBlock)]`.
+pub trait FromBlock: Sized {
+    fn from_block(block: &Block) -> Result<Vec<Self>>;
 }
 
 /// Represents Clickhouse Block
@@ -121,8 +150,12 @@ impl<K: ColumnType> AsRef<Block<K>> for Block<K> {
 
 impl ColumnIdx for usize {
     #[inline(always)]
-    fn get_index<K: ColumnType>(&self, _: &[Column<K>]) -> Result<usize> {
-        Ok(*self)
+    fn get_index<K: ColumnType>(&self, columns: &[Column<K>]) -> Result<usize> {
+        if *self < columns.len() {
+            Ok(*self)
+        } else {
+            Err(Error::FromSql(FromSqlError::OutOfRange))
+        }
     }
 }
 
@@ -133,7 +166,9 @@ impl<'a> ColumnIdx for &'a str {
             .enumerate()
             .find(|(_, column)| column.name() == *self)
         {
-            None => Err(Error::FromSql(FromSqlError::OutOfRange)),
+            None => Err(Error::FromSql(FromSqlError::MissingColumn {
+                name: self.to_string()
+            })),
             Some((index, _)) => Ok(index)
         }
     }
@@ -160,17 +195,60 @@ impl Block {
         }
     }
 
+    /// Constructs a block of one row, e.g. the answer of a query that returns a
+    /// status. Each column gets the type of its value.
+    pub fn single_row(columns: Vec<(&str, Value)>) -> Result<Self> {
+        let row: Vec<(String, Value)> = columns
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        let mut block = Self::with_capacity(1);
+        block.push(row)?;
+        Ok(block)
+    }
+
+    /// Constructs a block without rows whose columns have the given names and
+    /// type names, such as the header sent before the data of a query.
+    pub fn empty_with_schema(columns: Vec<(&str, &str)>) -> Result<Self> {
+        let mut block = Self::new();
+        for (name, type_name) in columns {
+            let data = <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(
+                &mut io::empty(),
+                type_name,
+                0,
+                Tz::UTC,
+                Default::default()
+            )?;
+            block.append_column(column::new_column(name, data));
+        }
+        Ok(block)
+    }
+
+    #[cfg(test)]
     pub(crate) fn load<R>(reader: &mut R, tz: Tz, compress: bool) -> Result<Self>
+    where R: Read + ReadEx {
+        Self::load_with_settings(reader, tz, compress, Default::default())
+    }
+
+    /// Loads a block as a client of `settings.client_revision` writes it, its
+    /// `String` values at most `settings.max_string_size` bytes each. A longer
+    /// one fails with `DriverError::TooLargeObject` as soon as its length is read.
+    pub(crate) fn load_with_settings<R>(
+        reader: &mut R,
+        tz: Tz,
+        compress: bool,
+        settings: SerializationSettings
+    ) -> Result<Self>
     where R: Read + ReadEx {
         if compress {
-            let mut cr = compressed::make(reader);
-            Self::raw_load(&mut cr, tz)
+            let mut cr = compressed::make(reader, settings.max_decompressed_size);
+            Self::raw_load(&mut cr, tz, settings)
         } else {
-            Self::raw_load(reader, tz)
+            Self::raw_load(reader, tz, settings)
         }
     }
 
-    fn raw_load<R>(reader: &mut R, tz: Tz) -> Result<Block<Simple>>
+    fn raw_load<R>(reader: &mut R, tz: Tz, settings: SerializationSettings) -> Result<Block<Simple>>
     where R: ReadEx {
         let mut block = Block::new();
         block.info = BlockInfo::read(reader)?;
@@ -179,7 +257,7 @@ impl Block {
         let num_rows = reader.read_uvarint()?;
 
         for _ in 0..num_columns {
-            let column = Column::read(reader, num_rows as usize, tz)?;
+            let column = Column::read(reader, num_rows as usize, tz, settings)?;
             block.append_column(column);
         }
 
@@ -188,6 +266,17 @@ impl Block {
 }
 
 impl<K: ColumnType> Block<K> {
+    /// Returns the properties sent along with the columns.
+    pub fn info(&self) -> BlockInfo {
+        self.info
+    }
+
+    /// Replaces the block info, e.g. to mark the bucket of a two-level aggregation.
+    pub fn with_info(mut self, info: BlockInfo) -> Self {
+        self.info = info;
+        self
+    }
+
     /// Return the number of rows in the current block.
     pub fn row_count(&self) -> usize {
         match self.columns.first() {
@@ -218,13 +307,25 @@ impl<K: ColumnType> Block<K> {
     }
 
     /// Get the value of a particular cell of the block.
+    ///
+    /// `col` is either a column index or a column name. Out of range indices,
+    /// unknown names and values that can't be converted to `T` are errors.
     pub fn get<'a, T, I>(&'a self, row: usize, col: I) -> Result<T>
     where
         T: FromSql<'a>,
         I: ColumnIdx + Copy
     {
-        let column_index = col.get_index(self.columns())?;
-        T::from_sql(self.columns[column_index].at(row))
+        let column = &self.columns[col.get_index(self.columns())?];
+        if row >= column.len() {
+            return Err(Error::FromSql(FromSqlError::OutOfRange));
+        }
+        T::from_sql(column.at(row))
+    }
+
+    /// Get the value of a particular cell of the block by column name.
+    pub fn get_by_name<'a, T>(&'a self, row: usize, col_name: &str) -> Result<T>
+    where T: FromSql<'a> {
+        self.get(row, col_name)
     }
 
     /// Add new column into this block
@@ -243,12 +344,47 @@ impl<K: ColumnType> Block<K> {
         self
     }
 
+    /// Add new column into this block, converted to `sql_type`
+    /// (e.g. strings stored as `FixedString(N)`).
+    pub fn column_as<S>(mut self, name: &str, values: S, sql_type: SqlType) -> Result<Self>
+    where S: ColumnFrom {
+        let data = S::column_from::<ArcColumnWrapper>(values);
+        let column = column::new_column(name, data).cast_to(sql_type)?;
+
+        self.append_column(column);
+        Ok(self)
+    }
+
+    /// Adds the sub-columns of a `Nested(...)` column, stored the way ClickHouse
+    /// sends them: one array column per sub-column, named `name.sub`.
+    pub fn nested(mut self, name: &str, nested: Nested) -> Result<Self> {
+        if let Some((first, rest)) = nested.columns.split_first() {
+            for column in rest {
+                let rows = cmp::max(first.lengths.len(), column.lengths.len());
+                let mismatch = (0..rows).find(|i| first.lengths.get(*i) != column.lengths.get(*i));
+                if let Some(row) = mismatch {
+                    return Err(Error::FromSql(FromSqlError::NestedSizesMismatch {
+                        name: format!("{}.{}", name, column.column.name()),
+                        row
+                    }));
+                }
+            }
+        }
+
+        for NestedColumn { column, .. } in nested.columns {
+            let column_name = format!("{}.{}", name, column.name());
+            self.append_column(column::new_column(&column_name, column.data));
+        }
+        Ok(self)
+    }
+
     /// Returns true if the block contains no elements.
     pub fn is_empty(&self) -> bool {
         self.columns.is_empty()
     }
 
-    /// This method returns a iterator of rows.
+    /// This method returns a iterator of rows. Rows borrow the block, so no column
+    /// data is copied.
     pub fn rows(&self) -> Rows<K> {
         Rows {
             row: 0,
@@ -262,6 +398,29 @@ impl<K: ColumnType> Block<K> {
         row.apply(self)
     }
 
+    /// Returns the rows `offset..offset + len`, clamped to the rows of the block.
+    /// Like `concat`, the columns refer to the data of this block.
+    pub fn slice(&self, offset: usize, len: usize) -> Block<Complex> {
+        let start = cmp::min(offset, self.row_count());
+        let end = cmp::min(start.saturating_add(len), self.row_count());
+
+        Block {
+            info: self.info,
+            columns: self.columns.iter().map(|column| column.slice(start..end)).collect(),
+            capacity: end - start
+        }
+    }
+
+    /// Column names and types, e.g. `a UInt8, b String`.
+    fn schema(&self) -> String {
+        let columns: Vec<_> = self
+            .columns
+            .iter()
+            .map(|column| format!("{} {}", column.name(), column.sql_type()))
+            .collect();
+        columns.join(", ")
+    }
+
     /// This method finds a column by identifier.
     pub fn get_column<I>(&self, col: I) -> Result<&Column<K>>
     where I: ColumnIdx + Copy {
@@ -269,18 +428,53 @@ impl<K: ColumnType> Block<K> {
         let column = &self.columns[column_index];
         Ok(column)
     }
+
+    /// Returns the columns in the order of `names`, which must name each column
+    /// once. A missing name fails with `FromSqlError::MissingColumn`, a column
+    /// left out or named twice with `FromSqlError::SchemaMismatch`. The columns
+    /// refer to the data of this block.
+    pub fn reorder_columns(&self, names: &[&str]) -> Result<Block<K>> {
+        let mut indices = Vec::with_capacity(names.len());
+        for name in names {
+            let index = name.get_index(self.columns())?;
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+
+        if indices.len() != names.len() || indices.len() != self.column_count() {
+            return Err(Error::FromSql(FromSqlError::SchemaMismatch {
+                src: self.schema(),
+                dst: names.join(", ")
+            }));
+        }
+
+        Ok(Block {
+            info: self.info,
+            columns: indices.into_iter().map(|index| self.columns[index].clone()).collect(),
+            capacity: self.capacity
+        })
+    }
 }
 
 impl Block<Simple> {
-    pub(crate) fn concat(blocks: &[Self]) -> Block<Complex> {
-        let first = blocks.first().expect("blocks should not be empty.");
-
+    /// Concatenates blocks with the same column names and types. The columns
+    /// refer to the data of `blocks` instead of copying it.
+    pub fn concat(blocks: &[Self]) -> Result<Block<Complex>> {
+        let first = match blocks.first() {
+            None => return Ok(Block::default()),
+            Some(first) => first
+        };
+
+        let schema = first.schema();
         for block in blocks {
-            assert_eq!(
-                first.column_count(),
-                block.column_count(),
-                "all columns should have the same size."
-            );
+            let block_schema = block.schema();
+            if block_schema != schema {
+                return Err(Error::FromSql(FromSqlError::SchemaMismatch {
+                    src: block_schema,
+                    dst: schema
+                }));
+            }
         }
 
         let num_columns = first.column_count();
@@ -290,11 +484,11 @@ impl Block<Simple> {
             columns.push(Column::concat(chunks));
         }
 
-        Block {
+        Ok(Block {
             info: first.info,
             columns,
             capacity: blocks.iter().map(|b| b.capacity).sum()
-        }
+        })
     }
 }
 
@@ -325,60 +519,130 @@ impl<K: ColumnType> Block<K> {
 
     pub(crate) fn write(&self, encoder: &mut Encoder, compress: bool) {
         if compress {
-            let mut tmp_encoder = Encoder::new();
-            self.write(&mut tmp_encoder, false);
-            let tmp = tmp_encoder.get_buffer();
-
-            let mut buf = Vec::new();
-            let size;
-            unsafe {
-                buf.resize(9 + LZ4_compressBound(tmp.len() as i32) as usize, 0_u8);
-                size = LZ4_compress_default(
-                    tmp.as_ptr() as *const c_char,
-                    (buf.as_mut_ptr() as *mut c_char).add(9),
-                    tmp.len() as i32,
-                    buf.len() as i32
-                );
-            }
-            buf.resize(9 + size as usize, 0_u8);
-
-            let buf_len = buf.len() as u32;
-            {
-                let mut cursor = Cursor::new(&mut buf);
-                cursor.write_u8(0x82).unwrap();
-                cursor.write_u32::<LittleEndian>(buf_len).unwrap();
-                cursor.write_u32::<LittleEndian>(tmp.len() as u32).unwrap();
-            }
-
-            let hash = city_hash_128(&buf);
-            encoder.write(hash.lo);
-            encoder.write(hash.hi);
-            encoder.write_bytes(buf.as_ref());
+            self.write_compressed(encoder, CompressionMethod::LZ4, Default::default());
         } else {
-            self.info.write(encoder);
-            encoder.uvarint(self.column_count() as u64);
-            encoder.uvarint(self.row_count() as u64);
+            self.write_plain(encoder, Default::default());
+        }
+    }
+
+    // Each column is written the way a client of `settings.client_revision`
+    // reads it, which may differ from its type.
+    fn write_plain(&self, encoder: &mut Encoder, settings: SerializationSettings) {
+        self.info.write(encoder);
+        encoder.uvarint(self.column_count() as u64);
+        encoder.uvarint(self.row_count() as u64);
+
+        for column in &self.columns {
+            column.write(encoder, settings);
+        }
+    }
 
-            for column in &self.columns {
-                column.write(encoder);
+    /// Writes the block as a single frame compressed with `method`, which must
+    /// be enabled.
+    pub(crate) fn write_compressed(
+        &self,
+        encoder: &mut Encoder,
+        method: CompressionMethod,
+        settings: SerializationSettings
+    ) {
+        let mut tmp_encoder = Encoder::new();
+        self.write_plain(&mut tmp_encoder, settings);
+        let tmp = tmp_encoder.get_buffer();
+
+        let mut buf = vec![0_u8; 9];
+        let method_byte = match method {
+            CompressionMethod::ZSTD => {
+                compressed::write_zstd_frame(&mut buf, &tmp);
+                compressed::ZSTD_METHOD
+            }
+            _ => {
+                let size;
+                unsafe {
+                    buf.resize(9 + LZ4_compressBound(tmp.len() as i32) as usize, 0_u8);
+                    size = LZ4_compress_default(
+                        tmp.as_ptr() as *const c_char,
+                        (buf.as_mut_ptr() as *mut c_char).add(9),
+                        tmp.len() as i32,
+                        (buf.len() - 9) as i32
+                    );
+                }
+                buf.truncate(9 + size as usize);
+                compressed::LZ4_METHOD
             }
+        };
+
+        let buf_len = buf.len() as u32;
+        {
+            let mut cursor = Cursor::new(&mut buf);
+            cursor.write_u8(method_byte).unwrap();
+            cursor.write_u32::<LittleEndian>(buf_len).unwrap();
+            cursor.write_u32::<LittleEndian>(tmp.len() as u32).unwrap();
         }
+
+        let hash = city_hash_128(&buf);
+        encoder.write(hash.lo);
+        encoder.write(hash.hi);
+        encoder.write_bytes(buf.as_ref());
+    }
+
+    pub(crate) fn send_client_data(&self, encoder: &mut Encoder, compress: bool) -> Result<()> {
+        let compression = if compress {
+            CompressionMethod::LZ4
+        } else {
+            CompressionMethod::None
+        };
+        self.send_data(encoder, protocols::CLIENT_DATA, compression, Default::default())
     }
 
-    pub(crate) fn send_client_data(&self, encoder: &mut Encoder, compress: bool) {
-        encoder.uvarint(protocols::CLIENT_DATA);
-        encoder.string(""); // temporary table
+    // Every chunk is a standalone `Data` packet, the peer reads exactly one
+    // (possibly compressed) block after each packet header.
+    pub(crate) fn send_data(
+        &self,
+        encoder: &mut Encoder,
+        packet: u64,
+        compression: CompressionMethod,
+        settings: SerializationSettings
+    ) -> Result<()> {
+        self.check_consistency()?;
+
         for chunk in self.chunks(INSERT_BLOCK_SIZE) {
-            chunk.write(encoder, compress);
+            encoder.uvarint(packet);
+            encoder.string(""); // temporary table
+            if compression.is_enabled() {
+                chunk.write_compressed(encoder, compression, settings);
+            } else {
+                chunk.write_plain(encoder, settings);
+            }
         }
+        Ok(())
     }
 
-    pub(crate) fn send_server_data(&self, encoder: &mut Encoder, compress: bool) {
-        encoder.uvarint(protocols::SERVER_DATA);
-        encoder.string(""); // temporary table
-        for chunk in self.chunks(INSERT_BLOCK_SIZE) {
-            chunk.write(encoder, compress);
+    /// A client can't read a block whose columns differ in length, nor find
+    /// its columns by name unless the names are set and unique.
+    fn check_consistency(&self) -> Result<()> {
+        let inconsistent = |reason: String| {
+            Err(Error::FromSql(FromSqlError::InconsistentBlock { reason }))
+        };
+
+        let rows = self.columns.first().map_or(0, Column::len);
+        let mut names = HashSet::new();
+        for column in &self.columns {
+            if column.len() != rows {
+                return inconsistent(format!(
+                    "column `{}` has {} rows, expected {}",
+                    column.name(),
+                    column.len(),
+                    rows
+                ));
+            }
+            if column.name().is_empty() {
+                return inconsistent("a column has no name".to_string());
+            }
+            if !names.insert(column.name()) {
+                return inconsistent(format!("column `{}` is duplicated", column.name()));
+            }
         }
+        Ok(())
     }
 
     pub(crate) fn chunks(&self, n: usize) -> ChunkIterator<K> {
@@ -452,6 +716,40 @@ fn text_cells<K: ColumnType>(data: &Column<K>) -> Vec<String> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::errors::DriverError;
+    use crate::types::StringColumnData;
+    use crate::types::VectorColumnData;
+
+    #[test]
+    fn test_get() {
+        let block = Block::<Simple>::new()
+            .column("id", vec![1_i64, -2])
+            .column("name", vec!["a", "b"]);
+
+        assert_eq!(block.get::<i64, _>(1, 0).unwrap(), -2);
+        assert_eq!(block.get_by_name::<i64>(0, "id").unwrap(), 1);
+        assert_eq!(block.get_by_name::<String>(1, "name").unwrap(), "b");
+
+        match block.get::<i64, _>(0, 1) {
+            Err(Error::FromSql(FromSqlError::InvalidType { src, dst })) => {
+                assert_eq!(src, "String");
+                assert_eq!(dst, "i64");
+            }
+            other => panic!("unexpected result {:?}", other)
+        }
+        assert!(matches!(
+            block.get::<i64, _>(2, 0),
+            Err(Error::FromSql(FromSqlError::OutOfRange))
+        ));
+        assert!(matches!(
+            block.get::<i64, _>(0, 2),
+            Err(Error::FromSql(FromSqlError::OutOfRange))
+        ));
+        assert!(matches!(
+            block.get_by_name::<i64>(0, "missing"),
+            Err(Error::FromSql(FromSqlError::MissingColumn { .. }))
+        ));
+    }
 
     #[test]
     fn test_write_default() {
@@ -461,6 +759,29 @@ mod test {
         assert_eq!(encoder.get_buffer_ref(), &expected)
     }
 
+    #[test]
+    fn test_block_info() {
+        let info = BlockInfo {
+            is_overflows: false,
+            bucket_num: 3
+        };
+        let block = Block::new().column("a", vec![1_u32, 2]).with_info(info);
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        assert_eq!(&encoder.get_buffer_ref()[..8], &[1_u8, 0, 2, 3, 0, 0, 0, 0]);
+
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+        assert_eq!(rblock.info(), info);
+        assert_eq!(rblock, block);
+
+        for chunk in block.chunks(1) {
+            assert_eq!(chunk.info(), info);
+        }
+        assert_eq!(block.slice(1, 1).info(), info);
+    }
+
     #[test]
     fn test_compress_block() {
         let expected = vec![
@@ -494,6 +815,71 @@ mod test {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_send_server_data_in_chunks() {
+        let block = Block::<Simple>::new().column("A", vec![1_u8; INSERT_BLOCK_SIZE + 1]);
+
+        let mut encoder = Encoder::new();
+        block
+            .send_data(
+                &mut encoder,
+                protocols::SERVER_DATA,
+                CompressionMethod::LZ4,
+                Default::default()
+            )
+            .unwrap();
+
+        let mut cursor = Cursor::new(encoder.get_buffer_ref());
+        let mut rows = Vec::new();
+        while (cursor.position() as usize) < cursor.get_ref().len() {
+            assert_eq!(cursor.read_uvarint().unwrap(), protocols::SERVER_DATA);
+            assert_eq!(cursor.read_string().unwrap(), "");
+            let chunk = Block::load(&mut cursor, Tz::Zulu, true).unwrap();
+            rows.push(chunk.row_count());
+        }
+
+        assert_eq!(rows, vec![INSERT_BLOCK_SIZE, 1]);
+    }
+
+    #[test]
+    fn test_send_inconsistent_block() {
+        let ragged = Block::<Simple> {
+            info: BlockInfo::default(),
+            columns: vec![
+                column::new_column("a", Vec::column_from::<ArcColumnWrapper>(vec![1_u8, 2, 3])),
+                column::new_column("b", Vec::column_from::<ArcColumnWrapper>(vec![1_u8, 2])),
+            ],
+            capacity: 3
+        };
+        let unnamed = Block::<Simple>::new().column("", vec![1_u8]);
+        let duplicated = Block::<Simple>::new()
+            .column("a", vec![1_u8])
+            .column("a", vec![2_u8]);
+
+        for (block, reason) in &[
+            (ragged, "column `b` has 2 rows, expected 3"),
+            (unnamed, "a column has no name"),
+            (duplicated, "column `a` is duplicated"),
+        ] {
+            let mut encoder = Encoder::new();
+            let err = block
+                .send_data(
+                    &mut encoder,
+                    protocols::SERVER_DATA,
+                    CompressionMethod::None,
+                    Default::default()
+                )
+                .unwrap_err();
+            match err {
+                Error::FromSql(FromSqlError::InconsistentBlock { reason: actual }) => {
+                    assert_eq!(actual, *reason)
+                }
+                _ => unreachable!()
+            }
+            assert!(encoder.get_buffer_ref().is_empty());
+        }
+    }
+
     #[test]
     fn test_read_empty_block() {
         let source = [1, 0, 2, 255, 255, 255, 255, 0, 0, 0];
@@ -534,7 +920,7 @@ mod test {
         let block_a = make_block();
         let block_b = make_block();
 
-        let actual = Block::concat(&[block_a, block_b]);
+        let actual = Block::concat(&[block_a, block_b]).unwrap();
         assert_eq!(actual.row_count(), 4);
         assert_eq!(actual.column_count(), 1);
 
@@ -556,6 +942,49 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_concat_schema_mismatch() {
+        let block_a = Block::new().column("a", vec![1_u32]);
+        let block_b = Block::new().column("a", vec!["x"]);
+        let block_c = Block::new().column("b", vec![2_u32]);
+
+        for other in [block_b, block_c].iter() {
+            match Block::concat(&[block_a.clone(), other.clone()]) {
+                Err(Error::FromSql(FromSqlError::SchemaMismatch { dst, .. })) => {
+                    assert_eq!(dst, "a UInt32")
+                }
+                other => panic!("unexpected result {:?}", other.map(|b| b.row_count()))
+            }
+        }
+
+        assert_eq!(Block::concat(&[]).unwrap().row_count(), 0);
+    }
+
+    #[test]
+    fn test_slice() {
+        let block = Block::new()
+            .column("id", vec![1_u32, 2, 3, 4])
+            .column("name", vec!["a", "b", "c", "d"]);
+
+        let slice = block.slice(1, 2);
+        assert_eq!(slice.row_count(), 2);
+        assert_eq!(slice.get::<u32, _>(0, "id").unwrap(), 2);
+        assert_eq!(slice.get::<String, _>(1, "name").unwrap(), "c");
+
+        assert_eq!(block.slice(3, 10).row_count(), 1);
+        assert_eq!(block.slice(10, 1).row_count(), 0);
+        assert_eq!(block.slice(10, 1).column_count(), 2);
+
+        let mut encoder = Encoder::new();
+        slice.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+        let expected = Block::new()
+            .column("id", vec![2_u32, 3])
+            .column("name", vec!["b", "c"]);
+        assert_eq!(rblock, expected);
+    }
+
     fn make_block() -> Block {
         Block::new().column("9b96ad8b-488a-4fef-8087-8a9ae4800f00", vec![
             "5446d186-4e90-4dd8-8ec1-f9a436834613".to_string(),
@@ -605,4 +1034,139 @@ mod test {
 
         assert_eq!(block, rblock);
     }
+
+    #[test]
+    fn test_downcast_column() {
+        let block = Block::<Simple>::new()
+            .column("id", vec![1_i64, -2])
+            .column("name", vec!["a", "bc"])
+            .column("note", vec![Some("x"), None]);
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let block = Block::<Simple>::load(&mut reader, Tz::Zulu, false).unwrap();
+
+        let names = block.columns()[1].downcast_ref::<StringColumnData>().unwrap();
+        assert_eq!(names.get(1), Some(&b"bc"[..]));
+        assert_eq!(names.get(2), None);
+        assert_eq!(names.iter().collect::<Vec<_>>(), vec![&b"a"[..], &b"bc"[..]]);
+
+        let ids = block.columns()[0].downcast_ref::<VectorColumnData<i64>>().unwrap();
+        assert_eq!(ids.as_slice(), &[1, -2]);
+
+        assert!(block.columns()[0].downcast_ref::<StringColumnData>().is_none());
+        assert!(block.columns()[0].downcast_ref::<VectorColumnData<u64>>().is_none());
+        assert!(block.columns()[2].downcast_ref::<StringColumnData>().is_none());
+
+        assert_eq!(block.columns()[0].column_type(), "Int64");
+        assert_eq!(block.columns()[2].column_type(), "Nullable(String)");
+    }
+
+    #[test]
+    fn test_load_bounded() {
+        let settings = SerializationSettings {
+            max_string_size: 4,
+            ..Default::default()
+        };
+        let block = Block::<Simple>::new()
+            .column("s", vec!["ab", "abcd"])
+            .column("a", vec![vec!["abc"], vec!["abcd", ""]]);
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load_with_settings(&mut reader, Tz::Zulu, false, settings).unwrap();
+        assert_eq!(rblock, block);
+
+        let block = block.column("tail", vec![vec!["abcde"], vec![]]);
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let err = Block::load_with_settings(&mut reader, Tz::Zulu, false, settings).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Driver(DriverError::TooLargeObject { size: 5, max_size: 4 })
+        ));
+    }
+
+    #[test]
+    fn test_single_row() {
+        let block = Block::single_row(vec![
+            ("ok", Value::UInt8(1)),
+            ("message", Value::from("done")),
+        ])
+        .unwrap();
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+        assert_eq!(rblock.row_count(), 1);
+        assert_eq!(rblock.columns()[1].sql_type(), SqlType::String);
+        assert_eq!(rblock.get::<u8, _>(0, "ok").unwrap(), 1);
+        assert_eq!(rblock.get::<String, _>(0, "message").unwrap(), "done");
+    }
+
+    #[test]
+    fn test_empty_with_schema() {
+        let block = Block::empty_with_schema(vec![
+            ("id", "UInt64"),
+            ("name", "Nullable(String)"),
+            ("tags", "Array(LowCardinality(String))"),
+            ("attrs", "Map(String, UInt32)"),
+        ])
+        .unwrap();
+        assert_eq!(block.row_count(), 0);
+        let types: Vec<String> = block
+            .columns()
+            .iter()
+            .map(|column| column.sql_type().to_string().into_owned())
+            .collect();
+        assert_eq!(types, vec![
+            "UInt64",
+            "Nullable(String)",
+            "Array(LowCardinality(String))",
+            "Map(String, UInt32)",
+        ]);
+
+        let header = Block::new().column("id", Vec::<u64>::new());
+        let mut expected = Encoder::new();
+        header.write(&mut expected, false);
+        let mut actual = Encoder::new();
+        Block::empty_with_schema(vec![("id", "UInt64")])
+            .unwrap()
+            .write(&mut actual, false);
+        assert_eq!(actual.get_buffer_ref(), expected.get_buffer_ref());
+
+        assert!(matches!(
+            Block::empty_with_schema(vec![("x", "Unknown")]),
+            Err(Error::FromSql(FromSqlError::UnknownType { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_reorder_columns() {
+        let block = Block::<Simple>::new()
+            .column("b", vec![2_u8])
+            .column("c", vec!["x"])
+            .column("a", vec![1_u64]);
+
+        let reordered = block.reorder_columns(&["a", "b", "c"]).unwrap();
+        let names: Vec<&str> = reordered.columns().iter().map(Column::name).collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        assert_eq!(reordered.get::<u64, _>(0, 0).unwrap(), 1);
+        assert_eq!(reordered.get::<&str, _>(0, "c").unwrap(), "x");
+
+        assert!(matches!(
+            block.reorder_columns(&["a", "b", "d"]),
+            Err(Error::FromSql(FromSqlError::MissingColumn { name })) if name == "d"
+        ));
+        for names in &[&["a", "b"][..], &["a", "b", "c", "a"], &["a", "a", "b"]] {
+            assert!(matches!(
+                block.reorder_columns(names),
+                Err(Error::FromSql(FromSqlError::SchemaMismatch { .. }))
+            ));
+        }
+    }
 }
@@ -0,0 +1,94 @@
+use crate::types::column::new_column;
+use crate::types::column::ArcColumnWrapper;
+use crate::types::column::Column;
+use crate::types::column::ColumnFrom;
+use crate::types::Simple;
+
+/// The sub-columns of a `Nested(...)` column, added with [`Block::nested`].
+///
+/// [`Block::nested`]: crate::types::Block::nested
+#[derive(Default)]
+pub struct Nested {
+    pub(crate) columns: Vec<NestedColumn>
+}
+
+pub(crate) struct NestedColumn {
+    pub(crate) lengths: Vec<usize>,
+    pub(crate) column: Column<Simple>
+}
+
+impl Nested {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a sub-column with the values of every row. All sub-columns must
+    /// have as many values as each other in every row.
+    pub fn column<T>(mut self, name: &str, values: Vec<Vec<T>>) -> Self
+    where Vec<Vec<T>>: ColumnFrom {
+        let lengths = values.iter().map(Vec::len).collect();
+        let data = Vec::<Vec<T>>::column_from::<ArcColumnWrapper>(values);
+        self.columns.push(NestedColumn {
+            lengths,
+            column: new_column(name, data)
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use chrono_tz::Tz;
+
+    use super::*;
+    use crate::binary::Encoder;
+    use crate::errors::Error;
+    use crate::errors::FromSqlError;
+    use crate::types::Block;
+
+    #[test]
+    fn test_write_and_read() {
+        let block = Block::new()
+            .column("id", vec![1_u64, 2])
+            .nested(
+                "n",
+                Nested::new()
+                    .column("a", vec![vec![1_u32, 2], vec![3]])
+                    .column("b", vec![vec!["x", "y"], vec!["z"]])
+            )
+            .unwrap();
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+
+        let columns: Vec<_> = rblock
+            .columns()
+            .iter()
+            .map(|column| format!("{} {}", column.name(), column.sql_type()))
+            .collect();
+        assert_eq!(columns, vec!["id UInt64", "n.a Array(UInt32)", "n.b Array(String)"]);
+        assert_eq!(block, rblock);
+
+        assert_eq!(rblock.get::<Vec<u32>, _>(0, "n.a").unwrap(), vec![1, 2]);
+        assert_eq!(rblock.get::<Vec<String>, _>(1, "n.b").unwrap(), vec!["z"]);
+    }
+
+    #[test]
+    fn test_sizes_mismatch() {
+        let nested = Nested::new()
+            .column("a", vec![vec![1_u32, 2], vec![3]])
+            .column("b", vec![vec!["x"], vec!["z"]]);
+
+        match Block::new().nested("n", nested) {
+            Err(Error::FromSql(FromSqlError::NestedSizesMismatch { name, row })) => {
+                assert_eq!(name, "n.b");
+                assert_eq!(row, 0);
+            }
+            other => panic!("unexpected result {:?}", other.map(|b| b.row_count()))
+        }
+    }
+}
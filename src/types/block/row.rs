@@ -18,6 +18,8 @@ pub struct Row<'a, K: ColumnType> {
 
 impl<'a, K: ColumnType> Row<'a, K> {
     /// Get the value of a particular cell of the row.
+    ///
+    /// `&str` and `&[u8]` borrow the cell from the block, without copying it.
     pub fn get<T, I>(&'a self, col: I) -> Result<T>
     where
         T: FromSql<'a>,
@@ -26,6 +28,12 @@ impl<'a, K: ColumnType> Row<'a, K> {
         self.block_ref.get(self.row, col)
     }
 
+    /// Get the value of a particular cell of the row by column name.
+    pub fn get_by_name<T>(&'a self, col_name: &str) -> Result<T>
+    where T: FromSql<'a> {
+        self.get(col_name)
+    }
+
     /// Return the number of cells in the current row.
     pub fn len(&self) -> usize {
         self.block_ref.column_count()
@@ -124,3 +132,87 @@ impl<'a, K: ColumnType> Iterator for Rows<'a, K> {
         result
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use chrono_tz::Tz;
+
+    use crate::binary::Encoder;
+    use crate::errors::Error;
+    use crate::errors::FromSqlError;
+    use crate::row;
+    use crate::types::Block;
+    use crate::types::Simple;
+
+    #[test]
+    fn test_rows() {
+        let block = Block::<Simple>::new()
+            .column("id", vec![1_u32, 2, 3])
+            .column("name", vec!["a", "b", "c"]);
+
+        let mut rows = Vec::new();
+        for row in block.rows() {
+            let id: u32 = row.get(0).unwrap();
+            let name: String = row.get_by_name("name").unwrap();
+            rows.push((id, name));
+        }
+        assert_eq!(rows, vec![
+            (1, "a".to_string()),
+            (2, "b".to_string()),
+            (3, "c".to_string()),
+        ]);
+
+        let row = block.rows().nth(1).unwrap();
+        assert_eq!(row.len(), 2);
+        assert_eq!(row.name(1).unwrap(), "name");
+    }
+
+    #[test]
+    fn test_empty_block() {
+        let block = Block::<Simple>::new();
+        assert_eq!(block.rows().count(), 0);
+    }
+
+    #[test]
+    fn test_ragged_block() {
+        let mut block = Block::<Simple>::new();
+        block.push(row! { id: 1_u32, name: "a" }).unwrap();
+        block.push(row! { id: 2_u32 }).unwrap();
+
+        let row = block.rows().nth(1).unwrap();
+        assert_eq!(row.get::<u32, _>("id").unwrap(), 2);
+        assert!(matches!(
+            row.get::<String, _>("name"),
+            Err(Error::FromSql(FromSqlError::OutOfRange))
+        ));
+    }
+
+    #[test]
+    fn test_borrowed_strings() {
+        let names = vec!["alpha", "", "gamma", "delta"];
+        let mut encoder = Encoder::new();
+        Block::<Simple>::new()
+            .column("name", names.clone())
+            .write(&mut encoder, false);
+
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let block = Block::<Simple>::load(&mut reader, Tz::Zulu, false).unwrap();
+
+        let rows: Vec<_> = block.rows().collect();
+        let slices: Vec<&[u8]> = rows.iter().map(|row| row.get(0).unwrap()).collect();
+        for (row, slice) in rows.iter().zip(&slices) {
+            let name: &str = row.get(0).unwrap();
+            assert_eq!(name.as_ptr(), slice.as_ptr());
+        }
+
+        // The values sit next to each other in the buffer the column was read into.
+        for pair in slices.windows(2) {
+            assert_eq!(pair[0].as_ptr_range().end, pair[1].as_ptr());
+        }
+
+        let owned: Vec<String> = rows.iter().map(|row| row.get(0).unwrap()).collect();
+        assert_eq!(owned, names);
+    }
+}
@@ -0,0 +1,146 @@
+use std::cmp::Ordering;
+
+use crate::errors::Error;
+use crate::errors::FromSqlError;
+use crate::errors::Result;
+use crate::types::block::ColumnIdx;
+use crate::types::column::Either;
+use crate::types::Block;
+use crate::types::ColumnType;
+use crate::types::ValueRef;
+
+impl<K: ColumnType> Block<K> {
+    /// Returns the rows ordered by the column `col`, every other column follows
+    /// the same order. Equal keys keep their order and NULLs come last in both
+    /// directions, as in ClickHouse.
+    ///
+    /// Only numbers, strings, dates, decimals and enums can be sorted by, other
+    /// key columns fail with `FromSqlError::UnsupportedOperation`.
+    pub fn sort_by<I>(&self, col: I, descending: bool) -> Result<Block<K>>
+    where I: ColumnIdx + Copy {
+        let key = self.get_column(col)?;
+        let values: Vec<ValueRef> = (0..key.len()).map(|index| key.at(index)).collect();
+
+        let mut supported = true;
+        let mut indices: Vec<usize> = (0..values.len()).collect();
+        indices.sort_by(|a, b| {
+            compare_rows(&values[*a], &values[*b], descending).unwrap_or_else(|| {
+                supported = false;
+                Ordering::Equal
+            })
+        });
+
+        if !supported {
+            return Err(Error::FromSql(FromSqlError::UnsupportedOperation));
+        }
+
+        let columns = self
+            .columns
+            .iter()
+            .map(|column| column.permute(&indices))
+            .collect::<Result<_>>()?;
+
+        Ok(Block {
+            info: self.info,
+            columns,
+            capacity: indices.len()
+        })
+    }
+}
+
+fn compare_rows(a: &ValueRef, b: &ValueRef, descending: bool) -> Option<Ordering> {
+    match (a, b) {
+        (ValueRef::Nullable(Either::Left(_)), ValueRef::Nullable(Either::Left(_))) => {
+            Some(Ordering::Equal)
+        }
+        (ValueRef::Nullable(Either::Left(_)), _) => Some(Ordering::Greater),
+        (_, ValueRef::Nullable(Either::Left(_))) => Some(Ordering::Less),
+        (ValueRef::Nullable(Either::Right(a)), ValueRef::Nullable(Either::Right(b))) => {
+            compare_rows(a, b, descending)
+        }
+        _ if descending => compare(a, b).map(Ordering::reverse),
+        _ => compare(a, b)
+    }
+}
+
+/// Orders two values of the same column, `None` for types without an order here.
+fn compare(a: &ValueRef, b: &ValueRef) -> Option<Ordering> {
+    Some(match (a, b) {
+        (ValueRef::UInt8(a), ValueRef::UInt8(b)) => a.cmp(b),
+        (ValueRef::UInt16(a), ValueRef::UInt16(b)) => a.cmp(b),
+        (ValueRef::UInt32(a), ValueRef::UInt32(b)) => a.cmp(b),
+        (ValueRef::UInt64(a), ValueRef::UInt64(b)) => a.cmp(b),
+        (ValueRef::UInt128(a), ValueRef::UInt128(b)) => a.cmp(b),
+        (ValueRef::Int8(a), ValueRef::Int8(b)) => a.cmp(b),
+        (ValueRef::Int16(a), ValueRef::Int16(b)) => a.cmp(b),
+        (ValueRef::Int32(a), ValueRef::Int32(b)) => a.cmp(b),
+        (ValueRef::Int64(a), ValueRef::Int64(b)) => a.cmp(b),
+        (ValueRef::Int128(a), ValueRef::Int128(b)) => a.cmp(b),
+        (ValueRef::Float32(a), ValueRef::Float32(b)) => a.total_cmp(b),
+        (ValueRef::Float64(a), ValueRef::Float64(b)) => a.total_cmp(b),
+        (ValueRef::Bool(a), ValueRef::Bool(b)) => a.cmp(b),
+        (ValueRef::String(a), ValueRef::String(b)) => a.cmp(b),
+        (ValueRef::Date(a, _), ValueRef::Date(b, _)) => a.cmp(b),
+        (ValueRef::Date32(a, _), ValueRef::Date32(b, _)) => a.cmp(b),
+        (ValueRef::DateTime(a, _), ValueRef::DateTime(b, _)) => a.cmp(b),
+        (ValueRef::DateTime64(a, _), ValueRef::DateTime64(b, _)) => a.cmp(b),
+        // A column has a single scale.
+        (ValueRef::Decimal(a), ValueRef::Decimal(b)) => a.underlying.cmp(&b.underlying),
+        (ValueRef::Enum8(_, a), ValueRef::Enum8(_, b)) => a.0.cmp(&b.0),
+        (ValueRef::Enum16(_, a), ValueRef::Enum16(_, b)) => a.0.cmp(&b.0),
+        _ => return None
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::Simple;
+
+    #[test]
+    fn test_sort_by() {
+        let block = Block::<Simple>::new()
+            .column("id", vec![3_u32, 1, 2, 1])
+            .column("name", vec!["c", "a", "b", "d"]);
+
+        let sorted = block.sort_by(0, false).unwrap();
+        let rows: Vec<(u32, String)> = sorted
+            .rows()
+            .map(|row| (row.get(0).unwrap(), row.get(1).unwrap()))
+            .collect();
+        assert_eq!(rows, vec![
+            (1, "a".to_string()),
+            (1, "d".to_string()),
+            (2, "b".to_string()),
+            (3, "c".to_string()),
+        ]);
+
+        let sorted = block.sort_by("id", true).unwrap();
+        let names: Vec<String> = sorted.rows().map(|row| row.get(1).unwrap()).collect();
+        assert_eq!(names, vec!["c", "b", "a", "d"]);
+        assert_eq!(sorted.columns()[1].sql_type(), block.columns()[1].sql_type());
+    }
+
+    #[test]
+    fn test_sort_nulls_last() {
+        let block = Block::<Simple>::new().column("a", vec![None, Some(2_i64), None, Some(-1)]);
+
+        for (descending, expected) in &[
+            (false, vec![Some(-1), Some(2), None, None]),
+            (true, vec![Some(2), Some(-1), None, None]),
+        ] {
+            let sorted = block.sort_by(0, *descending).unwrap();
+            let values: Vec<Option<i64>> = sorted.rows().map(|row| row.get(0).unwrap()).collect();
+            assert_eq!(&values, expected);
+        }
+    }
+
+    #[test]
+    fn test_sort_unsupported() {
+        let block = Block::<Simple>::new().column("a", vec![vec![1_u8], vec![0]]);
+        assert!(matches!(
+            block.sort_by(0, false),
+            Err(Error::FromSql(FromSqlError::UnsupportedOperation))
+        ));
+    }
+}
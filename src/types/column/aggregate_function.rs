@@ -0,0 +1,257 @@
+use crate::binary::Encoder;
+use crate::binary::ReadEx;
+use crate::errors::Error;
+use crate::errors::FromSqlError;
+use crate::errors::Result;
+use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
+use crate::types::column::factory::split_top_level;
+use crate::types::column::ColumnData;
+use crate::types::column::ColumnFrom;
+use crate::types::column::ColumnWrapper;
+use crate::types::SqlType;
+use crate::types::Value;
+use crate::types::ValueRef;
+
+/// Serialized states of an aggregate function, e.g. the intermediate result of
+/// `sumState(x)` that one server sends to another.
+///
+/// The states are kept as the raw bytes of their native serialization and are
+/// never interpreted, so a block of them can be forwarded as is.
+///
+/// ```rust
+/// use clickhouse_srv::types::AggregateFunctionState;
+/// use clickhouse_srv::types::Block;
+///
+/// let states = vec![42_u64.to_le_bytes().to_vec(), 7_u64.to_le_bytes().to_vec()];
+/// let block = Block::new().column("s", AggregateFunctionState::new("sum, UInt64", states));
+/// assert_eq!(
+///     block.columns()[0].sql_type().to_string(),
+///     "AggregateFunction(sum, UInt64)"
+/// );
+/// ```
+pub struct AggregateFunctionState {
+    function: String,
+    states: Vec<Vec<u8>>
+}
+
+impl AggregateFunctionState {
+    /// `function` is what goes inside `AggregateFunction(...)`: the function and
+    /// its argument types, e.g. `sum, UInt64`.
+    pub fn new(function: impl Into<String>, states: Vec<Vec<u8>>) -> Self {
+        Self {
+            function: function.into(),
+            states
+        }
+    }
+}
+
+impl ColumnFrom for AggregateFunctionState {
+    fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
+        W::wrap(AggregateFunctionColumnData {
+            function: source.function,
+            states: source.states
+        })
+    }
+}
+
+/// How the state of a row ends, which the native format leaves to the
+/// aggregate function.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StateLayout {
+    /// A `VarUInt`, e.g. `count`.
+    VarUInt,
+    /// A value of fixed size, e.g. `sum`.
+    Fixed(usize),
+    /// A flag byte followed by a value of fixed size when it is set, e.g. `max`.
+    Optional(usize)
+}
+
+/// Width of a value of one of the fixed-size types.
+fn value_width(type_name: &str) -> Option<usize> {
+    Some(match type_name {
+        "UInt8" | "Int8" | "Bool" => 1,
+        "UInt16" | "Int16" | "Date" => 2,
+        "UInt32" | "Int32" | "Float32" | "Date32" | "DateTime" => 4,
+        "UInt64" | "Int64" | "Float64" => 8,
+        "UInt128" | "Int128" => 16,
+        "UInt256" | "Int256" => 32,
+        _ => return None
+    })
+}
+
+fn state_layout(function: &str) -> Option<StateLayout> {
+    let parts = split_top_level(function)?;
+    let (name, args) = parts.split_first()?;
+
+    Some(match (*name, args) {
+        ("count", _) => StateLayout::VarUInt,
+        ("sumWithOverflow", [arg]) => StateLayout::Fixed(value_width(arg)?),
+        // Narrow sums are widened to 64 bits.
+        ("sum", [arg]) => StateLayout::Fixed(value_width(arg)?.max(8)),
+        ("min" | "max" | "any" | "anyLast", [arg]) => StateLayout::Optional(value_width(arg)?),
+        _ => return None
+    })
+}
+
+pub(crate) struct AggregateFunctionColumnData {
+    function: String,
+    states: Vec<Vec<u8>>
+}
+
+impl AggregateFunctionColumnData {
+    pub(crate) fn with_capacity(capacity: usize, function: String) -> Self {
+        Self {
+            function,
+            states: Vec::with_capacity(capacity)
+        }
+    }
+
+    /// Only functions whose state size is known to [`state_layout`] can be read,
+    /// the format has no length prefix.
+    pub(crate) fn load<R: ReadEx>(reader: &mut R, function: &str, size: usize) -> Result<Self> {
+        let layout = state_layout(function).ok_or_else(|| {
+            Error::FromSql(FromSqlError::UnknownType {
+                name: format!("AggregateFunction({})", function)
+            })
+        })?;
+
+        let mut states = Vec::with_capacity(size);
+        for _ in 0..size {
+            states.push(read_state(reader, layout)?);
+        }
+
+        Ok(Self {
+            function: function.to_string(),
+            states
+        })
+    }
+}
+
+fn read_state<R: ReadEx>(reader: &mut R, layout: StateLayout) -> Result<Vec<u8>> {
+    match layout {
+        StateLayout::VarUInt => {
+            let mut encoder = Encoder::new();
+            encoder.uvarint(reader.read_uvarint()?);
+            Ok(encoder.get_buffer())
+        }
+        StateLayout::Fixed(width) => {
+            let mut state = vec![0_u8; width];
+            reader.read_bytes(&mut state)?;
+            Ok(state)
+        }
+        StateLayout::Optional(width) => {
+            let has_value: u8 = reader.read_scalar()?;
+            let mut state = vec![has_value];
+            if has_value != 0 {
+                state.resize(1 + width, 0);
+                reader.read_bytes(&mut state[1..])?;
+            }
+            Ok(state)
+        }
+    }
+}
+
+impl ColumnData for AggregateFunctionColumnData {
+    fn sql_type(&self) -> SqlType {
+        SqlType::AggregateFunction(self.function.clone())
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
+        for state in &self.states[start..end] {
+            encoder.write_bytes(state);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    fn push(&mut self, value: Value) {
+        if let Value::String(state) = value {
+            self.states.push(state.as_ref().clone());
+        } else {
+            panic!("value should be an aggregate function state")
+        }
+    }
+
+    fn at(&self, index: usize) -> ValueRef<'_> {
+        ValueRef::String(&self.states[index])
+    }
+
+    fn clone_instance(&self) -> BoxColumnData {
+        Box::new(Self {
+            function: self.function.clone(),
+            states: self.states.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use chrono_tz::Tz;
+
+    use super::*;
+    use crate::types::Block;
+
+    #[test]
+    fn test_state_layout() {
+        assert_eq!(state_layout("count"), Some(StateLayout::VarUInt));
+        assert_eq!(state_layout("sum, UInt8"), Some(StateLayout::Fixed(8)));
+        assert_eq!(state_layout("sum, Int128"), Some(StateLayout::Fixed(16)));
+        assert_eq!(state_layout("sumWithOverflow, UInt8"), Some(StateLayout::Fixed(1)));
+        assert_eq!(state_layout("max, Float32"), Some(StateLayout::Optional(4)));
+        assert_eq!(state_layout("uniq, String"), None);
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let sums = vec![42_u64.to_le_bytes().to_vec(), u64::MAX.to_le_bytes().to_vec()];
+        let maxes = vec![vec![1, 0xff, 0xff, 0xff, 0x7f], vec![0]];
+        let counts = vec![vec![3], vec![0xac, 0x02]];
+
+        let block = Block::new()
+            .column("s", AggregateFunctionState::new("sum, UInt64", sums))
+            .column("m", AggregateFunctionState::new("max, Int32", maxes))
+            .column("c", AggregateFunctionState::new("count", counts));
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+
+        assert_eq!(
+            rblock.columns()[0].sql_type().to_string(),
+            "AggregateFunction(sum, UInt64)"
+        );
+        assert_eq!(rblock.columns()[1].sql_type().to_string(), "AggregateFunction(max, Int32)");
+        assert_eq!(rblock.columns()[2].sql_type().to_string(), "AggregateFunction(count)");
+        assert_eq!(block, rblock);
+        assert_eq!(reader.position() as usize, encoder.get_buffer_ref().len());
+
+        assert_eq!(rblock.columns()[1].at(1), ValueRef::String(&[0]));
+        assert_eq!(rblock.columns()[2].at(1), ValueRef::String(&[0xac, 0x02]));
+    }
+
+    #[test]
+    fn test_unknown_state_size() {
+        let block = Block::new().column(
+            "u",
+            AggregateFunctionState::new("uniq, String", vec![vec![0_u8; 3]])
+        );
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let err = Block::load(&mut reader, Tz::Zulu, false).unwrap_err();
+        assert!(matches!(err, Error::FromSql(FromSqlError::UnknownType { .. })));
+    }
+}
@@ -7,6 +7,7 @@ use crate::binary::ReadEx;
 use crate::errors::Result;
 use crate::types::column::column_data::ArcColumnData;
 use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::column::list::List;
 use crate::types::column::ArcColumnWrapper;
 use crate::types::column::ColumnData;
@@ -24,7 +25,8 @@ impl ArrayColumnData {
         reader: &mut R,
         type_name: &str,
         rows: usize,
-        tz: Tz
+        tz: Tz,
+        settings: SerializationSettings
     ) -> Result<Self> {
         let mut offsets = List::with_capacity(rows);
         offsets.resize(rows, 0_u64);
@@ -34,8 +36,13 @@ impl ArrayColumnData {
             0 => 0,
             _ => offsets.at(rows - 1) as usize
         };
-        let inner =
-            <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(reader, type_name, size, tz)?;
+        let inner = <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(
+            reader,
+            type_name,
+            size,
+            tz,
+            settings
+        )?;
 
         Ok(ArrayColumnData { inner, offsets })
     }
@@ -47,15 +54,27 @@ impl ColumnData for ArrayColumnData {
         SqlType::Array(inner_type.into())
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
-        let mut offset = 0_u64;
+    fn serialized_type(&self, settings: SerializationSettings) -> SqlType {
+        SqlType::Array(self.inner.serialized_type(settings).into())
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
+        // Offsets are absolute, a slice is re-based so it reads as a standalone column.
+        let base = if start == 0 { 0 } else { self.offsets.at(start - 1) };
+        let mut offset = base;
 
         for i in start..end {
             offset = self.offsets.at(i);
-            encoder.write(offset);
+            encoder.write(offset - base);
         }
 
-        self.inner.save(encoder, 0, offset as usize);
+        self.inner.save(encoder, base as usize, offset as usize, settings);
     }
 
     fn len(&self) -> usize {
@@ -133,6 +152,9 @@ mod test {
     use std::io::Cursor;
 
     use super::*;
+    use crate::row;
+    use crate::types::column::round_trip;
+    use crate::types::Column;
     use crate::types::Simple;
     use crate::Block;
 
@@ -151,4 +173,58 @@ mod test {
 
         assert_eq!(block, rblock);
     }
+
+    #[test]
+    fn test_array_of_strings() {
+        let block = Block::<Simple>::new().column("tags", vec![
+            vec![],
+            vec!["a".to_string(), "bc".to_string()],
+            vec!["".to_string()],
+        ]);
+
+        let rblock = round_trip(&block);
+        assert_eq!(rblock.columns()[0].sql_type(), SqlType::Array(&SqlType::String));
+        assert_eq!(block, rblock);
+
+        let tags: Vec<String> = rblock.get(1, "tags").unwrap();
+        assert_eq!(tags, vec!["a".to_string(), "bc".to_string()]);
+        assert!(rblock.get::<Vec<String>, _>(0, "tags").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_nested_arrays() {
+        let inner = SqlType::Array(&SqlType::Int32).into();
+        let row = |items: Vec<Vec<i32>>| {
+            let items = items
+                .into_iter()
+                .map(|xs| {
+                    let xs = xs.into_iter().map(Value::from).collect();
+                    Value::Array(&SqlType::Int32, Arc::new(xs))
+                })
+                .collect();
+            Value::Array(inner, Arc::new(items))
+        };
+
+        let mut block = Block::new();
+        block.push(row! { xs: row(vec![vec![1, 2], vec![]]) }).unwrap();
+        block.push(row! { xs: row(vec![]) }).unwrap();
+        block.push(row! { xs: row(vec![vec![3], vec![4, 5, 6]]) }).unwrap();
+
+        let rblock = round_trip(&block);
+        assert_eq!(rblock.columns()[0].sql_type().to_string(), "Array(Array(Int32))");
+        assert_eq!(block, rblock);
+    }
+
+    #[test]
+    fn test_save_slice() {
+        let block = Block::<Simple>::new().column("vals", vec![vec![1_u64], vec![2, 3], vec![4]]);
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].slice(1..3).write(&mut encoder, Default::default());
+
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let column = Column::<Simple>::read(&mut reader, 2, Tz::Zulu, Default::default()).unwrap();
+        let expected = Block::<Simple>::new().column("vals", vec![vec![2_u64, 3], vec![4]]);
+        assert_eq!(Block::<Simple>::new().column("vals", column), expected);
+    }
 }
@@ -0,0 +1,140 @@
+use crate::binary::Encoder;
+use crate::binary::ReadEx;
+use crate::errors::Result;
+use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
+use crate::types::column::nullable::NullableColumnData;
+use crate::types::column::ArcColumnWrapper;
+use crate::types::column::ColumnData;
+use crate::types::column::ColumnFrom;
+use crate::types::column::ColumnWrapper;
+use crate::types::SqlType;
+use crate::types::Value;
+use crate::types::ValueRef;
+
+/// `Bool` is serialized as one `UInt8` per row. The raw bytes are kept as read,
+/// so any nonzero byte is reported as `true`.
+pub(crate) struct BoolColumnData {
+    data: Vec<u8>
+}
+
+impl BoolColumnData {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        BoolColumnData {
+            data: Vec::with_capacity(capacity)
+        }
+    }
+
+    pub(crate) fn load<R: ReadEx>(reader: &mut R, size: usize) -> Result<Self> {
+        let mut data = vec![0_u8; size];
+        reader.read_bytes(data.as_mut())?;
+        Ok(BoolColumnData { data })
+    }
+}
+
+impl ColumnFrom for Vec<bool> {
+    fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
+        let data = source.into_iter().map(u8::from).collect();
+        W::wrap(BoolColumnData { data })
+    }
+}
+
+impl ColumnFrom for Vec<Option<bool>> {
+    fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
+        let inner = Vec::<bool>::column_from::<ArcColumnWrapper>(Vec::new());
+
+        let mut data = NullableColumnData {
+            inner,
+            nulls: Vec::with_capacity(source.len())
+        };
+
+        for value in source {
+            data.push(value.into());
+        }
+
+        W::wrap(data)
+    }
+}
+
+impl ColumnData for BoolColumnData {
+    fn sql_type(&self) -> SqlType {
+        SqlType::Bool
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
+        encoder.write_bytes(&self.data[start..end]);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn push(&mut self, value: Value) {
+        if let Value::Bool(v) = value {
+            self.data.push(u8::from(v));
+        } else {
+            panic!("value should be a bool")
+        }
+    }
+
+    fn at(&self, index: usize) -> ValueRef<'_> {
+        ValueRef::Bool(self.data[index] != 0)
+    }
+
+    fn clone_instance(&self) -> BoxColumnData {
+        Box::new(Self {
+            data: self.data.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use chrono_tz::Tz;
+
+    use super::*;
+    use crate::row;
+    use crate::types::Block;
+    use crate::types::Simple;
+
+    #[test]
+    fn test_write_and_read() {
+        let mut block = Block::<Simple>::new();
+        block.push(row! { flag: true }).unwrap();
+        block.push(row! { flag: false }).unwrap();
+        assert_eq!(block.columns()[0].sql_type().to_string(), "Bool");
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 2, SerializationSettings::default());
+        assert_eq!(encoder.get_buffer_ref(), &[1_u8, 0]);
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+
+        assert_eq!(block, rblock);
+        assert!(rblock.get::<bool, _>(0, "flag").unwrap());
+        assert!(!rblock.get::<bool, _>(1, "flag").unwrap());
+    }
+
+    #[test]
+    fn test_nonzero_is_true() {
+        let data = [0_u8, 1, 2];
+        let mut reader = Cursor::new(&data[..]);
+        let column = BoolColumnData::load(&mut reader, 3).unwrap();
+
+        assert_eq!(column.at(0), ValueRef::Bool(false));
+        assert_eq!(column.at(1), ValueRef::Bool(true));
+        assert_eq!(column.at(2), ValueRef::Bool(true));
+        assert_eq!(format!("{}", column.at(2)), "true");
+    }
+}
@@ -10,6 +10,7 @@ use crate::errors::Result;
 use crate::types::column::column_data::ArcColumnData;
 use crate::types::column::column_data::BoxColumnData;
 use crate::types::column::column_data::ColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::column::datetime64::from_datetime;
 use crate::types::column::nullable::NullableColumnData;
 use crate::types::column::ArcColumnWrapper;
@@ -84,7 +85,13 @@ impl ColumnData for ChronoDateTimeColumnData {
         SqlType::DateTime(DateTimeType::Chrono)
     }
 
-    fn save(&self, _encoder: &mut Encoder, _start: usize, _end: usize) {
+    fn save(
+        &self,
+        _encoder: &mut Encoder,
+        _start: usize,
+        _end: usize,
+        _settings: SerializationSettings
+    ) {
         unimplemented!()
     }
 
@@ -157,7 +164,13 @@ impl ColumnData for ChronoDateTimeAdapter {
         self.dst_type.clone()
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         if !is_chrono_datetime(self.column.as_ref()) {
             panic!("Invalid column type {}.", self.column.sql_type());
         }
@@ -171,7 +184,7 @@ impl ColumnData for ChronoDateTimeAdapter {
                     encoder.write(value);
                 }
             }
-            SqlType::DateTime(DateTimeType::DateTime32) => {
+            SqlType::DateTime(DateTimeType::DateTime32 | DateTimeType::DateTime32Tz(_)) => {
                 for date in &dates[start..end] {
                     let value = date.timestamp() as u32;
                     encoder.write(value);
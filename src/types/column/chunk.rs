@@ -5,6 +5,7 @@ use super::ColumnData;
 use crate::binary::Encoder;
 use crate::types::column::column_data::ArcColumnData;
 use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::SqlType;
 use crate::types::Value;
 use crate::types::ValueRef;
@@ -25,11 +26,22 @@ impl ColumnData for ChunkColumnData {
         self.data.sql_type()
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn serialized_type(&self, settings: SerializationSettings) -> SqlType {
+        self.data.serialized_type(settings)
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
         self.data.save(
             encoder,
             self.range.start + start,
-            cmp::min(self.range.end, self.range.start + end)
+            cmp::min(self.range.end, self.range.start + end),
+            settings
         )
     }
 
@@ -1,10 +1,14 @@
+use std::any::Any;
 use std::convert;
 use std::sync::Arc;
 
 use crate::binary::Encoder;
+use crate::binary::MAX_STRING_SIZE;
 use crate::errors::Error;
 use crate::errors::FromSqlError;
 use crate::errors::Result;
+use crate::protocols::DBMS_TCP_PROTOCOL_VERSION;
+use crate::types::block::MAX_DECOMPRESSED_SIZE;
 use crate::types::SqlType;
 use crate::types::Value;
 use crate::types::ValueRef;
@@ -13,9 +17,55 @@ pub(crate) type ArcColumnData = Arc<dyn ColumnData + Send + Sync>;
 
 pub(crate) type BoxColumnData = Box<dyn ColumnData + Send + Sync>;
 
-pub trait ColumnData {
+/// Gives `Column::downcast_ref` the concrete type behind a `dyn ColumnData`.
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: ColumnData + 'static> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// What the wire format of a column may depend on, passed to every column that
+/// is saved or loaded.
+#[derive(Clone, Copy, Debug)]
+pub struct SerializationSettings {
+    /// Revision negotiated with the client, formats it predates are avoided.
+    pub client_revision: u64,
+    /// Longest `String` value accepted when loading.
+    pub max_string_size: usize,
+    /// Largest size a compressed frame may announce for its data when loading.
+    pub max_decompressed_size: usize
+}
+
+impl Default for SerializationSettings {
+    fn default() -> Self {
+        Self {
+            client_revision: DBMS_TCP_PROTOCOL_VERSION,
+            max_string_size: MAX_STRING_SIZE,
+            max_decompressed_size: MAX_DECOMPRESSED_SIZE
+        }
+    }
+}
+
+pub trait ColumnData: AsAny {
     fn sql_type(&self) -> SqlType;
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize);
+
+    /// The type the column is saved as for `settings`, its `sql_type` unless it
+    /// falls back to an older format.
+    fn serialized_type(&self, _settings: SerializationSettings) -> SqlType {
+        self.sql_type()
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    );
     fn len(&self) -> usize;
     fn push(&mut self, value: Value);
     fn at(&self, index: usize) -> ValueRef;
@@ -3,6 +3,7 @@ use std::iter;
 use super::column_data::ArcColumnData;
 use super::column_data::BoxColumnData;
 use super::column_data::ColumnData;
+use super::column_data::SerializationSettings;
 use crate::binary::Encoder;
 use crate::errors::Error;
 use crate::errors::FromSqlError;
@@ -47,7 +48,7 @@ impl ColumnData for ConcatColumnData {
         self.data[0].sql_type()
     }
 
-    fn save(&self, _: &mut Encoder, _: usize, _: usize) {
+    fn save(&self, _: &mut Encoder, _: usize, _: usize, _: SerializationSettings) {
         unimplemented!()
     }
 
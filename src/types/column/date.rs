@@ -1,4 +1,5 @@
 use std::convert;
+use std::convert::TryFrom;
 use std::fmt;
 use std::sync::Arc;
 
@@ -8,18 +9,26 @@ use chrono_tz::Tz;
 
 use crate::binary::Encoder;
 use crate::binary::ReadEx;
+use crate::errors::Error;
+use crate::errors::FromSqlError;
 use crate::errors::Result;
 use crate::types::column::array::ArrayColumnData;
+use crate::types::column::column_data::ArcColumnData;
 use crate::types::column::column_data::BoxColumnData;
 use crate::types::column::column_data::ColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::column::list::List;
 use crate::types::column::nullable::NullableColumnData;
 use crate::types::column::numeric::save_data;
 use crate::types::column::ArcColumnWrapper;
+use crate::types::column::Column;
 use crate::types::column::ColumnFrom;
 use crate::types::column::ColumnWrapper;
 use crate::types::column::Either;
+use crate::types::date_converter::days_since_epoch;
+use crate::types::ColumnType;
 use crate::types::DateConverter;
+use crate::types::DateTimeType;
 use crate::types::Marshal;
 use crate::types::SqlType;
 use crate::types::StatBuffer;
@@ -40,7 +49,10 @@ where T: StatBuffer
         + 'static
 {
     data: List<T>,
-    tz: Tz
+    tz: Tz,
+    /// Whether `tz` is part of the column type, as in `DateTime('Europe/Berlin')`,
+    /// rather than the session default.
+    explicit_tz: bool
 }
 
 impl<T> DateColumnData<T>
@@ -58,7 +70,8 @@ where T: StatBuffer
     pub(crate) fn with_capacity(capacity: usize, timezone: Tz) -> DateColumnData<T> {
         DateColumnData {
             data: List::with_capacity(capacity),
-            tz: timezone
+            tz: timezone,
+            explicit_tz: false
         }
     }
 
@@ -72,26 +85,122 @@ where T: StatBuffer
             data.set_len(size);
         }
         reader.read_bytes(data.as_mut())?;
-        Ok(DateColumnData { data, tz })
+        Ok(DateColumnData {
+            data,
+            tz,
+            explicit_tz: false
+        })
+    }
+}
+
+impl DateColumnData<u32> {
+    pub(crate) fn with_timezone(capacity: usize, timezone: Tz) -> Self {
+        DateColumnData {
+            explicit_tz: true,
+            ..Self::with_capacity(capacity, timezone)
+        }
+    }
+
+    pub(crate) fn load_with_timezone<R: ReadEx>(
+        reader: &mut R,
+        size: usize,
+        timezone: Tz
+    ) -> Result<Self> {
+        Ok(DateColumnData {
+            explicit_tz: true,
+            ..Self::load(reader, size, timezone)?
+        })
+    }
+}
+
+impl DateColumnData<u16> {
+    /// Narrows a `Date32` column to `Date`, which only covers 1970-01-01 to 2149-06-06.
+    pub(crate) fn from_date32<K: ColumnType>(column: &Column<K>) -> Result<Self> {
+        let mut data = List::with_capacity(column.len());
+        let mut tz = Tz::Zulu;
+
+        for index in 0..column.len() {
+            if let ValueRef::Date32(days, value_tz) = column.at(index) {
+                let days =
+                    u16::try_from(days).map_err(|_| Error::FromSql(FromSqlError::OutOfRange))?;
+                data.push(days);
+                tz = value_tz;
+            } else {
+                unreachable!()
+            }
+        }
+
+        Ok(DateColumnData {
+            data,
+            tz,
+            explicit_tz: false
+        })
+    }
+}
+
+impl ColumnFrom for Vec<NaiveDate> {
+    fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
+        let mut data = List::<i32>::with_capacity(source.len());
+        for s in source {
+            data.push(days_since_epoch(s) as i32);
+        }
+
+        let column: DateColumnData<i32> = DateColumnData {
+            data,
+            tz: Tz::Zulu,
+            explicit_tz: false
+        };
+        W::wrap(column)
+    }
+}
+
+impl ColumnFrom for Vec<Option<NaiveDate>> {
+    fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
+        let fake: Vec<NaiveDate> = Vec::with_capacity(source.len());
+        let inner = Vec::column_from::<ArcColumnWrapper>(fake);
+
+        let mut data = NullableColumnData {
+            inner,
+            nulls: Vec::with_capacity(source.len())
+        };
+
+        for value in source {
+            data.push(value.into());
+        }
+
+        W::wrap(data)
     }
 }
 
+/// A `Date` column, or a `Date32` one when a date does not fit `Date`, so that
+/// none is wrapped. Narrowing it with `Block::column_as(.., SqlType::Date)`
+/// then fails with `FromSqlError::OutOfRange`.
 impl ColumnFrom for Vec<Date<Tz>> {
     fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
         let mut data = List::<u16>::with_capacity(source.len());
-        for s in source {
-            data.push(u16::get_days(s));
+        for s in &source {
+            match u16::get_days(*s) {
+                Ok(days) => data.push(days),
+                Err(_) => {
+                    let dates = source.iter().map(|date| date.naive_local()).collect();
+                    return Vec::<NaiveDate>::column_from::<W>(dates);
+                }
+            }
         }
 
-        let column: DateColumnData<u16> = DateColumnData { data, tz: Tz::Zulu };
+        let column: DateColumnData<u16> = DateColumnData {
+            data,
+            tz: Tz::Zulu,
+            explicit_tz: false
+        };
         W::wrap(column)
     }
 }
 
 impl ColumnFrom for Vec<Vec<Date<Tz>>> {
     fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
-        let fake: Vec<Date<Tz>> = Vec::with_capacity(source.len());
-        let inner = Vec::column_from::<ArcColumnWrapper>(fake);
+        let fits = fits_date(source.iter().flatten().map(|date| date.naive_local()));
+        let inner = empty_date_column(fits);
         let sql_type = inner.sql_type();
 
         let mut data = ArrayColumnData {
@@ -102,9 +211,7 @@ impl ColumnFrom for Vec<Vec<Date<Tz>>> {
         for vs in source {
             let mut inner = Vec::with_capacity(vs.len());
             for v in vs {
-                let days = u16::get_days(v);
-                let value: Value = Value::Date(days, v.timezone());
-                inner.push(value);
+                inner.push(date_value(v.naive_local(), v.timezone(), fits));
             }
             data.push(Value::Array(sql_type.clone().into(), Arc::new(inner)));
         }
@@ -139,8 +246,9 @@ impl ColumnFrom for Vec<Vec<DateTime<Tz>>> {
 
 impl ColumnFrom for Vec<Option<Date<Tz>>> {
     fn column_from<W: ColumnWrapper>(source: Self) -> <W as ColumnWrapper>::Wrapper {
-        let fake: Vec<Date<Tz>> = Vec::with_capacity(source.len());
-        let inner = Vec::column_from::<ArcColumnWrapper>(fake);
+        let fits = fits_date(source.iter().flatten().map(|date| date.naive_local()));
+        let inner = empty_date_column(fits);
+        let sql_type = inner.sql_type();
 
         let mut data = NullableColumnData {
             inner,
@@ -149,10 +257,9 @@ impl ColumnFrom for Vec<Option<Date<Tz>>> {
 
         for value in source {
             match value {
-                None => data.push(Value::Nullable(Either::Left(SqlType::Date.into()))),
+                None => data.push(Value::Nullable(Either::Left(sql_type.clone().into()))),
                 Some(d) => {
-                    let days = u16::get_days(d);
-                    let value = Value::Date(days, d.timezone());
+                    let value = date_value(d.naive_local(), d.timezone(), fits);
                     data.push(Value::Nullable(Either::Right(Box::new(value))))
                 }
             }
@@ -162,6 +269,30 @@ impl ColumnFrom for Vec<Option<Date<Tz>>> {
     }
 }
 
+// Whether every date fits `Date`, the nested columns keep them as `Date32`
+// otherwise, as `Vec<Date<Tz>>` does.
+fn fits_date<I: Iterator<Item = NaiveDate>>(mut dates: I) -> bool {
+    dates.all(|date| u16::try_from(days_since_epoch(date)).is_ok())
+}
+
+fn empty_date_column(fits: bool) -> ArcColumnData {
+    if fits {
+        Vec::<Date<Tz>>::column_from::<ArcColumnWrapper>(Vec::new())
+    } else {
+        Vec::<NaiveDate>::column_from::<ArcColumnWrapper>(Vec::new())
+    }
+}
+
+/// `date` as a `Date` value, or as `Date32` when it does not fit `Date` or
+/// `fits` is false.
+pub(crate) fn date_value(date: NaiveDate, tz: Tz, fits: bool) -> Value {
+    let days = days_since_epoch(date);
+    match u16::try_from(days) {
+        Ok(days) if fits => Value::Date(days, tz),
+        _ => Value::Date32(days as i32, tz)
+    }
+}
+
 impl<T> ColumnData for DateColumnData<T>
 where T: StatBuffer
         + Unmarshal<T>
@@ -177,10 +308,20 @@ where T: StatBuffer
         + 'static
 {
     fn sql_type(&self) -> SqlType {
-        T::date_type()
+        if self.explicit_tz {
+            SqlType::DateTime(DateTimeType::DateTime32Tz(self.tz))
+        } else {
+            T::date_type()
+        }
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         save_data::<T>(self.data.as_ref(), encoder, start, end);
     }
 
@@ -199,7 +340,8 @@ where T: StatBuffer
     fn clone_instance(&self) -> BoxColumnData {
         Box::new(Self {
             data: self.data.clone(),
-            tz: self.tz
+            tz: self.tz,
+            explicit_tz: self.explicit_tz
         })
     }
 
@@ -218,7 +360,15 @@ mod test {
     use chrono_tz::Tz;
 
     use super::*;
+    use crate::types::column::round_trip;
     use crate::types::column::ArcColumnWrapper;
+    use crate::types::FromSql;
+    use crate::types::Simple;
+    use crate::Block;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
 
     #[test]
     fn test_create_date() {
@@ -228,6 +378,77 @@ mod test {
         assert_eq!(SqlType::Date, column.sql_type());
     }
 
+    #[test]
+    fn test_date32_before_epoch() {
+        let dates = vec![
+            date(1925, 3, 14),
+            date(1969, 12, 31),
+            date(1970, 1, 1),
+            date(2200, 1, 1),
+        ];
+        let block = Block::<Simple>::new().column("d", dates.clone());
+        assert_eq!(block.columns()[0].sql_type(), SqlType::Date32);
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 2, SerializationSettings::default());
+        let mut expected = Encoder::new();
+        expected.write(-16_364_i32);
+        expected.write(-1_i32);
+        assert_eq!(encoder.get_buffer_ref(), expected.get_buffer_ref());
+
+        let rblock = round_trip(&block);
+        assert_eq!(rblock.columns()[0].sql_type().to_string(), "Date32");
+        assert_eq!(format!("{}", rblock.columns()[0].at(0)), "1925-03-14");
+        for (i, date) in dates.iter().enumerate() {
+            assert_eq!(rblock.get::<NaiveDate, _>(i, "d").unwrap(), *date);
+        }
+    }
+
+    #[test]
+    fn test_date_from_naive_date() {
+        let dates = vec![date(1970, 1, 2), date(2149, 6, 6)];
+        let block = Block::<Simple>::new()
+            .column_as("d", dates.clone(), SqlType::Date)
+            .unwrap();
+        assert_eq!(block.columns()[0].sql_type(), SqlType::Date);
+
+        let rblock = round_trip(&block);
+        assert_eq!(rblock.columns()[0].sql_type().to_string(), "Date");
+        assert_eq!(rblock.get::<NaiveDate, _>(0, "d").unwrap(), dates[0]);
+        assert_eq!(rblock.get::<NaiveDate, _>(1, "d").unwrap(), dates[1]);
+    }
+
+    #[test]
+    fn test_date_out_of_range() {
+        for value in &[date(1969, 12, 31), date(2149, 6, 7)] {
+            let result = Block::<Simple>::new().column_as("d", vec![*value], SqlType::Date);
+            assert!(matches!(result, Err(Error::FromSql(FromSqlError::OutOfRange))));
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_date_not_wrapped() {
+        let mut column: Column<Simple> = Column::from(vec![Tz::Zulu.ymd(2000, 1, 1)]);
+        for value in &[Tz::Zulu.ymd(1969, 12, 31), Tz::Zulu.ymd(2150, 1, 1)] {
+            assert!(u16::get_days(*value).is_err());
+            assert!(matches!(Value::from(*value), Value::Date32(..)));
+            assert!(column.push(Value::from(*value)).is_err());
+
+            let block = Block::<Simple>::new().column("d", vec![*value]);
+            assert_eq!(block.columns()[0].sql_type(), SqlType::Date32);
+            assert_eq!(block.get::<NaiveDate, _>(0, "d").unwrap(), value.naive_local());
+            let result = Block::<Simple>::new().column_as("d", vec![*value], SqlType::Date);
+            assert!(matches!(result, Err(Error::FromSql(FromSqlError::OutOfRange))));
+        }
+        assert_eq!(column.len(), 1);
+
+        // Days past the range of `NaiveDate`, as a client may send them.
+        let result = NaiveDate::from_sql(ValueRef::Date32(i32::MAX, Tz::Zulu));
+        assert!(matches!(result, Err(Error::FromSql(FromSqlError::OutOfRange))));
+        assert_eq!(format!("{}", ValueRef::Date32(i32::MAX, Tz::Zulu)), i32::MAX.to_string());
+    }
+
     #[test]
     fn test_create_date_time() {
         let tz = Tz::Zulu;
@@ -235,4 +456,35 @@ mod test {
             Vec::column_from::<ArcColumnWrapper>(vec![tz.ymd(2016, 10, 22).and_hms(12, 0, 0)]);
         assert_eq!(format!("{}", column.at(0)), "2016-10-22 12:00:00");
     }
+
+    #[test]
+    fn test_date_time_column_timezone() {
+        let time = Tz::UTC.with_ymd_and_hms(2021, 3, 1, 12, 0, 0).unwrap();
+        let berlin = SqlType::DateTime(DateTimeType::DateTime32Tz(Tz::Europe__Berlin));
+        let tokyo = SqlType::DateTime(DateTimeType::DateTime32Tz(Tz::Asia__Tokyo));
+        let datetime = SqlType::DateTime(DateTimeType::DateTime32);
+        let block = Block::<Simple>::new()
+            .column_as("berlin", vec![time], berlin.clone())
+            .unwrap()
+            .column_as("tokyo", vec![time], tokyo.clone())
+            .unwrap()
+            .column_as("session", vec![time.with_timezone(&Tz::Asia__Tokyo)], datetime)
+            .unwrap();
+        assert_eq!(block.columns()[0].sql_type().to_string(), "DateTime('Europe/Berlin')");
+        assert_eq!(block.columns()[1].sql_type().to_string(), "DateTime('Asia/Tokyo')");
+
+        let block = round_trip(&block);
+        assert_eq!(block.columns()[0].sql_type(), berlin);
+        assert_eq!(block.columns()[1].sql_type(), tokyo);
+
+        let in_berlin: DateTime<Tz> = block.get(0, "berlin").unwrap();
+        let in_tokyo: DateTime<Tz> = block.get(0, "tokyo").unwrap();
+        let in_session: DateTime<Tz> = block.get(0, "session").unwrap();
+        assert_eq!(in_berlin, time);
+        assert_eq!(in_tokyo, time);
+        assert_eq!(in_berlin.timezone(), Tz::Europe__Berlin);
+        assert_eq!(in_tokyo.timezone(), Tz::Asia__Tokyo);
+        assert_eq!(in_session.timezone(), Tz::Zulu);
+        assert_eq!(format!("{}", block.columns()[1].at(0)), "2021-03-01 21:00:00");
+    }
 }
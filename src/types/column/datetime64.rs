@@ -6,7 +6,9 @@ use crate::binary::ReadEx;
 use crate::errors::Result;
 use crate::types::column::column_data::BoxColumnData;
 use crate::types::column::column_data::ColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::column::list::List;
+use crate::types::column::numeric::save_data;
 use crate::types::DateTimeType;
 use crate::types::SqlType;
 use crate::types::Value;
@@ -49,8 +51,14 @@ impl ColumnData for DateTime64ColumnData {
         SqlType::DateTime(DateTimeType::DateTime64(precision, tz))
     }
 
-    fn save(&self, _encoder: &mut Encoder, _start: usize, _end: usize) {
-        unimplemented!()
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
+        save_data::<i64>(self.data.as_ref(), encoder, start, end);
     }
 
     fn len(&self) -> usize {
@@ -111,7 +119,48 @@ pub(crate) fn to_datetime(value: i64, precision: u32, tz: Tz) -> DateTime<Tz> {
 
 #[cfg(test)]
 mod test {
+    use std::io::Cursor;
+
     use super::*;
+    use crate::row;
+    use crate::types::Block;
+
+    fn round_trip(precision: u32) -> (i64, DateTime<Tz>) {
+        let time = Tz::Europe__Moscow.timestamp_opt(1_546_300_800, 123_456_789).unwrap();
+
+        let mut block = Block::new();
+        block.push(row! { ts: Value::datetime64(time, precision) }).unwrap();
+        assert_eq!(
+            block.columns()[0].sql_type(),
+            SqlType::DateTime(DateTimeType::DateTime64(precision, Tz::Europe__Moscow))
+        );
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut cursor = Cursor::new(encoder.get_buffer_ref());
+        let block = Block::load(&mut cursor, Tz::UTC, false).unwrap();
+
+        let ticks = match block.columns()[0].at(0) {
+            ValueRef::DateTime64(ticks, _) => ticks,
+            _ => panic!("should be DateTime64")
+        };
+        (ticks, block.get(0, "ts").unwrap())
+    }
+
+    #[test]
+    fn test_round_trip_millis() {
+        let (ticks, time) = round_trip(3);
+        assert_eq!(ticks, 1_546_300_800_123);
+        assert_eq!(time.timestamp_subsec_nanos(), 123_000_000);
+        assert_eq!(time.timezone(), Tz::Europe__Moscow);
+    }
+
+    #[test]
+    fn test_round_trip_nanos() {
+        let (ticks, time) = round_trip(9);
+        assert_eq!(ticks, 1_546_300_800_123_456_789);
+        assert_eq!(time.timestamp_subsec_nanos(), 123_456_789);
+    }
 
     #[test]
     fn test_to_datetime() {
@@ -7,6 +7,7 @@ use crate::binary::ReadEx;
 use crate::errors::Result;
 use crate::types::column::column_data::BoxColumnData;
 use crate::types::column::column_data::ColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::column::list::List;
 use crate::types::column::nullable::NullableColumnData;
 use crate::types::column::BoxColumnWrapper;
@@ -51,14 +52,21 @@ impl DecimalColumnData {
         scale: u8,
         nobits: NoBits,
         size: usize,
-        tz: Tz
+        tz: Tz,
+        settings: SerializationSettings
     ) -> Result<Self> {
         let type_name = match nobits {
             NoBits::N32 => "Int32",
-            NoBits::N64 => "Int64"
+            NoBits::N64 => "Int64",
+            NoBits::N128 => "Int128"
         };
-        let inner =
-            <dyn ColumnData>::load_data::<BoxColumnWrapper, _>(reader, type_name, size, tz)?;
+        let inner = <dyn ColumnData>::load_data::<BoxColumnWrapper, _>(
+            reader,
+            type_name,
+            size,
+            tz,
+            settings
+        )?;
 
         Ok(DecimalColumnData {
             inner,
@@ -71,29 +79,17 @@ impl DecimalColumnData {
 
 impl ColumnFrom for Vec<Decimal> {
     fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
-        let mut data = List::<i64>::with_capacity(source.len());
-        let mut precision = 18;
-        let mut opt_scale = None;
+        let (precision, scale, nobits) = decimal_params(source.iter());
+        let mut inner = new_inner(nobits, source.len());
         for s in source {
-            precision = std::cmp::max(precision, s.precision);
-            if let Some(old_scale) = opt_scale {
-                if old_scale != s.scale {
-                    panic!("scale != scale")
-                }
-            } else {
-                opt_scale = Some(s.scale);
-            }
-
-            data.push(s.internal());
+            push_internal(inner.as_mut(), nobits, &s);
         }
-        let scale = opt_scale.unwrap_or(4);
-        let inner = Box::new(VectorColumnData { data });
 
         let column = DecimalColumnData {
             inner,
             precision,
             scale,
-            nobits: NoBits::N64
+            nobits
         };
 
         W::wrap(column)
@@ -102,36 +98,24 @@ impl ColumnFrom for Vec<Decimal> {
 
 impl ColumnFrom for Vec<Option<Decimal>> {
     fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
+        let (precision, scale, nobits) = decimal_params(source.iter().flatten());
         let mut nulls: Vec<u8> = Vec::with_capacity(source.len());
-        let mut data = List::<i64>::with_capacity(source.len());
-        let mut precision = 18;
-        let mut opt_scale = None;
+        let mut inner = new_inner(nobits, source.len());
         for os in source {
             if let Some(s) = os {
-                precision = std::cmp::max(precision, s.precision);
-                if let Some(old_scale) = opt_scale {
-                    if old_scale != s.scale {
-                        panic!("scale != scale")
-                    }
-                } else {
-                    opt_scale = Some(s.scale);
-                }
-
-                data.push(s.internal());
+                push_internal(inner.as_mut(), nobits, &s);
                 nulls.push(0);
             } else {
-                data.push(0);
+                push_internal(inner.as_mut(), nobits, &Decimal::default());
                 nulls.push(1);
             }
         }
-        let scale = opt_scale.unwrap_or(4);
-        let inner = Box::new(VectorColumnData { data });
 
         let inner = DecimalColumnData {
             inner,
             precision,
             scale,
-            nobits: NoBits::N64
+            nobits
         };
 
         W::wrap(NullableColumnData {
@@ -141,13 +125,61 @@ impl ColumnFrom for Vec<Option<Decimal>> {
     }
 }
 
+// All values of a column share one scale, the widest precision picks the
+// backing integer.
+fn decimal_params<'a, I>(source: I) -> (u8, u8, NoBits)
+where I: Iterator<Item = &'a Decimal> {
+    let mut precision = 18;
+    let mut opt_scale = None;
+    for s in source {
+        precision = std::cmp::max(precision, s.precision);
+        if let Some(old_scale) = opt_scale {
+            if old_scale != s.scale {
+                panic!("scale != scale")
+            }
+        } else {
+            opt_scale = Some(s.scale);
+        }
+    }
+    let scale = opt_scale.unwrap_or(4);
+    (precision, scale, NoBits::from_precision(precision).unwrap())
+}
+
+fn new_inner(nobits: NoBits, capacity: usize) -> Box<dyn ColumnData + Send + Sync> {
+    match nobits {
+        NoBits::N32 => Box::new(VectorColumnData {
+            data: List::<i32>::with_capacity(capacity)
+        }),
+        NoBits::N64 => Box::new(VectorColumnData {
+            data: List::<i64>::with_capacity(capacity)
+        }),
+        NoBits::N128 => Box::new(VectorColumnData {
+            data: List::<i128>::with_capacity(capacity)
+        })
+    }
+}
+
+fn push_internal(inner: &mut (dyn ColumnData + Send + Sync), nobits: NoBits, decimal: &Decimal) {
+    match nobits {
+        NoBits::N32 => inner.push(decimal.internal::<i32>().into()),
+        NoBits::N64 => inner.push(decimal.internal::<i64>().into()),
+        NoBits::N128 => inner.push(decimal.internal::<i128>().into())
+    }
+}
+
 impl ColumnData for DecimalColumnData {
     fn sql_type(&self) -> SqlType {
         SqlType::Decimal(self.precision, self.scale)
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
-        self.inner.save(encoder, start, end)
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
+        self.inner.save(encoder, start, end, settings)
     }
 
     fn len(&self) -> usize {
@@ -156,25 +188,17 @@ impl ColumnData for DecimalColumnData {
 
     fn push(&mut self, value: Value) {
         if let Value::Decimal(decimal) = value {
-            match self.nobits {
-                NoBits::N32 => {
-                    let internal: i32 = decimal.internal();
-                    self.inner.push(internal.into())
-                }
-                NoBits::N64 => {
-                    let internal: i64 = decimal.internal();
-                    self.inner.push(internal.into())
-                }
-            }
+            push_internal(self.inner.as_mut(), self.nobits, &decimal)
         } else {
             panic!("value should be decimal ({:?})", value);
         }
     }
 
     fn at(&self, index: usize) -> ValueRef {
-        let underlying: i64 = match self.nobits {
-            NoBits::N32 => i64::from(i32::from(self.inner.at(index))),
-            NoBits::N64 => i64::from(self.inner.at(index))
+        let underlying: i128 = match self.nobits {
+            NoBits::N32 => i128::from(i32::from(self.inner.at(index))),
+            NoBits::N64 => i128::from(i64::from(self.inner.at(index))),
+            NoBits::N128 => i128::from(self.inner.at(index))
         };
 
         ValueRef::Decimal(Decimal {
@@ -207,7 +231,13 @@ impl<K: ColumnType> ColumnData for DecimalAdapter<K> {
         SqlType::Decimal(self.precision, self.scale)
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         for i in start..end {
             if let ValueRef::Decimal(decimal) = self.at(i) {
                 match self.nobits {
@@ -219,6 +249,10 @@ impl<K: ColumnType> ColumnData for DecimalAdapter<K> {
                         let internal: i64 = decimal.internal();
                         encoder.write(internal);
                     }
+                    NoBits::N128 => {
+                        let internal: i128 = decimal.internal();
+                        encoder.write(internal);
+                    }
                 }
             } else {
                 panic!("should be decimal");
@@ -255,7 +289,13 @@ impl<K: ColumnType> ColumnData for NullableDecimalAdapter<K> {
         SqlType::Nullable(SqlType::Decimal(self.precision, self.scale).into())
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         let size = end - start;
         let mut nulls = vec![0; size];
         let mut values: Vec<Option<Decimal>> = vec![None; size];
@@ -277,6 +317,9 @@ impl<K: ColumnType> ColumnData for NullableDecimalAdapter<K> {
                     encoder.write(underlying as i32);
                 }
                 NoBits::N64 => {
+                    encoder.write(underlying as i64);
+                }
+                NoBits::N128 => {
                     encoder.write(underlying);
                 }
             }
@@ -309,3 +352,61 @@ impl<K: ColumnType> ColumnData for NullableDecimalAdapter<K> {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::types::column::ArcColumnWrapper;
+    use crate::types::column::new_column;
+    use crate::types::Block;
+    use crate::types::Simple;
+
+    fn round_trip(values: Vec<Decimal>, sql_type: SqlType) -> Vec<Decimal> {
+        let column = new_column::<Simple>("d", Vec::column_from::<ArcColumnWrapper>(values))
+            .cast_to(sql_type.clone())
+            .unwrap();
+        let block = Block::new().column("d", column);
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut cursor = Cursor::new(encoder.get_buffer_ref());
+        let block = Block::load(&mut cursor, Tz::UTC, false).unwrap();
+
+        assert_eq!(block.columns()[0].sql_type(), sql_type);
+        (0..block.row_count())
+            .map(|row| block.get(row, "d").unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_decimal32_round_trip() {
+        let values = vec![Decimal::new(-12_345, 2), Decimal::new(999_999, 2)];
+        let actual = round_trip(values.clone(), SqlType::Decimal(9, 2));
+        assert_eq!(actual, values);
+        assert_eq!(actual[0].internal::<i32>(), -12_345);
+    }
+
+    #[test]
+    fn test_decimal64_round_trip() {
+        let values = vec![Decimal::new(-123_456_789_012_345, 4), Decimal::new(1, 4)];
+        let actual = round_trip(values.clone(), SqlType::Decimal(18, 4));
+        assert_eq!(actual, values);
+        assert_eq!(format!("{}", actual[0]), "-12345678901.2345");
+    }
+
+    #[test]
+    fn test_decimal128_round_trip() {
+        let values = vec![
+            Decimal::from((-98_765_432_109_876_543_210_987_654_321_i128, 10)),
+            Decimal::from((1_i128 << 100, 10)),
+        ];
+        let actual = round_trip(values.clone(), SqlType::Decimal(38, 10));
+        assert_eq!(actual, values);
+        assert_eq!(
+            <(i128, u8)>::from(actual[0].clone()),
+            (-98_765_432_109_876_543_210_987_654_321_i128, 10)
+        );
+    }
+}
@@ -7,6 +7,7 @@ use crate::binary::ReadEx;
 use crate::errors::Result;
 use crate::types::column::column_data::BoxColumnData;
 use crate::types::column::column_data::ColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::column::list::List;
 use crate::types::column::nullable::NullableColumnData;
 use crate::types::column::BoxColumnWrapper;
@@ -43,12 +44,18 @@ impl Enum16ColumnData {
         reader: &mut T,
         enum_values: Vec<(String, i16)>,
         size: usize,
-        tz: Tz
+        tz: Tz,
+        settings: SerializationSettings
     ) -> Result<Self> {
         let type_name = "Int16";
 
-        let inner =
-            <dyn ColumnData>::load_data::<BoxColumnWrapper, _>(reader, type_name, size, tz)?;
+        let inner = <dyn ColumnData>::load_data::<BoxColumnWrapper, _>(
+            reader,
+            type_name,
+            size,
+            tz,
+            settings
+        )?;
 
         Ok(Enum16ColumnData { enum_values, inner })
     }
@@ -59,8 +66,14 @@ impl ColumnData for Enum16ColumnData {
         SqlType::Enum16(self.enum_values.clone())
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
-        self.inner.save(encoder, start, end)
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
+        self.inner.save(encoder, start, end, settings)
     }
 
     fn len(&self) -> usize {
@@ -93,7 +106,13 @@ impl<K: ColumnType> ColumnData for Enum16Adapter<K> {
         SqlType::Enum16(self.enum_values.clone())
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         for i in start..end {
             if let ValueRef::Enum16(_enum_values, value) = self.at(i) {
                 encoder.write(value.internal())
@@ -129,7 +148,13 @@ impl<K: ColumnType> ColumnData for NullableEnum16Adapter<K> {
         SqlType::Nullable(SqlType::Enum16(self.enum_values.clone()).into())
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         let size = end - start;
         let mut nulls = vec![0; size];
         let mut values: Vec<Option<i16>> = vec![None; size];
@@ -239,12 +264,18 @@ impl Enum8ColumnData {
         reader: &mut T,
         enum_values: Vec<(String, i8)>,
         size: usize,
-        tz: Tz
+        tz: Tz,
+        settings: SerializationSettings
     ) -> Result<Self> {
         let type_name = "Int8";
 
-        let inner =
-            <dyn ColumnData>::load_data::<BoxColumnWrapper, _>(reader, type_name, size, tz)?;
+        let inner = <dyn ColumnData>::load_data::<BoxColumnWrapper, _>(
+            reader,
+            type_name,
+            size,
+            tz,
+            settings
+        )?;
 
         Ok(Enum8ColumnData { enum_values, inner })
     }
@@ -255,8 +286,14 @@ impl ColumnData for Enum8ColumnData {
         SqlType::Enum8(self.enum_values.clone())
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
-        self.inner.save(encoder, start, end)
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
+        self.inner.save(encoder, start, end, settings)
     }
 
     fn len(&self) -> usize {
@@ -289,7 +326,13 @@ impl<K: ColumnType> ColumnData for Enum8Adapter<K> {
         SqlType::Enum8(self.enum_values.clone())
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         for i in start..end {
             if let ValueRef::Enum8(_enum_values, value) = self.at(i) {
                 encoder.write(value.internal())
@@ -325,7 +368,13 @@ impl<K: ColumnType> ColumnData for NullableEnum8Adapter<K> {
         SqlType::Nullable(SqlType::Enum8(self.enum_values.clone()).into())
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         let size = end - start;
         let mut nulls = vec![0; size];
         let mut values: Vec<Option<i8>> = vec![None; size];
@@ -415,3 +464,56 @@ impl ColumnFrom for Vec<Option<Enum8>> {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::binary::Encoder;
+    use crate::row;
+    use crate::types::Block;
+
+    #[test]
+    fn test_enum8_round_trip() {
+        let values = vec![
+            ("active".to_string(), 1),
+            ("closed, archived".to_string(), 2),
+            ("it's".to_string(), -3),
+        ];
+
+        let mut block = Block::new();
+        for v in [1, 2, -3] {
+            let status = Value::Enum8(values.clone(), Enum8::of(v));
+            block.push(row! { status: status }).unwrap();
+        }
+
+        let sql_type = block.columns()[0].sql_type();
+        assert_eq!(
+            sql_type.to_string(),
+            r"Enum8('active' = 1,'closed, archived' = 2,'it\'s' = -3)"
+        );
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut cursor = Cursor::new(encoder.get_buffer_ref());
+        let block = Block::load(&mut cursor, Tz::UTC, false).unwrap();
+
+        assert_eq!(block.columns()[0].sql_type(), SqlType::Enum8(values));
+        assert_eq!(block.get::<Enum8, _>(1, "status").unwrap().internal(), 2);
+        assert_eq!(block.get::<String, _>(1, "status").unwrap(), "closed, archived");
+        assert_eq!(block.get::<String, _>(2, "status").unwrap(), "it's");
+    }
+
+    #[test]
+    fn test_enum16_name() {
+        let values = vec![("low".to_string(), -300_i16), ("high".to_string(), 300)];
+        let mut block = Block::new();
+        block
+            .push(row! { level: Value::Enum16(values, Enum16::of(300)) })
+            .unwrap();
+
+        assert_eq!(block.get::<Enum16, _>(0, "level").unwrap().internal(), 300);
+        assert_eq!(block.get::<String, _>(0, "level").unwrap(), "high");
+    }
+}
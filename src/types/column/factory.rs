@@ -1,3 +1,5 @@
+use std::convert::TryFrom;
+
 use chrono_tz::Tz;
 use combine::any;
 use combine::error::StringStreamError;
@@ -13,29 +15,46 @@ use combine::token;
 use combine::Parser;
 
 use crate::binary::ReadEx;
+use crate::errors::Error;
+use crate::errors::FromSqlError;
 use crate::errors::Result;
+use crate::types::column::aggregate_function::AggregateFunctionColumnData;
 use crate::types::column::array::ArrayColumnData;
+use crate::types::column::bool::BoolColumnData;
 use crate::types::column::column_data::ColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::column::date::DateColumnData;
 use crate::types::column::datetime64::DateTime64ColumnData;
 use crate::types::column::decimal::DecimalColumnData;
 use crate::types::column::enums::Enum16ColumnData;
 use crate::types::column::enums::Enum8ColumnData;
 use crate::types::column::fixed_string::FixedStringColumnData;
+use crate::types::column::geo::GeoColumnData;
 use crate::types::column::ip::IpColumnData;
 use crate::types::column::ip::Ipv4;
 use crate::types::column::ip::Ipv6;
 use crate::types::column::ip::Uuid;
 use crate::types::column::list::List;
+use crate::types::column::low_cardinality::LowCardinalityColumnData;
+use crate::types::column::map::entry_type;
+use crate::types::column::map::MapColumnData;
+use crate::types::column::nothing::NothingColumnData;
 use crate::types::column::nullable::NullableColumnData;
 use crate::types::column::numeric::VectorColumnData;
+use crate::types::column::object::ObjectColumnData;
+use crate::types::column::simple_aggregate_function::SimpleAggregateFunctionColumnData;
 use crate::types::column::string::StringColumnData;
+use crate::types::column::tuple::TupleColumnData;
 use crate::types::column::ArcColumnWrapper;
 use crate::types::column::BoxColumnWrapper;
 use crate::types::column::ColumnWrapper;
 use crate::types::decimal::NoBits;
 use crate::types::DateTimeType;
+use crate::types::I256;
 use crate::types::SqlType;
+use crate::types::U256;
+
+const MAX_DATETIME64_PRECISION: u32 = 9;
 
 macro_rules! match_str {
     ($arg:ident, {
@@ -59,46 +78,124 @@ impl dyn ColumnData {
         reader: &mut T,
         type_name: &str,
         size: usize,
-        tz: Tz
+        tz: Tz,
+        settings: SerializationSettings
     ) -> Result<W::Wrapper> {
         Ok(match_str!(type_name, {
             "UInt8" => W::wrap(VectorColumnData::<u8>::load(reader, size)?),
             "UInt16" => W::wrap(VectorColumnData::<u16>::load(reader, size)?),
             "UInt32" => W::wrap(VectorColumnData::<u32>::load(reader, size)?),
             "UInt64" => W::wrap(VectorColumnData::<u64>::load(reader, size)?),
+            "UInt128" => W::wrap(VectorColumnData::<u128>::load(reader, size)?),
+            "UInt256" => W::wrap(VectorColumnData::<U256>::load(reader, size)?),
             "Int8" | "TinyInt" => W::wrap(VectorColumnData::<i8>::load(reader, size)?),
             "Int16" | "SmallInt" => W::wrap(VectorColumnData::<i16>::load(reader, size)?),
             "Int32" | "Int" | "Integer" => W::wrap(VectorColumnData::<i32>::load(reader, size)?),
             "Int64" | "BigInt" => W::wrap(VectorColumnData::<i64>::load(reader, size)?),
+            "Int128" => W::wrap(VectorColumnData::<i128>::load(reader, size)?),
+            "Int256" => W::wrap(VectorColumnData::<I256>::load(reader, size)?),
             "Float32" | "Float" => W::wrap(VectorColumnData::<f32>::load(reader, size)?),
             "Float64" | "Double" => W::wrap(VectorColumnData::<f64>::load(reader, size)?),
-            "String" | "Char" | "Varchar" | "Text" | "TinyText" | "MediumText" | "LongText" | "Blob" | "TinyBlob" | "MediumBlob" | "LongBlob" => W::wrap(StringColumnData::load(reader, size)?),
+            "Bool" | "Boolean" => W::wrap(BoolColumnData::load(reader, size)?),
+            "String" | "Char" | "Varchar" | "Text" | "TinyText" | "MediumText" | "LongText" | "Blob" | "TinyBlob" | "MediumBlob" | "LongBlob" => W::wrap(StringColumnData::load(reader, size, settings.max_string_size)?),
             "Date" => W::wrap(DateColumnData::<u16>::load(reader, size, tz)?),
+            "Date32" => W::wrap(DateColumnData::<i32>::load(reader, size, tz)?),
             "DateTime" | "Timestamp" => W::wrap(DateColumnData::<u32>::load(reader, size, tz)?),
             "IPv4" => W::wrap(IpColumnData::<Ipv4>::load(reader, size)?),
             "IPv6" => W::wrap(IpColumnData::<Ipv6>::load(reader, size)?),
             "UUID" => W::wrap(IpColumnData::<Uuid>::load(reader, size)?),
+            "Nothing" => W::wrap(NothingColumnData::load(reader, size)?),
+            "Point" => {
+                W::wrap(GeoColumnData::load(reader, SqlType::Point, size, tz, settings)?)
+            },
+            "Ring" => {
+                W::wrap(GeoColumnData::load(reader, SqlType::Ring, size, tz, settings)?)
+            },
+            "Polygon" => {
+                W::wrap(GeoColumnData::load(reader, SqlType::Polygon, size, tz, settings)?)
+            },
+            "MultiPolygon" => {
+                W::wrap(GeoColumnData::load(
+                    reader,
+                    SqlType::MultiPolygon,
+                    size,
+                    tz,
+                    settings
+                )?)
+            },
+            "JSON" => {
+                W::wrap(ObjectColumnData::load(reader, SqlType::Json, size, tz, settings)?)
+            },
             _ => {
                 if let Some(inner_type) = parse_nullable_type(type_name) {
-                    W::wrap(NullableColumnData::load(reader, inner_type, size, tz)?)
+                    W::wrap(NullableColumnData::load(
+                        reader,
+                        inner_type,
+                        size,
+                        tz,
+                        settings
+                    )?)
                 } else if let Some(str_len) = parse_fixed_string(type_name) {
                     W::wrap(FixedStringColumnData::load(reader, size, str_len)?)
                 } else if let Some(inner_type) = parse_array_type(type_name) {
-                    W::wrap(ArrayColumnData::load(reader, inner_type, size, tz)?)
+                    W::wrap(ArrayColumnData::load(reader, inner_type, size, tz, settings)?)
+                } else if let Some((key_type, value_type)) = parse_map_type(type_name) {
+                    W::wrap(MapColumnData::load(
+                        reader,
+                        key_type,
+                        value_type,
+                        size,
+                        tz,
+                        settings
+                    )?)
+                } else if let Some(elements) = parse_tuple_type(type_name) {
+                    W::wrap(TupleColumnData::load(reader, elements, size, tz, settings)?)
+                } else if let Some(inner_type) = parse_low_cardinality_type(type_name) {
+                    let (nested_type, nullable) = match parse_nullable_type(inner_type) {
+                        Some(nested_type) => (nested_type, true),
+                        None => (inner_type, false)
+                    };
+                    W::wrap(LowCardinalityColumnData::load(
+                        reader,
+                        nested_type,
+                        nullable,
+                        size,
+                        tz,
+                        settings
+                    )?)
                 } else if let Some((precision, scale, nobits)) = parse_decimal(type_name) {
                     W::wrap(DecimalColumnData::load(
-                        reader, precision, scale, nobits, size, tz,
+                        reader, precision, scale, nobits, size, tz, settings,
                     )?)
                 } else if let Some(items) = parse_enum8(type_name) {
-                    W::wrap(Enum8ColumnData::load(reader, items, size, tz)?)
+                    W::wrap(Enum8ColumnData::load(reader, items, size, tz, settings)?)
                 } else if let Some(items) = parse_enum16(type_name) {
-                    W::wrap(Enum16ColumnData::load(reader, items, size, tz)?)
+                    W::wrap(Enum16ColumnData::load(reader, items, size, tz, settings)?)
+                } else if let Some(timezone) = parse_date_time(type_name) {
+                    let column_timezone: Tz = timezone.parse()?;
+                    W::wrap(DateColumnData::<u32>::load_with_timezone(
+                        reader,
+                        size,
+                        column_timezone
+                    )?)
                 } else if let Some((precision, timezone)) = parse_date_time64(type_name) {
                     let column_timezone = get_timezone(&timezone, tz)?;
                     W::wrap(DateTime64ColumnData::load(reader, size, precision, column_timezone)?)
+                } else if let Some((function, inner_type)) =
+                    parse_simple_aggregate_function_type(type_name)
+                {
+                    W::wrap(SimpleAggregateFunctionColumnData::load(
+                        reader, function, inner_type, size, tz, settings,
+                    )?)
+                } else if let Some(function) = parse_aggregate_function_type(type_name) {
+                    W::wrap(AggregateFunctionColumnData::load(reader, function, size)?)
+                } else if let Some(schema) = parse_object_type(type_name) {
+                    let sql_type = SqlType::Object(schema.to_string());
+                    W::wrap(ObjectColumnData::load(reader, sql_type, size, tz, settings)?)
                 } else {
-                    let message = format!("Unsupported column type \"{}\".", type_name);
-                    return Err(message.into());
+                    return Err(Error::FromSql(FromSqlError::UnknownType {
+                        name: type_name.to_string()
+                    }));
                 }
             }
         }))
@@ -118,18 +215,31 @@ impl dyn ColumnData {
             SqlType::Int16 => W::wrap(VectorColumnData::<i16>::with_capacity(capacity)),
             SqlType::Int32 => W::wrap(VectorColumnData::<i32>::with_capacity(capacity)),
             SqlType::Int64 => W::wrap(VectorColumnData::<i64>::with_capacity(capacity)),
+            SqlType::Int128 => W::wrap(VectorColumnData::<i128>::with_capacity(capacity)),
+            SqlType::Int256 => W::wrap(VectorColumnData::<I256>::with_capacity(capacity)),
+            SqlType::UInt128 => W::wrap(VectorColumnData::<u128>::with_capacity(capacity)),
+            SqlType::UInt256 => W::wrap(VectorColumnData::<U256>::with_capacity(capacity)),
             SqlType::String => W::wrap(StringColumnData::with_capacity(capacity)),
             SqlType::FixedString(len) => {
                 W::wrap(FixedStringColumnData::with_capacity(capacity, len))
             }
             SqlType::Float32 => W::wrap(VectorColumnData::<f32>::with_capacity(capacity)),
             SqlType::Float64 => W::wrap(VectorColumnData::<f64>::with_capacity(capacity)),
+            SqlType::Bool => W::wrap(BoolColumnData::with_capacity(capacity)),
 
             SqlType::Ipv4 => W::wrap(IpColumnData::<Ipv4>::with_capacity(capacity)),
             SqlType::Ipv6 => W::wrap(IpColumnData::<Ipv6>::with_capacity(capacity)),
             SqlType::Uuid => W::wrap(IpColumnData::<Uuid>::with_capacity(capacity)),
+            SqlType::Nothing => W::wrap(NothingColumnData::with_capacity(capacity)),
+            SqlType::Point | SqlType::Ring | SqlType::Polygon | SqlType::MultiPolygon => {
+                W::wrap(GeoColumnData::with_capacity(sql_type, timezone, capacity)?)
+            }
 
             SqlType::Date => W::wrap(DateColumnData::<u16>::with_capacity(capacity, timezone)),
+            SqlType::Date32 => W::wrap(DateColumnData::<i32>::with_capacity(capacity, timezone)),
+            SqlType::DateTime(DateTimeType::DateTime32Tz(timezone)) => {
+                W::wrap(DateColumnData::<u32>::with_timezone(capacity, timezone))
+            }
             SqlType::DateTime(DateTimeType::DateTime64(precision, timezone)) => W::wrap(
                 DateTime64ColumnData::with_capacity(capacity, precision, timezone)
             ),
@@ -152,12 +262,36 @@ impl dyn ColumnData {
                 )?,
                 offsets: List::with_capacity(capacity)
             }),
+            SqlType::Map(key_type, value_type) => W::wrap(MapColumnData {
+                inner: <dyn ColumnData>::from_type::<ArcColumnWrapper>(
+                    SqlType::Array(entry_type(key_type, value_type).into()),
+                    timezone,
+                    capacity
+                )?
+            }),
+            SqlType::Tuple(elements) => {
+                let mut inner = Vec::with_capacity(elements.len());
+                let mut names = Vec::with_capacity(elements.len());
+                for (name, element_type) in elements {
+                    inner.push(<dyn ColumnData>::from_type::<ArcColumnWrapper>(
+                        element_type.clone(),
+                        timezone,
+                        capacity
+                    )?);
+                    names.extend(name);
+                }
+                W::wrap(TupleColumnData { inner, names })
+            }
+            SqlType::LowCardinality(inner_type) => W::wrap(
+                LowCardinalityColumnData::with_capacity(inner_type.clone(), timezone, capacity)?
+            ),
             SqlType::Decimal(precision, scale) => {
                 let nobits = NoBits::from_precision(precision).unwrap();
 
                 let inner_type = match nobits {
                     NoBits::N32 => SqlType::Int32,
-                    NoBits::N64 => SqlType::Int64
+                    NoBits::N64 => SqlType::Int64,
+                    NoBits::N128 => SqlType::Int128
                 };
 
                 W::wrap(DecimalColumnData {
@@ -184,13 +318,29 @@ impl dyn ColumnData {
                     timezone,
                     capacity
                 )?
-            })
+            }),
+            SqlType::SimpleAggregateFunction(function, inner_type) => {
+                W::wrap(SimpleAggregateFunctionColumnData {
+                    function,
+                    inner: <dyn ColumnData>::from_type::<ArcColumnWrapper>(
+                        inner_type.clone(),
+                        timezone,
+                        capacity
+                    )?
+                })
+            }
+            SqlType::AggregateFunction(function) => {
+                W::wrap(AggregateFunctionColumnData::with_capacity(capacity, function))
+            }
+            SqlType::Json | SqlType::Object(_) => {
+                W::wrap(ObjectColumnData::with_capacity(sql_type, capacity))
+            }
         })
     }
 }
 
 fn parse_fixed_string(source: &str) -> Option<usize> {
-    if !source.starts_with("FixedString") {
+    if !source.starts_with("FixedString(") || !source.ends_with(')') {
         return None;
     }
 
@@ -202,7 +352,7 @@ fn parse_fixed_string(source: &str) -> Option<usize> {
 }
 
 fn parse_nullable_type(source: &str) -> Option<&str> {
-    if !source.starts_with("Nullable") {
+    if !source.starts_with("Nullable(") || !source.ends_with(')') {
         return None;
     }
 
@@ -224,6 +374,131 @@ fn parse_array_type(source: &str) -> Option<&str> {
     Some(inner_type)
 }
 
+fn parse_tuple_type(source: &str) -> Option<Vec<(Option<&str>, &str)>> {
+    if !source.starts_with("Tuple(") || !source.ends_with(')') {
+        return None;
+    }
+
+    let elements = split_top_level(&source[6..source.len() - 1])?;
+    let mut result = Vec::with_capacity(elements.len());
+
+    for element in elements {
+        if element.is_empty() {
+            return None;
+        }
+
+        // `name Type` for named tuples. A space inside the parentheses of
+        // the type itself (e.g. `DateTime64(3, 'UTC')`) does not count.
+        let space = element.find(char::is_whitespace);
+        let named = match (space, element.find('(')) {
+            (Some(space), Some(paren)) => space < paren,
+            (Some(_), None) => true,
+            _ => false
+        };
+
+        match space {
+            Some(space) if named => {
+                let (name, element_type) = element.split_at(space);
+                result.push((Some(name), element_type.trim()));
+            }
+            _ => result.push((None, element))
+        }
+    }
+
+    Some(result)
+}
+
+fn parse_map_type(source: &str) -> Option<(&str, &str)> {
+    if !source.starts_with("Map(") || !source.ends_with(')') {
+        return None;
+    }
+
+    match split_top_level(&source[4..source.len() - 1])?.as_slice() {
+        [key_type, value_type] if !key_type.is_empty() && !value_type.is_empty() => {
+            Some((key_type, value_type))
+        }
+        _ => None
+    }
+}
+
+fn parse_simple_aggregate_function_type(source: &str) -> Option<(&str, &str)> {
+    if !source.starts_with("SimpleAggregateFunction(") || !source.ends_with(')') {
+        return None;
+    }
+
+    match split_top_level(&source[24..source.len() - 1])?.as_slice() {
+        [function, inner_type] if !function.is_empty() && !inner_type.is_empty() => {
+            Some((function, inner_type))
+        }
+        _ => None
+    }
+}
+
+fn parse_aggregate_function_type(source: &str) -> Option<&str> {
+    if !source.starts_with("AggregateFunction(") || !source.ends_with(')') {
+        return None;
+    }
+
+    Some(&source[18..source.len() - 1])
+}
+
+fn parse_object_type(source: &str) -> Option<&str> {
+    if !source.starts_with("Object('") || !source.ends_with("')") {
+        return None;
+    }
+
+    Some(&source[8..source.len() - 2])
+}
+
+/// Splits a list of type parameters on the commas that are not nested inside
+/// parentheses or quoted strings. Returns `None` when they are unbalanced.
+pub(crate) fn split_top_level(source: &str) -> Option<Vec<&str>> {
+    let mut result = Vec::new();
+    let mut depth = 0_usize;
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (idx, c) in source.char_indices() {
+        if quoted {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '\'' => quoted = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '\'' => quoted = true,
+            '(' => depth += 1,
+            ')' => depth = depth.checked_sub(1)?,
+            ',' if depth == 0 => {
+                result.push(source[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if depth != 0 || quoted {
+        return None;
+    }
+
+    result.push(source[start..].trim());
+    Some(result)
+}
+
+fn parse_low_cardinality_type(source: &str) -> Option<&str> {
+    if !source.starts_with("LowCardinality(") || !source.ends_with(')') {
+        return None;
+    }
+
+    let inner_type = &source[15..source.len() - 1];
+    Some(inner_type)
+}
+
 fn parse_decimal(source: &str) -> Option<(u8, u8, NoBits)> {
     if source.len() < 12 {
         return None;
@@ -249,6 +524,9 @@ fn parse_decimal(source: &str) -> Option<(u8, u8, NoBits)> {
                 b"Decimal64" => {
                     nobits = Some(NoBits::N64);
                 }
+                b"Decimal128" => {
+                    nobits = Some(NoBits::N128);
+                }
                 _ => return None
             }
             params_indexes.0 = Some(idx);
@@ -295,7 +573,8 @@ fn parse_decimal(source: &str) -> Option<(u8, u8, NoBits)> {
         (None, Some(scale), Some(bits)) => {
             let precision = match bits {
                 NoBits::N32 => 9,
-                NoBits::N64 => 18
+                NoBits::N64 => 18,
+                NoBits::N128 => 38
             };
             Some((precision, scale, bits))
         }
@@ -311,11 +590,11 @@ enum EnumSize {
 fn parse_enum8(input: &str) -> Option<Vec<(String, i8)>> {
     match parse_enum(EnumSize::Enum8, input) {
         Some(result) => {
-            let res: Vec<(String, i8)> = result
+            let res: Option<Vec<(String, i8)>> = result
                 .iter()
-                .map(|(key, val)| (key.clone(), *val as i8))
+                .map(|(key, val)| i8::try_from(*val).ok().map(|val| (key.clone(), val)))
                 .collect();
-            Some(res)
+            res
         }
         None => None
     }
@@ -371,6 +650,27 @@ fn parse_enum(size: EnumSize, input: &str) -> Option<Vec<(String, i16)>> {
     }
 }
 
+fn parse_date_time(source: &str) -> Option<String> {
+    let word_syms = token('\\').with(any()).or(none_of("'".chars()));
+    let word = token('\'')
+        .with(many::<String, _, _>(word_syms))
+        .skip(token('\''));
+
+    let mut parser = spaces()
+        .with(string("DateTime"))
+        .skip(spaces())
+        .skip(token('('))
+        .skip(spaces())
+        .with(word)
+        .skip(spaces())
+        .skip(token(')'));
+
+    match parser.parse(source) {
+        Ok((timezone, remain)) if remain.is_empty() => Some(timezone),
+        _ => None
+    }
+}
+
 fn parse_date_time64(source: &str) -> Option<(u32, Option<String>)> {
     let integer = many1::<String, _, _>(digit()).and_then(|digits| {
         digits
@@ -401,7 +701,9 @@ fn parse_date_time64(source: &str) -> Option<(u32, Option<String>)> {
         .skip(token(')'));
 
     match parser.parse(source) {
-        Ok((pair, remain)) if remain.is_empty() => Some(pair),
+        Ok((pair, remain)) if remain.is_empty() && pair.0 <= MAX_DATETIME64_PRECISION => {
+            Some(pair)
+        }
         _ => None
     }
 }
@@ -421,7 +723,8 @@ mod test {
     fn test_parse_decimal() {
         assert_eq!(parse_decimal("Decimal(9, 4)"), Some((9, 4, NoBits::N32)));
         assert_eq!(parse_decimal("Decimal(10, 4)"), Some((10, 4, NoBits::N64)));
-        assert_eq!(parse_decimal("Decimal(20, 4)"), None);
+        assert_eq!(parse_decimal("Decimal(20, 4)"), Some((20, 4, NoBits::N128)));
+        assert_eq!(parse_decimal("Decimal(40, 4)"), None);
         assert_eq!(parse_decimal("Decimal(2000, 4)"), None);
         assert_eq!(parse_decimal("Decimal(3, 4)"), None);
         assert_eq!(parse_decimal("Decimal(20, -4)"), None);
@@ -440,12 +743,62 @@ mod test {
         assert_eq!(parse_nullable_type("Nullable(Int8)"), Some("Int8"));
         assert_eq!(parse_nullable_type("Int8"), None);
         assert_eq!(parse_nullable_type("Nullable(Nullable(Int8))"), None);
+        assert_eq!(parse_nullable_type("Nullable(String)"), Some("String"));
+        assert_eq!(parse_nullable_type("Nullable(Nothing)"), Some("Nothing"));
+        assert_eq!(parse_nullable_type("Nullable"), None);
+    }
+
+    #[test]
+    fn test_parse_low_cardinality_type() {
+        assert_eq!(parse_low_cardinality_type("LowCardinality(String)"), Some("String"));
+        assert_eq!(
+            parse_low_cardinality_type("LowCardinality(Nullable(String))"),
+            Some("Nullable(String)")
+        );
+        assert_eq!(parse_low_cardinality_type("String"), None);
+    }
+
+    #[test]
+    fn test_parse_map_type() {
+        assert_eq!(parse_map_type("Map(String, UInt64)"), Some(("String", "UInt64")));
+        assert_eq!(
+            parse_map_type("Map(String, Map(String, Array(UInt8)))"),
+            Some(("String", "Map(String, Array(UInt8))"))
+        );
+        assert_eq!(parse_map_type("Map(String)"), None);
+        assert_eq!(parse_map_type("Map(String, UInt8, UInt8)"), None);
+        assert_eq!(parse_map_type("Array(String)"), None);
+    }
+
+    #[test]
+    fn test_parse_tuple_type() {
+        assert_eq!(
+            parse_tuple_type("Tuple(String, UInt32)"),
+            Some(vec![(None, "String"), (None, "UInt32")])
+        );
+        assert_eq!(
+            parse_tuple_type("Tuple(name String, age UInt32)"),
+            Some(vec![(Some("name"), "String"), (Some("age"), "UInt32")])
+        );
+        assert_eq!(
+            parse_tuple_type("Tuple(DateTime64(3, 'UTC'), Enum8('a,b' = 1, ')' = 2))"),
+            Some(vec![(None, "DateTime64(3, 'UTC')"), (None, "Enum8('a,b' = 1, ')' = 2)")])
+        );
+        assert_eq!(
+            parse_tuple_type("Tuple(Array(Tuple(UInt8, String)), ts DateTime)"),
+            Some(vec![(None, "Array(Tuple(UInt8, String))"), (Some("ts"), "DateTime")])
+        );
+        assert_eq!(parse_tuple_type("Tuple(String, )"), None);
+        assert_eq!(parse_tuple_type("Tuple(Array(String)"), None);
+        assert_eq!(parse_tuple_type("String"), None);
     }
 
     #[test]
     fn test_parse_fixed_string() {
         assert_eq!(parse_fixed_string("FixedString(8)"), Some(8_usize));
+        assert_eq!(parse_fixed_string("FixedString(16)"), Some(16_usize));
         assert_eq!(parse_fixed_string("FixedString(zz)"), None);
+        assert_eq!(parse_fixed_string("FixedString"), None);
         assert_eq!(parse_fixed_string("Int8"), None);
     }
 
@@ -464,6 +817,24 @@ mod test {
         assert_eq!(res, vec![("a_".to_owned(), -128), ("b&".to_owned(), 0)])
     }
 
+    #[test]
+    fn test_parse_enum8_escaped_names() {
+        let enum8 = r"Enum8('it\'s' = -1, 'a,b' = 2, 'c\\d' = 3)";
+
+        let res = parse_enum8(enum8).unwrap();
+        assert_eq!(res, vec![
+            ("it's".to_owned(), -1),
+            ("a,b".to_owned(), 2),
+            ("c\\d".to_owned(), 3)
+        ])
+    }
+
+    #[test]
+    fn test_parse_enum8_out_of_range() {
+        assert_eq!(parse_enum8("Enum8('a' = 128)"), None);
+        assert!(parse_enum16("Enum16('a' = 128)").is_some());
+    }
+
     #[test]
     fn test_parse_enum8_single() {
         let enum8 = "Enum8 ('a' = 1)";
@@ -590,6 +961,17 @@ mod test {
         assert!(dbg!(parse_enum16(enum16)).is_none());
     }
 
+    #[test]
+    fn test_parse_date_time() {
+        assert_eq!(
+            parse_date_time(" DateTime ( 'Europe/Berlin' )"),
+            Some("Europe/Berlin".to_string())
+        );
+        assert_eq!(parse_date_time("DateTime('UTC')"), Some("UTC".to_string()));
+        assert_eq!(parse_date_time("DateTime"), None);
+        assert_eq!(parse_date_time("DateTime64(3, 'UTC')"), None);
+    }
+
     #[test]
     fn test_parse_date_time64() {
         let source = " DateTime64 ( 3 , 'Europe/Moscow' )";
@@ -603,4 +985,18 @@ mod test {
         let res = parse_date_time64(source).unwrap();
         assert_eq!(res, (5, None))
     }
+
+    #[test]
+    fn test_parse_decimal128() {
+        assert_eq!(parse_decimal("Decimal(18, 4)"), Some((18, 4, NoBits::N64)));
+        assert_eq!(parse_decimal("Decimal(38, 10)"), Some((38, 10, NoBits::N128)));
+        assert_eq!(parse_decimal("Decimal128(6)"), Some((38, 6, NoBits::N128)));
+        assert_eq!(parse_decimal("Decimal(39, 10)"), None);
+    }
+
+    #[test]
+    fn test_parse_date_time64_precision_out_of_range() {
+        assert_eq!(parse_date_time64("DateTime64(9)"), Some((9, None)));
+        assert_eq!(parse_date_time64("DateTime64(10)"), None);
+    }
 }
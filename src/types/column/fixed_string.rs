@@ -1,10 +1,11 @@
-use std::cmp;
-
 use super::column_data::ColumnData;
 use crate::binary::Encoder;
 use crate::binary::ReadEx;
+use crate::errors::Error;
+use crate::errors::FromSqlError;
 use crate::errors::Result;
 use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::from_sql::*;
 use crate::types::Column;
 use crate::types::ColumnType;
@@ -17,11 +18,6 @@ pub(crate) struct FixedStringColumnData {
     str_len: usize
 }
 
-pub(crate) struct FixedStringAdapter<K: ColumnType> {
-    pub(crate) column: Column<K>,
-    pub(crate) str_len: usize
-}
-
 pub(crate) struct NullableFixedStringAdapter<K: ColumnType> {
     pub(crate) column: Column<K>,
     pub(crate) str_len: usize
@@ -46,6 +42,34 @@ impl FixedStringColumnData {
 
         Ok(instance)
     }
+
+    /// Copies a string column, right-padding every value with zero bytes.
+    pub(crate) fn from_column<K: ColumnType>(column: &Column<K>, str_len: usize) -> Result<Self> {
+        let mut instance = Self::with_capacity(column.len(), str_len);
+        for index in 0..column.len() {
+            instance.push_bytes(column.at(index).as_bytes()?)?;
+        }
+        Ok(instance)
+    }
+
+    fn push_bytes(&mut self, bs: &[u8]) -> Result<()> {
+        check_str_len(bs, self.str_len)?;
+
+        let old_len = self.buffer.len();
+        self.buffer.extend_from_slice(bs);
+        self.buffer.resize(old_len + self.str_len, 0_u8);
+        Ok(())
+    }
+}
+
+pub(crate) fn check_str_len(bs: &[u8], str_len: usize) -> Result<()> {
+    if bs.len() > str_len {
+        return Err(Error::FromSql(FromSqlError::FixedStringOverflow {
+            len: bs.len(),
+            str_len
+        }));
+    }
+    Ok(())
 }
 
 impl ColumnData for FixedStringColumnData {
@@ -53,7 +77,13 @@ impl ColumnData for FixedStringColumnData {
         SqlType::FixedString(self.str_len)
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         let start_index = start * self.str_len;
         let end_index = end * self.str_len;
         encoder.write_bytes(&self.buffer[start_index..end_index]);
@@ -64,11 +94,11 @@ impl ColumnData for FixedStringColumnData {
     }
 
     fn push(&mut self, value: Value) {
-        let bs: String = String::from(value);
-        let l = cmp::min(bs.len(), self.str_len);
-        let old_len = self.buffer.len();
-        self.buffer.extend_from_slice(&bs.as_bytes()[0..l]);
-        self.buffer.resize(old_len + (self.str_len - l), 0_u8);
+        if let Value::String(bs) = value {
+            self.push_bytes(&bs).unwrap();
+        } else {
+            panic!("value should be a string")
+        }
     }
 
     fn at(&self, index: usize) -> ValueRef {
@@ -92,59 +122,18 @@ impl ColumnData for FixedStringColumnData {
     }
 }
 
-impl<K: ColumnType> ColumnData for FixedStringAdapter<K> {
-    fn sql_type(&self) -> SqlType {
-        SqlType::FixedString(self.str_len)
-    }
-
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
-        let mut buffer = Vec::with_capacity(self.str_len);
-        for index in start..end {
-            buffer.clear();
-            match self.column.at(index) {
-                ValueRef::String(_) => {
-                    let string_ref = self.column.at(index).as_bytes().unwrap();
-                    buffer.extend(string_ref.as_ref());
-                }
-                ValueRef::Array(SqlType::UInt8, vs) => {
-                    let mut string_val: Vec<u8> = Vec::with_capacity(vs.len());
-                    for v in vs.iter() {
-                        let byte: u8 = v.clone().into();
-                        string_val.push(byte);
-                    }
-                    let string_ref: &[u8] = string_val.as_ref();
-                    buffer.extend(string_ref);
-                }
-                _ => unimplemented!()
-            }
-            buffer.resize(self.str_len, 0);
-            encoder.write_bytes(&buffer[..]);
-        }
-    }
-
-    fn len(&self) -> usize {
-        self.column.len()
-    }
-
-    fn push(&mut self, _value: Value) {
-        unimplemented!()
-    }
-
-    fn at(&self, index: usize) -> ValueRef {
-        self.column.at(index)
-    }
-
-    fn clone_instance(&self) -> BoxColumnData {
-        unimplemented!()
-    }
-}
-
 impl<K: ColumnType> ColumnData for NullableFixedStringAdapter<K> {
     fn sql_type(&self) -> SqlType {
         SqlType::Nullable(SqlType::FixedString(self.str_len).into())
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         let size = end - start;
         let mut nulls = vec![0; size];
         let mut values: Vec<Option<&[u8]>> = vec![None; size];
@@ -185,3 +174,67 @@ impl<K: ColumnType> ColumnData for NullableFixedStringAdapter<K> {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use chrono_tz::Tz;
+
+    use super::*;
+    use crate::types::Simple;
+    use crate::Block;
+
+    #[test]
+    fn test_padding() {
+        let block = Block::<Simple>::new()
+            .column_as("id", vec!["abc", "0123456789abcdef"], SqlType::FixedString(16))
+            .unwrap();
+        assert_eq!(block.columns()[0].sql_type(), SqlType::FixedString(16));
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 2, SerializationSettings::default());
+        let mut expected = b"abc".to_vec();
+        expected.resize(16, 0);
+        expected.extend_from_slice(b"0123456789abcdef");
+        assert_eq!(encoder.get_buffer(), expected);
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+        assert_eq!(block, rblock);
+
+        let short: &[u8] = rblock.get(0, "id").unwrap();
+        assert_eq!(short, &expected[..16]);
+        let exact: &[u8] = rblock.get(1, "id").unwrap();
+        assert_eq!(exact, b"0123456789abcdef");
+    }
+
+    #[test]
+    fn test_too_long() {
+        let result = Block::<Simple>::new().column_as(
+            "id",
+            vec!["abc", "0123456789abcdefg"],
+            SqlType::FixedString(16)
+        );
+
+        assert!(matches!(
+            result,
+            Err(Error::FromSql(FromSqlError::FixedStringOverflow { len: 17, str_len: 16 }))
+        ));
+    }
+
+    #[test]
+    fn test_nullable_too_long() {
+        let result = Block::<Simple>::new().column_as(
+            "id",
+            vec![None, Some("abcde")],
+            SqlType::Nullable(&SqlType::FixedString(4))
+        );
+        assert!(matches!(
+            result,
+            Err(Error::FromSql(FromSqlError::FixedStringOverflow { len: 5, str_len: 4 }))
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use chrono_tz::Tz;
+
+use crate::binary::Encoder;
+use crate::binary::ReadEx;
+use crate::errors::Result;
+use crate::types::column::array::ArrayColumnData;
+use crate::types::column::column_data::ArcColumnData;
+use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
+use crate::types::column::list::List;
+use crate::types::column::tuple::TupleColumnData;
+use crate::types::column::ArcColumnWrapper;
+use crate::types::column::ColumnData;
+use crate::types::column::ColumnFrom;
+use crate::types::column::ColumnWrapper;
+use crate::types::SqlType;
+use crate::types::Value;
+use crate::types::ValueRef;
+
+/// `Point`, stored as `Tuple(Float64, Float64)`.
+pub type Point = (f64, f64);
+/// `Ring`, stored as `Array(Point)`.
+pub type Ring = Vec<Point>;
+/// `Polygon`, stored as `Array(Ring)`: the outer ring, then the holes.
+pub type Polygon = Vec<Ring>;
+/// `MultiPolygon`, stored as `Array(Polygon)`.
+pub type MultiPolygon = Vec<Polygon>;
+
+/// The type a geo type is stored as.
+pub(crate) fn storage_type(sql_type: &SqlType) -> SqlType {
+    match sql_type {
+        SqlType::Point => {
+            SqlType::Tuple(vec![(None, &SqlType::Float64), (None, &SqlType::Float64)])
+        }
+        SqlType::Ring => SqlType::Array(&SqlType::Point),
+        SqlType::Polygon => SqlType::Array(&SqlType::Ring),
+        SqlType::MultiPolygon => SqlType::Array(&SqlType::Polygon),
+        _ => unreachable!()
+    }
+}
+
+/// A geo column only renames the column it is stored as, every value is the
+/// tuple or array of that column.
+pub(crate) struct GeoColumnData {
+    sql_type: SqlType,
+    inner: ArcColumnData
+}
+
+impl GeoColumnData {
+    pub(crate) fn load<R: ReadEx>(
+        reader: &mut R,
+        sql_type: SqlType,
+        size: usize,
+        tz: Tz,
+        settings: SerializationSettings
+    ) -> Result<Self> {
+        let type_name = storage_type(&sql_type).to_string();
+        let inner = <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(
+            reader,
+            &type_name,
+            size,
+            tz,
+            settings
+        )?;
+        Ok(GeoColumnData { sql_type, inner })
+    }
+
+    pub(crate) fn with_capacity(sql_type: SqlType, tz: Tz, capacity: usize) -> Result<Self> {
+        let inner = <dyn ColumnData>::from_type::<ArcColumnWrapper>(
+            storage_type(&sql_type),
+            tz,
+            capacity
+        )?;
+        Ok(GeoColumnData { sql_type, inner })
+    }
+}
+
+impl ColumnFrom for Vec<Point> {
+    fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
+        let (xs, ys): (Vec<f64>, Vec<f64>) = source.into_iter().unzip();
+        let inner = TupleColumnData {
+            inner: vec![
+                Vec::column_from::<ArcColumnWrapper>(xs),
+                Vec::column_from::<ArcColumnWrapper>(ys),
+            ],
+            names: Vec::new()
+        };
+
+        W::wrap(GeoColumnData {
+            sql_type: SqlType::Point,
+            inner: Arc::new(inner)
+        })
+    }
+}
+
+impl ColumnFrom for Vec<Ring> {
+    fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
+        geo_array::<W, _>(SqlType::Ring, source)
+    }
+}
+
+impl ColumnFrom for Vec<Polygon> {
+    fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
+        geo_array::<W, _>(SqlType::Polygon, source)
+    }
+}
+
+impl ColumnFrom for Vec<MultiPolygon> {
+    fn column_from<W: ColumnWrapper>(source: Self) -> W::Wrapper {
+        geo_array::<W, _>(SqlType::MultiPolygon, source)
+    }
+}
+
+fn geo_array<W: ColumnWrapper, T>(sql_type: SqlType, source: Vec<Vec<T>>) -> W::Wrapper
+where Vec<T>: ColumnFrom {
+    let mut offsets = List::with_capacity(source.len());
+    let mut offset = 0_u64;
+    for items in &source {
+        offset += items.len() as u64;
+        offsets.push(offset);
+    }
+
+    let items: Vec<T> = source.into_iter().flatten().collect();
+    let inner = ArrayColumnData {
+        inner: Vec::column_from::<ArcColumnWrapper>(items),
+        offsets
+    };
+
+    W::wrap(GeoColumnData {
+        sql_type,
+        inner: Arc::new(inner)
+    })
+}
+
+impl ColumnData for GeoColumnData {
+    fn sql_type(&self) -> SqlType {
+        self.sql_type.clone()
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
+        self.inner.save(encoder, start, end, settings);
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn push(&mut self, value: Value) {
+        let inner_column = Arc::get_mut(&mut self.inner).unwrap();
+        inner_column.push(value);
+    }
+
+    fn at(&self, index: usize) -> ValueRef<'_> {
+        self.inner.at(index)
+    }
+
+    fn clone_instance(&self) -> BoxColumnData {
+        Box::new(Self {
+            sql_type: self.sql_type.clone(),
+            inner: self.inner.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::types::Block;
+
+    #[test]
+    fn test_write_and_read() {
+        let square = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)];
+        let hole = vec![(1.0, 1.0), (2.0, 1.0), (1.0, 2.0)];
+        let polygons: Vec<Polygon> = vec![vec![square.clone(), hole.clone()], vec![]];
+        let block = Block::new()
+            .column("shape", polygons)
+            .column("center", vec![(2.0, 2.0), (0.5, -0.5)]);
+
+        assert_eq!(block.columns()[0].sql_type().to_string(), "Polygon");
+        assert_eq!(block.columns()[1].sql_type().to_string(), "Point");
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 2, SerializationSettings::default());
+
+        let mut expected = Encoder::new();
+        for offset in &[2_u64, 2] {
+            expected.write(*offset);
+        }
+        for offset in &[4_u64, 7] {
+            expected.write(*offset);
+        }
+        for (x, _) in square.iter().chain(hole.iter()) {
+            expected.write(*x);
+        }
+        for (_, y) in square.iter().chain(hole.iter()) {
+            expected.write(*y);
+        }
+        assert_eq!(encoder.get_buffer_ref(), expected.get_buffer_ref());
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+
+        assert_eq!(rblock.columns()[0].sql_type().to_string(), "Polygon");
+        assert_eq!(rblock.columns()[1].sql_type().to_string(), "Point");
+        assert_eq!(block, rblock);
+    }
+}
@@ -9,6 +9,7 @@ use crate::binary::Encoder;
 use crate::binary::ReadEx;
 use crate::errors::Result;
 use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::column::nullable::NullableColumnData;
 use crate::types::column::ColumnWrapper;
 use crate::types::SqlType;
@@ -265,7 +266,7 @@ pub(crate) struct IpColumnData<V: IpVersion> {
 impl<V: IpVersion> IpColumnData<V> {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            inner: vec![0; capacity * V::size()],
+            inner: Vec::with_capacity(capacity * V::size()),
             phantom: PhantomData
         }
     }
@@ -286,7 +287,13 @@ impl<V: IpVersion> ColumnData for IpColumnData<V> {
         V::sql_type()
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         let start_index = start * V::size();
         let end_index = end * V::size();
 
@@ -319,3 +326,132 @@ impl<V: IpVersion> ColumnData for IpColumnData<V> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use chrono_tz::Tz;
+
+    use super::*;
+    use crate::row;
+    use crate::types::Simple;
+    use crate::Block;
+
+    #[test]
+    fn test_ipv4_byte_order() {
+        // IPv4 is a UInt32 on the wire, so `toIPv4('1.2.3.4')` arrives little-endian.
+        let ip: Ipv4Addr = "1.2.3.4".parse().unwrap();
+        let block = Block::<Simple>::new().column("ip", vec![ip]);
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 1, SerializationSettings::default());
+        assert_eq!(encoder.get_buffer(), vec![4, 3, 2, 1]);
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+        assert_eq!(rblock.columns()[0].sql_type(), SqlType::Ipv4);
+
+        let actual: Ipv4Addr = rblock.get(0, "ip").unwrap();
+        assert_eq!(actual, ip);
+        assert_eq!(format!("{}", rblock.columns()[0].at(0)), "1.2.3.4");
+    }
+
+    #[test]
+    fn test_ipv4_from_row() {
+        let ip: Ipv4Addr = "192.168.0.1".parse().unwrap();
+
+        let mut block = Block::<Simple>::new();
+        block.push(row! { ip: ip }).unwrap();
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 1, SerializationSettings::default());
+        assert_eq!(encoder.get_buffer(), vec![1, 0, 168, 192]);
+    }
+
+    #[test]
+    fn test_ipv6() {
+        let ip: Ipv6Addr = "2001:db8::ff00:42:8329".parse().unwrap();
+        let block = Block::<Simple>::new().column("ip", vec![ip]);
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 1, SerializationSettings::default());
+        assert_eq!(encoder.get_buffer(), ip.octets().to_vec());
+
+        let actual: Ipv6Addr = block.get(0, "ip").unwrap();
+        assert_eq!(actual, ip);
+    }
+
+    #[test]
+    fn test_array_of_ipv6() {
+        let ips: Vec<Ipv6Addr> = vec!["::1".parse().unwrap(), "fe80::1".parse().unwrap()];
+        let array = |ips: &[Ipv6Addr]| {
+            let values = ips.iter().map(|ip| Value::from(*ip)).collect();
+            Value::Array(&SqlType::Ipv6, Arc::new(values))
+        };
+
+        let mut block = Block::<Simple>::new();
+        block.push(row! { ips: array(&ips) }).unwrap();
+        block.push(row! { ips: array(&[]) }).unwrap();
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+
+        assert_eq!(rblock.columns()[0].sql_type().to_string(), "Array(IPv6)");
+        assert_eq!(block, rblock);
+        assert_eq!(format!("{}", rblock.columns()[0].at(0)), "[::1, fe80::1]");
+        assert_eq!(format!("{}", rblock.columns()[0].at(1)), "[]");
+    }
+
+    const UUID: &str = "936da01f-9abd-4d9d-80c7-02af85c822a8";
+
+    // The server stores a UUID as two UInt64 halves, each little-endian, so
+    // `toUUID('936da01f-9abd-4d9d-80c7-02af85c822a8')` goes over the wire as:
+    const UUID_BYTES: [u8; 16] = [
+        0x9d, 0x4d, 0xbd, 0x9a, 0x1f, 0xa0, 0x6d, 0x93, 0xa8, 0x22, 0xc8, 0x85, 0xaf, 0x02, 0xc7,
+        0x80,
+    ];
+
+    #[test]
+    fn test_uuid_byte_order() {
+        let uuid = uuid::Uuid::parse_str(UUID).unwrap();
+        let block = Block::<Simple>::new().column("id", vec![uuid]);
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 1, SerializationSettings::default());
+        assert_eq!(encoder.get_buffer(), UUID_BYTES);
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+
+        let actual: uuid::Uuid = rblock.get(0, "id").unwrap();
+        assert_eq!(actual, uuid);
+        assert_eq!(format!("{}", rblock.columns()[0].at(0)), UUID);
+    }
+
+    #[test]
+    fn test_uuid_from_row() {
+        let uuid = uuid::Uuid::parse_str(UUID).unwrap();
+
+        let mut block = Block::<Simple>::new();
+        block.push(row! { id: uuid, parent: Some(uuid) }).unwrap();
+        block.push(row! { id: uuid, parent: Option::<uuid::Uuid>::None }).unwrap();
+
+        assert_eq!(block.columns()[0].sql_type(), SqlType::Uuid);
+        assert_eq!(block.columns()[1].sql_type(), SqlType::Nullable(&SqlType::Uuid));
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 2, SerializationSettings::default());
+        assert_eq!(encoder.get_buffer(), [UUID_BYTES, UUID_BYTES].concat());
+
+        let parents: Vec<Option<uuid::Uuid>> =
+            (0..2).map(|i| block.get(i, "parent").unwrap()).collect();
+        assert_eq!(parents, vec![Some(uuid), None]);
+    }
+}
@@ -58,11 +58,13 @@ simple_num_iterable! {
     u16: UInt16,
     u32: UInt32,
     u64: UInt64,
+    u128: UInt128,
 
     i8: Int8,
     i16: Int16,
     i32: Int32,
     i64: Int64,
+    i128: Int128,
 
     f32: Float32,
     f64: Float64
@@ -267,7 +269,7 @@ impl<'a> DecimalIterator<'a> {
     unsafe fn next_unchecked_<T>(&mut self) -> Decimal
     where
         T: Copy + Sized,
-        i64: From<T>
+        i128: From<T>
     {
         let current_value = *(self.ptr as *const T);
         self.ptr = (self.ptr as *const T).offset(1) as *const u8;
@@ -284,7 +286,8 @@ impl<'a> DecimalIterator<'a> {
     unsafe fn next_unchecked(&mut self) -> Decimal {
         match self.nobits {
             NoBits::N32 => self.next_unchecked_::<i32>(),
-            NoBits::N64 => self.next_unchecked_::<i64>()
+            NoBits::N64 => self.next_unchecked_::<i64>(),
+            NoBits::N128 => self.next_unchecked_::<i128>()
         }
     }
 
@@ -293,7 +296,8 @@ impl<'a> DecimalIterator<'a> {
         unsafe {
             match self.nobits {
                 NoBits::N32 => self.ptr = (self.ptr as *const i32).add(n) as *const u8,
-                NoBits::N64 => self.ptr = (self.ptr as *const i64).add(n) as *const u8
+                NoBits::N64 => self.ptr = (self.ptr as *const i64).add(n) as *const u8,
+                NoBits::N128 => self.ptr = (self.ptr as *const i128).add(n) as *const u8
             }
         }
     }
@@ -304,7 +308,8 @@ impl<'a> ExactSizeIterator for DecimalIterator<'a> {
     fn len(&self) -> usize {
         let size = match self.nobits {
             NoBits::N32 => mem::size_of::<i32>(),
-            NoBits::N64 => mem::size_of::<i64>()
+            NoBits::N64 => mem::size_of::<i64>(),
+            NoBits::N128 => mem::size_of::<i128>()
         };
         (self.end as usize - self.ptr as usize) / size
     }
@@ -735,7 +740,8 @@ impl<'a> Iterable<'a, Simple> for Decimal {
         let end = unsafe {
             match nobits {
                 NoBits::N32 => (ptr as *const u32).add(size) as *const u8,
-                NoBits::N64 => (ptr as *const u64).add(size) as *const u8
+                NoBits::N64 => (ptr as *const u64).add(size) as *const u8,
+                NoBits::N128 => (ptr as *const u128).add(size) as *const u8
             }
         };
 
@@ -995,7 +1001,7 @@ mod test {
         let lo = Block::new().column("?", vec![1_u32, 2]);
         let hi = Block::new().column("?", vec![3_u32, 4, 5]);
 
-        let block = Block::concat(&[lo, hi]);
+        let block = Block::concat(&[lo, hi]).unwrap();
 
         let columns = block.columns()[0].iter::<u32>().unwrap();
         let actual: Vec<_> = columns.collect();
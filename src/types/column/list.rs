@@ -24,6 +24,10 @@ where T: StatBuffer + Unmarshal<T> + Marshal + Copy + Sync + 'static
         self.data[index]
     }
 
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
     pub fn push(&mut self, value: T) {
         self.data.push(value);
     }
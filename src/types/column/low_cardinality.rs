@@ -0,0 +1,519 @@
+use std::collections::HashMap;
+
+use chrono_tz::Tz;
+
+use crate::binary::Encoder;
+use crate::binary::ReadEx;
+use crate::errors::Result;
+use crate::protocols::DBMS_MIN_REVISION_WITH_LOW_CARDINALITY_TYPE;
+use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
+use crate::types::column::BoxColumnWrapper;
+use crate::types::column::ColumnData;
+use crate::types::column::Either;
+use crate::types::SqlType;
+use crate::types::Value;
+use crate::types::ValueRef;
+
+/// `SharedDictionariesWithAdditionalKeys`, the only keys serialization version in use.
+const KEYS_SERIALIZATION_VERSION: u64 = 1;
+
+const INDEX_TYPE_MASK: u64 = 0xff;
+const NEED_GLOBAL_DICTIONARY_BIT: u64 = 1 << 8;
+const HAS_ADDITIONAL_KEYS_BIT: u64 = 1 << 9;
+const NEED_UPDATE_DICTIONARY_BIT: u64 = 1 << 10;
+
+const INDEX_TYPE_UINT8: u64 = 0;
+const INDEX_TYPE_UINT16: u64 = 1;
+const INDEX_TYPE_UINT32: u64 = 2;
+const INDEX_TYPE_UINT64: u64 = 3;
+
+/// Dictionary encoded column. `dictionary` holds the distinct values of the
+/// nested (never nullable) type and `keys` holds one dictionary position per
+/// row. Position 0 holds the default value, it stands for `NULL` alone when
+/// the column is `LowCardinality(Nullable(T))` and a non-null default gets a
+/// position of its own.
+pub(crate) struct LowCardinalityColumnData {
+    pub(crate) dictionary: BoxColumnData,
+    pub(crate) keys: Vec<u64>,
+    pub(crate) nullable: bool,
+    index: Option<HashMap<Vec<u8>, u64>>
+}
+
+impl LowCardinalityColumnData {
+    pub(crate) fn new(mut dictionary: BoxColumnData, nullable: bool, capacity: usize) -> Self {
+        if dictionary.len() == 0 {
+            dictionary.push(Value::default(dictionary.sql_type()));
+        }
+
+        LowCardinalityColumnData {
+            dictionary,
+            keys: Vec::with_capacity(capacity),
+            nullable,
+            index: None
+        }
+    }
+
+    pub(crate) fn with_capacity(
+        inner_type: SqlType,
+        timezone: Tz,
+        capacity: usize
+    ) -> Result<Self> {
+        let (nested_type, nullable) = match inner_type {
+            SqlType::Nullable(nested_type) => (nested_type.clone(), true),
+            _ => (inner_type, false)
+        };
+
+        let dictionary =
+            <dyn ColumnData>::from_type::<BoxColumnWrapper>(nested_type, timezone, capacity)?;
+        Ok(Self::new(dictionary, nullable, capacity))
+    }
+
+    pub(crate) fn load<R: ReadEx>(
+        reader: &mut R,
+        nested_type: &str,
+        nullable: bool,
+        size: usize,
+        tz: Tz,
+        settings: SerializationSettings
+    ) -> Result<Self> {
+        if size == 0 {
+            let dictionary = <dyn ColumnData>::load_data::<BoxColumnWrapper, _>(
+                reader,
+                nested_type,
+                0,
+                tz,
+                settings
+            )?;
+            return Ok(Self::new(dictionary, nullable, 0));
+        }
+
+        let version: u64 = reader.read_scalar()?;
+        if version != KEYS_SERIALIZATION_VERSION {
+            let message = format!(
+                "Unsupported LowCardinality keys serialization version {}.",
+                version
+            );
+            return Err(message.into());
+        }
+
+        let index_type: u64 = reader.read_scalar()?;
+        if index_type & NEED_GLOBAL_DICTIONARY_BIT != 0 {
+            let message = "LowCardinality columns with a global dictionary are not supported.";
+            return Err(message.into());
+        }
+        if index_type & HAS_ADDITIONAL_KEYS_BIT == 0 {
+            return Err("LowCardinality column has no additional keys.".into());
+        }
+
+        let dictionary_size: u64 = reader.read_scalar()?;
+        let dictionary = <dyn ColumnData>::load_data::<BoxColumnWrapper, _>(
+            reader,
+            nested_type,
+            dictionary_size as usize,
+            tz,
+            settings
+        )?;
+
+        let rows: u64 = reader.read_scalar()?;
+        if rows as usize != size {
+            let message = format!("LowCardinality column has {} keys, expected {}.", rows, size);
+            return Err(message.into());
+        }
+
+        let mut keys = Vec::with_capacity(size);
+        for _ in 0..size {
+            let key = match index_type & INDEX_TYPE_MASK {
+                INDEX_TYPE_UINT8 => u64::from(reader.read_scalar::<u8>()?),
+                INDEX_TYPE_UINT16 => u64::from(reader.read_scalar::<u16>()?),
+                INDEX_TYPE_UINT32 => u64::from(reader.read_scalar::<u32>()?),
+                INDEX_TYPE_UINT64 => reader.read_scalar::<u64>()?,
+                other => {
+                    let message = format!("Unknown LowCardinality index type {}.", other);
+                    return Err(message.into());
+                }
+            };
+
+            if key >= dictionary_size {
+                let message = format!(
+                    "LowCardinality key {} is out of dictionary of size {}.",
+                    key, dictionary_size
+                );
+                return Err(message.into());
+            }
+            keys.push(key);
+        }
+
+        Ok(LowCardinalityColumnData {
+            dictionary,
+            keys,
+            nullable,
+            index: None
+        })
+    }
+
+    fn position_of(&mut self, value: Value) -> u64 {
+        let dictionary = &mut self.dictionary;
+        let first = self.nullable as usize;
+        let index = self.index.get_or_insert_with(|| {
+            let mut index = HashMap::with_capacity(dictionary.len());
+            for row in first..dictionary.len() {
+                index
+                    .entry(encode_row(dictionary.as_ref(), row))
+                    .or_insert(row as u64);
+            }
+            index
+        });
+
+        let mut scratch =
+            <dyn ColumnData>::from_type::<BoxColumnWrapper>(dictionary.sql_type(), Tz::Zulu, 1)
+                .unwrap();
+        scratch.push(value.clone());
+
+        *index
+            .entry(encode_row(scratch.as_ref(), 0))
+            .or_insert_with(|| {
+                dictionary.push(value);
+                (dictionary.len() - 1) as u64
+            })
+    }
+}
+
+/// Native encoding of a single row, used as the deduplication key.
+fn encode_row(column: &dyn ColumnData, row: usize) -> Vec<u8> {
+    let mut encoder = Encoder::new();
+    column.save(&mut encoder, row, row + 1, SerializationSettings::default());
+    encoder.get_buffer()
+}
+
+fn supported(settings: SerializationSettings) -> bool {
+    settings.client_revision >= DBMS_MIN_REVISION_WITH_LOW_CARDINALITY_TYPE
+}
+
+fn index_type_for(dictionary_size: usize) -> u64 {
+    if dictionary_size <= 1 << 8 {
+        INDEX_TYPE_UINT8
+    } else if dictionary_size <= 1 << 16 {
+        INDEX_TYPE_UINT16
+    } else if dictionary_size as u64 <= 1 << 32 {
+        INDEX_TYPE_UINT32
+    } else {
+        INDEX_TYPE_UINT64
+    }
+}
+
+impl ColumnData for LowCardinalityColumnData {
+    fn sql_type(&self) -> SqlType {
+        let nested_type = self.dictionary.sql_type();
+        let inner_type = if self.nullable {
+            SqlType::Nullable(nested_type.into())
+        } else {
+            nested_type
+        };
+        SqlType::LowCardinality(inner_type.into())
+    }
+
+    // Clients older than the type read it as its nested type.
+    fn serialized_type(&self, settings: SerializationSettings) -> SqlType {
+        match self.sql_type() {
+            SqlType::LowCardinality(inner_type) if !supported(settings) => inner_type.clone(),
+            sql_type => sql_type
+        }
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
+        if start == end {
+            return;
+        }
+
+        if !supported(settings) {
+            if self.nullable {
+                for key in &self.keys[start..end] {
+                    encoder.write((*key == 0) as u8);
+                }
+            }
+            for key in &self.keys[start..end] {
+                let position = *key as usize;
+                self.dictionary.save(encoder, position, position + 1, settings);
+            }
+            return;
+        }
+
+        // Only the dictionary entries used by `start..end` are written, so the
+        // keys are renumbered. Position 0 always stays first.
+        let mut positions = HashMap::new();
+        let mut used = vec![0_u64];
+        positions.insert(0_u64, 0_u64);
+
+        let keys: Vec<u64> = self.keys[start..end]
+            .iter()
+            .map(|key| {
+                *positions.entry(*key).or_insert_with(|| {
+                    used.push(*key);
+                    (used.len() - 1) as u64
+                })
+            })
+            .collect();
+
+        let index_type = index_type_for(used.len());
+
+        encoder.write(KEYS_SERIALIZATION_VERSION);
+        encoder.write(index_type | HAS_ADDITIONAL_KEYS_BIT | NEED_UPDATE_DICTIONARY_BIT);
+
+        encoder.write(used.len() as u64);
+        for position in used {
+            let position = position as usize;
+            self.dictionary.save(encoder, position, position + 1, settings);
+        }
+
+        encoder.write(keys.len() as u64);
+        for key in keys {
+            match index_type {
+                INDEX_TYPE_UINT8 => encoder.write(key as u8),
+                INDEX_TYPE_UINT16 => encoder.write(key as u16),
+                INDEX_TYPE_UINT32 => encoder.write(key as u32),
+                _ => encoder.write(key)
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn push(&mut self, value: Value) {
+        let value = match value {
+            Value::Nullable(Either::Left(_)) if self.nullable => {
+                self.keys.push(0);
+                return;
+            }
+            Value::Nullable(Either::Right(inner)) if self.nullable => *inner,
+            value => value
+        };
+
+        let position = self.position_of(value);
+        self.keys.push(position);
+    }
+
+    fn at(&self, index: usize) -> ValueRef<'_> {
+        let key = self.keys[index] as usize;
+
+        if !self.nullable {
+            return self.dictionary.at(key);
+        }
+
+        if key == 0 {
+            let sql_type = self.dictionary.sql_type();
+            ValueRef::Nullable(Either::Left(sql_type.into()))
+        } else {
+            let inner_value = self.dictionary.at(key);
+            ValueRef::Nullable(Either::Right(Box::new(inner_value)))
+        }
+    }
+
+    fn clone_instance(&self) -> BoxColumnData {
+        Box::new(Self {
+            dictionary: self.dictionary.clone_instance(),
+            keys: self.keys.clone(),
+            nullable: self.nullable,
+            index: self.index.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::types::block::BlockInfo;
+    use crate::types::column::array::ArrayColumnData;
+    use crate::types::column::list::List;
+    use crate::types::column::new_column;
+    use crate::types::column::round_trip;
+    use crate::types::column::ArcColumnWrapper;
+    use crate::types::column::ColumnFrom;
+    use crate::types::Column;
+    use crate::types::Simple;
+    use crate::Block;
+
+    fn low_cardinality<T>(name: &str, data: T, inner_type: SqlType) -> Column<Simple>
+    where T: ColumnFrom {
+        new_column::<Simple>(name, T::column_from::<ArcColumnWrapper>(data))
+            .cast_to(SqlType::LowCardinality(inner_type.into()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_load_server_block() {
+        // Laid out the way the server serializes `LowCardinality(String)`
+        // for the rows 'a', 'b', 'a', ''.
+        let mut encoder = Encoder::new();
+        BlockInfo::default().write(&mut encoder);
+        encoder.uvarint(1);
+        encoder.uvarint(4);
+        encoder.string("s");
+        encoder.string("LowCardinality(String)");
+        encoder.write(1_u64);
+        encoder.write(0x0600_u64);
+        encoder.write(3_u64);
+        encoder.string("");
+        encoder.string("a");
+        encoder.string("b");
+        encoder.write(4_u64);
+        encoder.write_bytes(&[1, 2, 1, 0]);
+        let expected = encoder.get_buffer();
+
+        let mut reader = Cursor::new(expected.as_slice());
+        let block = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+        assert_eq!(block.columns()[0].sql_type(), SqlType::LowCardinality(&SqlType::String));
+
+        let values: Vec<String> = (0..4).map(|i| block.get(i, "s").unwrap()).collect();
+        assert_eq!(values, vec!["a", "b", "a", ""]);
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        assert_eq!(encoder.get_buffer(), expected);
+    }
+
+    #[test]
+    fn test_nullable_round_trip() {
+        let data = vec![Some("x".to_string()), None, Some("y".to_string()), Some("x".to_string())];
+        let column = low_cardinality("s", data.clone(), SqlType::Nullable(&SqlType::String));
+        let block = Block::<Simple>::new().column("s", column);
+
+        let rblock = round_trip(&block);
+        assert_eq!(
+            rblock.columns()[0].sql_type(),
+            SqlType::LowCardinality(&SqlType::Nullable(&SqlType::String))
+        );
+        assert_eq!(block, rblock);
+
+        let values: Vec<Option<String>> = (0..4).map(|i| rblock.get(i, "s").unwrap()).collect();
+        assert_eq!(values, data);
+    }
+
+    #[test]
+    fn test_nullable_default_value() {
+        let data = vec![Some(String::new()), None, Some("x".to_string()), Some(String::new())];
+        let column = low_cardinality("s", data.clone(), SqlType::Nullable(&SqlType::String));
+        let block = Block::<Simple>::new().column("s", column);
+
+        let rblock = round_trip(&block);
+        let values: Vec<Option<String>> = (0..4).map(|i| rblock.get(i, "s").unwrap()).collect();
+        assert_eq!(values, data);
+
+        let settings = SerializationSettings {
+            client_revision: DBMS_MIN_REVISION_WITH_LOW_CARDINALITY_TYPE - 5,
+            ..Default::default()
+        };
+        let mut encoder = Encoder::new();
+        block.columns()[0].write(&mut encoder, settings);
+        let mut expected = Encoder::new();
+        new_column::<Simple>("s", Vec::column_from::<ArcColumnWrapper>(data))
+            .write(&mut expected, settings);
+        assert_eq!(encoder.get_buffer(), expected.get_buffer());
+    }
+
+    #[test]
+    fn test_deduplicates_values() {
+        let mut data =
+            LowCardinalityColumnData::with_capacity(SqlType::String, Tz::Zulu, 0).unwrap();
+        for value in &["b", "a", "b", "", "a"] {
+            data.push(Value::from(*value));
+        }
+
+        assert_eq!(data.len(), 5);
+        assert_eq!(data.dictionary.len(), 3);
+        assert_eq!(data.keys, vec![1, 2, 1, 0, 2]);
+    }
+
+    #[test]
+    fn test_save_slice() {
+        let data = vec!["a", "b", "c", "b"];
+        let column = low_cardinality("s", data, SqlType::String);
+
+        let mut encoder = Encoder::new();
+        column.data.save(&mut encoder, 2, 4, SerializationSettings::default());
+
+        let mut expected = Encoder::new();
+        expected.write(1_u64);
+        expected.write(0x0600_u64);
+        expected.write(3_u64);
+        expected.string("");
+        expected.string("c");
+        expected.string("b");
+        expected.write(2_u64);
+        expected.write_bytes(&[1, 2]);
+        assert_eq!(encoder.get_buffer(), expected.get_buffer());
+    }
+
+    #[test]
+    fn test_fall_back_before_revision() {
+        let settings = SerializationSettings {
+            client_revision: DBMS_MIN_REVISION_WITH_LOW_CARDINALITY_TYPE - 5,
+            ..Default::default()
+        };
+        let strings = vec!["a", "b", "a", ""];
+        let nullable = vec![Some("x"), None, Some("x")];
+
+        let mut encoder = Encoder::new();
+        low_cardinality("s", strings.clone(), SqlType::String).write(&mut encoder, settings);
+        let mut expected = Encoder::new();
+        new_column::<Simple>("s", Vec::column_from::<ArcColumnWrapper>(strings))
+            .write(&mut expected, settings);
+        assert_eq!(encoder.get_buffer(), expected.get_buffer());
+
+        let mut encoder = Encoder::new();
+        low_cardinality("n", nullable.clone(), SqlType::Nullable(&SqlType::String))
+            .write(&mut encoder, settings);
+        let mut expected = Encoder::new();
+        new_column::<Simple>("n", Vec::column_from::<ArcColumnWrapper>(nullable))
+            .write(&mut expected, settings);
+        assert_eq!(encoder.get_buffer(), expected.get_buffer());
+
+        let mut offsets = List::new();
+        offsets.push(1);
+        let array = ArrayColumnData {
+            inner: low_cardinality("s", vec!["a"], SqlType::String).data,
+            offsets
+        };
+        assert_eq!(array.serialized_type(settings), SqlType::Array(&SqlType::String));
+        assert_eq!(
+            array.serialized_type(SerializationSettings::default()),
+            SqlType::Array(&SqlType::LowCardinality(&SqlType::String))
+        );
+    }
+
+    #[test]
+    fn test_wide_keys() {
+        assert_eq!(index_type_for(256), INDEX_TYPE_UINT8);
+        assert_eq!(index_type_for(257), INDEX_TYPE_UINT16);
+        assert_eq!(index_type_for(1 << 16), INDEX_TYPE_UINT16);
+        assert_eq!(index_type_for((1 << 16) + 1), INDEX_TYPE_UINT32);
+
+        let data: Vec<u32> = (0..1000).collect();
+        let column = low_cardinality("n", data, SqlType::UInt32);
+        let block = Block::<Simple>::new().column("n", column);
+
+        let rblock = round_trip(&block);
+        assert_eq!(block, rblock);
+        assert_eq!(rblock.get::<u32, _>(999, "n").unwrap(), 999);
+    }
+
+    #[test]
+    fn test_empty_column() {
+        let column = low_cardinality("s", Vec::<String>::new(), SqlType::String);
+        let block = Block::<Simple>::new().column("s", column);
+
+        let rblock = round_trip(&block);
+        assert_eq!(rblock.row_count(), 0);
+        assert_eq!(rblock.columns()[0].sql_type(), SqlType::LowCardinality(&SqlType::String));
+    }
+}
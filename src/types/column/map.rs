@@ -0,0 +1,186 @@
+use std::sync::Arc;
+
+use chrono_tz::Tz;
+
+use crate::binary::Encoder;
+use crate::binary::ReadEx;
+use crate::errors::Result;
+use crate::types::column::column_data::ArcColumnData;
+use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
+use crate::types::column::ArcColumnWrapper;
+use crate::types::column::ColumnData;
+use crate::types::SqlType;
+use crate::types::Value;
+use crate::types::ValueRef;
+
+/// `Map(K, V)` is stored exactly like `Array(Tuple(K, V))`, so the column
+/// wraps one and only converts values on the way in and out.
+pub(crate) struct MapColumnData {
+    pub(crate) inner: ArcColumnData
+}
+
+/// The `Tuple(K, V)` type of a single map entry.
+pub(crate) fn entry_type(key_type: &'static SqlType, value_type: &'static SqlType) -> SqlType {
+    SqlType::Tuple(vec![(None, key_type), (None, value_type)])
+}
+
+impl MapColumnData {
+    pub(crate) fn load<R: ReadEx>(
+        reader: &mut R,
+        key_type: &str,
+        value_type: &str,
+        rows: usize,
+        tz: Tz,
+        settings: SerializationSettings
+    ) -> Result<Self> {
+        let type_name = format!("Array(Tuple({}, {}))", key_type, value_type);
+        let inner = <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(
+            reader,
+            &type_name,
+            rows,
+            tz,
+            settings
+        )?;
+        Ok(MapColumnData { inner })
+    }
+
+    fn key_value_types(&self) -> (&'static SqlType, &'static SqlType) {
+        match self.inner.sql_type() {
+            SqlType::Array(SqlType::Tuple(elements)) if elements.len() == 2 => {
+                (elements[0].1, elements[1].1)
+            }
+            sql_type => panic!("Invalid map entries type {}.", sql_type)
+        }
+    }
+}
+
+impl ColumnData for MapColumnData {
+    fn sql_type(&self) -> SqlType {
+        let (key_type, value_type) = self.key_value_types();
+        SqlType::Map(key_type, value_type)
+    }
+
+    fn serialized_type(&self, settings: SerializationSettings) -> SqlType {
+        match self.inner.serialized_type(settings) {
+            SqlType::Array(SqlType::Tuple(elements)) if elements.len() == 2 => {
+                SqlType::Map(elements[0].1, elements[1].1)
+            }
+            sql_type => panic!("Invalid map entries type {}.", sql_type)
+        }
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
+        self.inner.save(encoder, start, end, settings);
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn push(&mut self, value: Value) {
+        if let Value::Map(key_type, value_type, pairs) = value {
+            let entries = pairs
+                .iter()
+                .map(|(k, v)| Value::Tuple(Arc::new(vec![k.clone(), v.clone()])))
+                .collect();
+            let entry_type = entry_type(key_type, value_type).into();
+
+            let inner_column = Arc::get_mut(&mut self.inner).unwrap();
+            inner_column.push(Value::Array(entry_type, Arc::new(entries)));
+        } else {
+            panic!("value should be a map")
+        }
+    }
+
+    fn at(&self, index: usize) -> ValueRef<'_> {
+        let (key_type, value_type) = self.key_value_types();
+
+        let pairs = match self.inner.at(index) {
+            ValueRef::Array(_, entries) => entries
+                .iter()
+                .map(|entry| match entry {
+                    ValueRef::Tuple(kv) => (kv[0].clone(), kv[1].clone()),
+                    _ => unreachable!()
+                })
+                .collect(),
+            _ => unreachable!()
+        };
+        ValueRef::Map(key_type, value_type, Arc::new(pairs))
+    }
+
+    fn clone_instance(&self) -> BoxColumnData {
+        Box::new(Self {
+            inner: self.inner.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::row;
+    use crate::types::Simple;
+    use crate::Block;
+
+    #[test]
+    fn test_write_and_read() {
+        let mut block = Block::<Simple>::new();
+        block
+            .push(row! { labels: Value::map(Vec::<(&str, u64)>::new()) })
+            .unwrap();
+        block
+            .push(row! { labels: Value::map(vec![("a", 1_u64), ("a", 2), ("A", 3), ("a ", 4)]) })
+            .unwrap();
+        block.push(row! { labels: Value::map(vec![("", 0_u64)]) }).unwrap();
+        block
+            .push(row! { labels: Value::map(Vec::<(&str, u64)>::new()) })
+            .unwrap();
+
+        assert_eq!(
+            block.columns()[0].sql_type(),
+            SqlType::Map(&SqlType::String, &SqlType::UInt64)
+        );
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 4, SerializationSettings::default());
+
+        let mut expected = Encoder::new();
+        for offset in &[0_u64, 4, 5, 5] {
+            expected.write(*offset);
+        }
+        for key in &["a", "a", "A", "a ", ""] {
+            expected.string(key);
+        }
+        for value in &[1_u64, 2, 3, 4, 0] {
+            expected.write(*value);
+        }
+        assert_eq!(encoder.get_buffer_ref(), expected.get_buffer_ref());
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+
+        assert_eq!(rblock.columns()[0].sql_type().to_string(), "Map(String, UInt64)");
+        assert_eq!(block, rblock);
+
+        let labels: Vec<(String, u64)> = rblock.get(1, "labels").unwrap();
+        assert_eq!(labels, vec![
+            ("a".to_string(), 1),
+            ("a".to_string(), 2),
+            ("A".to_string(), 3),
+            ("a ".to_string(), 4),
+        ]);
+        assert!(rblock.get::<Vec<(String, u64)>, _>(0, "labels").unwrap().is_empty());
+        assert!(rblock.get::<Vec<(String, u64)>, _>(3, "labels").unwrap().is_empty());
+    }
+}
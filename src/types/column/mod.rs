@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::fmt;
 use std::marker;
 use std::net::Ipv4Addr;
@@ -9,51 +10,70 @@ use chrono_tz::Tz;
 
 use self::chunk::ChunkColumnData;
 pub(crate) use self::column_data::ColumnData;
+pub(crate) use self::column_data::SerializationSettings;
 pub use self::concat::ConcatColumnData;
 pub use self::numeric::VectorColumnData;
+pub use self::string::StringColumnData;
 pub(crate) use self::string_pool::StringPool;
 use crate::binary::Encoder;
 use crate::binary::ReadEx;
 use crate::errors::Error;
 use crate::errors::FromSqlError;
 use crate::errors::Result;
+use crate::types::block::check_type;
+use crate::types::block::extract_timezone;
 use crate::types::column::column_data::ArcColumnData;
+use crate::types::column::date::DateColumnData;
 use crate::types::column::decimal::DecimalAdapter;
 use crate::types::column::decimal::NullableDecimalAdapter;
 use crate::types::column::enums::Enum16Adapter;
 use crate::types::column::enums::Enum8Adapter;
 use crate::types::column::enums::NullableEnum16Adapter;
 use crate::types::column::enums::NullableEnum8Adapter;
-use crate::types::column::fixed_string::FixedStringAdapter;
+use crate::types::column::fixed_string::check_str_len;
+use crate::types::column::fixed_string::FixedStringColumnData;
 use crate::types::column::fixed_string::NullableFixedStringAdapter;
 use crate::types::column::ip::IpColumnData;
 use crate::types::column::ip::Ipv4;
 use crate::types::column::ip::Ipv6;
 use crate::types::column::iter::Iterable;
+use crate::types::column::low_cardinality::LowCardinalityColumnData;
+use crate::types::column::object::ObjectColumnData;
+use crate::types::column::simple_aggregate_function::SimpleAggregateFunctionColumnData;
 use crate::types::column::string::StringAdapter;
 use crate::types::decimal::NoBits;
+use crate::types::FromSql;
 use crate::types::SqlType;
 use crate::types::Value;
 use crate::types::ValueRef;
 
+pub(crate) mod aggregate_function;
 mod array;
+mod bool;
 pub(crate) mod chrono_datetime;
 mod chunk;
 mod column_data;
 mod concat;
-mod date;
+pub(crate) mod date;
 pub(crate) mod datetime64;
 mod decimal;
 mod enums;
 mod factory;
 pub(crate) mod fixed_string;
+pub(crate) mod geo;
 mod ip;
 pub(crate) mod iter;
 mod list;
+pub(crate) mod low_cardinality;
+mod map;
+mod nothing;
 mod nullable;
 mod numeric;
+mod object;
+mod simple_aggregate_function;
 mod string;
 mod string_pool;
+mod tuple;
 
 /// Represents Clickhouse Column
 pub struct Column<K: ColumnType> {
@@ -192,11 +212,57 @@ impl<K: ColumnType> Column<K> {
 }
 
 impl<K: ColumnType> Column<K> {
-    pub(crate) fn read<R: ReadEx>(reader: &mut R, size: usize, tz: Tz) -> Result<Column<K>> {
+    /// A `Nullable` column of `values`, the `None` rows are set in its null map.
+    /// Like [`Column::from`] it has no name until it is added with `Block::column`.
+    ///
+    /// ```rust
+    /// use clickhouse_srv::types::Block;
+    /// use clickhouse_srv::types::Column;
+    /// use clickhouse_srv::types::Simple;
+    ///
+    /// let ids: Column<Simple> = Column::nullable_from(vec![Some(1_i64), None, Some(3)]);
+    /// let block = Block::new().column("id", ids);
+    /// assert_eq!(block.columns()[0].sql_type().to_string(), "Nullable(Int64)");
+    /// ```
+    pub fn nullable_from<T>(values: Vec<Option<T>>) -> Self
+    where Vec<Option<T>>: ColumnFrom {
+        new_column("", Vec::column_from::<ArcColumnWrapper>(values))
+    }
+
+    /// An empty column of `sql_type` to fill with [`Column::push`], for types
+    /// such as `LowCardinality(String)` that `Column::from` cannot make.
+    pub fn with_type(sql_type: SqlType) -> Result<Self> {
+        let data = <dyn ColumnData>::from_type::<ArcColumnWrapper>(sql_type, Tz::Zulu, 0)?;
+        Ok(new_column("", data))
+    }
+}
+
+/// An unnamed column of `values`, e.g. `Column::from(vec![1_i64, 2])` for an
+/// `Int64` one.
+impl<K: ColumnType, T> From<Vec<T>> for Column<K>
+where Vec<T>: ColumnFrom
+{
+    fn from(values: Vec<T>) -> Self {
+        new_column("", Vec::column_from::<ArcColumnWrapper>(values))
+    }
+}
+
+impl<K: ColumnType> Column<K> {
+    pub(crate) fn read<R: ReadEx>(
+        reader: &mut R,
+        size: usize,
+        tz: Tz,
+        settings: SerializationSettings
+    ) -> Result<Column<K>> {
         let name = reader.read_string()?;
         let type_name = reader.read_string()?;
-        let data =
-            <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(reader, &type_name, size, tz)?;
+        let data = <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(
+            reader,
+            &type_name,
+            size,
+            tz,
+            settings
+        )?;
         let column = Self {
             name,
             data,
@@ -215,16 +281,28 @@ impl<K: ColumnType> Column<K> {
         self.data.sql_type()
     }
 
+    /// The type as it is named in the block header, e.g. `Nullable(String)`.
+    pub fn column_type(&self) -> Cow<'static, str> {
+        self.data.sql_type().to_string()
+    }
+
+    /// The column as its concrete type, e.g. `VectorColumnData<i64>` for an
+    /// `Int64` column or `StringColumnData` for a `String` one, `None` if it is
+    /// of another type.
+    pub fn downcast_ref<T: ColumnData + 'static>(&self) -> Option<&T> {
+        self.data.as_any().downcast_ref::<T>()
+    }
+
     #[inline(always)]
     pub(crate) fn at(&self, index: usize) -> ValueRef {
         self.data.at(index)
     }
 
-    pub(crate) fn write(&self, encoder: &mut Encoder) {
+    pub(crate) fn write(&self, encoder: &mut Encoder, settings: SerializationSettings) {
         encoder.string(&self.name);
-        encoder.string(self.data.sql_type().to_string().as_ref());
+        encoder.string(self.data.serialized_type(settings).to_string().as_ref());
         let len = self.data.len();
-        self.data.save(encoder, 0, len);
+        self.data.save(encoder, 0, len, settings);
     }
 
     #[inline(always)]
@@ -232,6 +310,28 @@ impl<K: ColumnType> Column<K> {
         self.data.len()
     }
 
+    /// A copy of the column with the rows at `indices`, in that order.
+    pub(crate) fn permute(&self, indices: &[usize]) -> Result<Column<K>> {
+        let values: Vec<Value> = indices.iter().map(|index| self.at(*index).into()).collect();
+        let timezone = values.first().map_or(Tz::Zulu, extract_timezone);
+
+        let mut data = <dyn ColumnData>::from_type::<ArcColumnWrapper>(
+            self.sql_type(),
+            timezone,
+            values.len()
+        )?;
+        let inner = Arc::get_mut(&mut data).unwrap();
+        for value in values {
+            inner.push(value);
+        }
+
+        Ok(Column {
+            name: self.name.clone(),
+            data,
+            _marker: marker::PhantomData
+        })
+    }
+
     pub(crate) fn slice(&self, range: ops::Range<usize>) -> Column<Complex> {
         let data = ChunkColumnData::new(self.data.clone(), range);
         Column {
@@ -249,15 +349,38 @@ impl<K: ColumnType> Column<K> {
         }
 
         match (dst_type.clone(), src_type.clone()) {
-            (SqlType::FixedString(str_len), SqlType::String) => {
+            (SqlType::SimpleAggregateFunction(function, inner_type), _)
+                if *inner_type == src_type =>
+            {
                 let name = self.name().to_owned();
-                let adapter = FixedStringAdapter {
-                    column: self,
-                    str_len
+                let data = SimpleAggregateFunctionColumnData {
+                    function,
+                    inner: self.data
                 };
                 Ok(Column {
                     name,
-                    data: Arc::new(adapter),
+                    data: Arc::new(data),
+                    _marker: marker::PhantomData
+                })
+            }
+            (SqlType::Json | SqlType::Object(_), SqlType::String) => {
+                let name = self.name().to_owned();
+                let data = ObjectColumnData {
+                    sql_type: dst_type,
+                    inner: self.data
+                };
+                Ok(Column {
+                    name,
+                    data: Arc::new(data),
+                    _marker: marker::PhantomData
+                })
+            }
+            (SqlType::FixedString(str_len), SqlType::String) => {
+                let name = self.name().to_owned();
+                let data = FixedStringColumnData::from_column(&self, str_len)?;
+                Ok(Column {
+                    name,
+                    data: Arc::new(data),
                     _marker: marker::PhantomData
                 })
             }
@@ -265,6 +388,12 @@ impl<K: ColumnType> Column<K> {
                 SqlType::Nullable(SqlType::FixedString(str_len)),
                 SqlType::Nullable(SqlType::String)
             ) => {
+                for index in 0..self.len() {
+                    if let Some(bs) = Option::<&[u8]>::from_sql(self.at(index))? {
+                        check_str_len(bs, *str_len)?;
+                    }
+                }
+
                 let name = self.name().to_owned();
                 let adapter = NullableFixedStringAdapter {
                     column: self,
@@ -276,6 +405,15 @@ impl<K: ColumnType> Column<K> {
                     _marker: marker::PhantomData
                 })
             }
+            (SqlType::Date, SqlType::Date32) => {
+                let name = self.name().to_owned();
+                let data = DateColumnData::<u16>::from_date32(&self)?;
+                Ok(Column {
+                    name,
+                    data: Arc::new(data),
+                    _marker: marker::PhantomData
+                })
+            }
             (SqlType::String, SqlType::Array(SqlType::UInt8)) => {
                 let name = self.name().to_owned();
                 let adapter = StringAdapter { column: self };
@@ -425,6 +563,22 @@ impl<K: ColumnType> Column<K> {
                     _marker: marker::PhantomData
                 })
             }
+            (SqlType::LowCardinality(inner_type), _) if *inner_type == src_type => {
+                let name = self.name().to_owned();
+
+                let n = self.len();
+                let mut data =
+                    LowCardinalityColumnData::with_capacity(inner_type.clone(), Tz::Zulu, n)?;
+                for i in 0..n {
+                    data.push(Value::from(self.at(i)));
+                }
+
+                Ok(Column {
+                    name,
+                    data: Arc::new(data),
+                    _marker: marker::PhantomData
+                })
+            }
             _ => {
                 if let Some(data) = self.data.cast_to(&self.data, &dst_type) {
                     let name = self.name().to_owned();
@@ -443,7 +597,16 @@ impl<K: ColumnType> Column<K> {
         }
     }
 
-    pub(crate) fn push(&mut self, value: Value) {
+    /// Appends `value` as a new row, to build a column when the number of rows
+    /// is not known upfront. A value the column cannot hold fails with
+    /// `FromSqlError::InvalidType` and leaves the column as it was.
+    pub fn push(&mut self, value: Value) -> Result<()> {
+        check_type(self, &value)?;
+        self.push_unchecked(value);
+        Ok(())
+    }
+
+    pub(crate) fn push_unchecked(&mut self, value: Value) {
         loop {
             match Arc::get_mut(&mut self.data) {
                 None => {
@@ -521,3 +684,13 @@ impl ColumnWrapper for BoxColumnWrapper {
         unimplemented!()
     }
 }
+
+/// Writes `block` and loads it back, as column tests check their encoding.
+#[cfg(test)]
+pub(crate) fn round_trip(block: &crate::types::Block) -> crate::types::Block {
+    let mut encoder = crate::binary::Encoder::new();
+    block.write(&mut encoder, false);
+
+    let mut reader = std::io::Cursor::new(encoder.get_buffer_ref());
+    crate::types::Block::load(&mut reader, chrono_tz::Tz::Zulu, false).unwrap()
+}
@@ -0,0 +1,62 @@
+use crate::binary::Encoder;
+use crate::binary::ReadEx;
+use crate::errors::Result;
+use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
+use crate::types::column::ColumnData;
+use crate::types::column::Either;
+use crate::types::SqlType;
+use crate::types::Value;
+use crate::types::ValueRef;
+
+/// Placeholder column for `Nothing`, which only ever appears as `Nullable(Nothing)`
+/// (e.g. the type of a bare `NULL` literal). Every row is serialized as a single byte.
+pub(crate) struct NothingColumnData {
+    len: usize
+}
+
+impl NothingColumnData {
+    pub(crate) fn with_capacity(_capacity: usize) -> Self {
+        NothingColumnData { len: 0 }
+    }
+
+    pub(crate) fn load<R: ReadEx>(reader: &mut R, size: usize) -> Result<Self> {
+        let mut data = vec![0_u8; size];
+        reader.read_bytes(data.as_mut())?;
+        Ok(NothingColumnData { len: size })
+    }
+}
+
+impl ColumnData for NothingColumnData {
+    fn sql_type(&self) -> SqlType {
+        SqlType::Nothing
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
+        for _ in start..end {
+            encoder.write(b'0');
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn push(&mut self, _value: Value) {
+        self.len += 1;
+    }
+
+    fn at(&self, _index: usize) -> ValueRef<'_> {
+        ValueRef::Nullable(Either::Left(&SqlType::Nothing))
+    }
+
+    fn clone_instance(&self) -> BoxColumnData {
+        Box::new(Self { len: self.len })
+    }
+}
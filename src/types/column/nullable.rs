@@ -7,6 +7,7 @@ use crate::binary::ReadEx;
 use crate::errors::Result;
 use crate::types::column::column_data::ArcColumnData;
 use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::column::ArcColumnWrapper;
 use crate::types::column::ColumnData;
 use crate::types::column::Either;
@@ -24,12 +25,18 @@ impl NullableColumnData {
         reader: &mut R,
         type_name: &str,
         size: usize,
-        tz: Tz
+        tz: Tz,
+        settings: SerializationSettings
     ) -> Result<Self> {
         let mut nulls = vec![0; size];
         reader.read_bytes(nulls.as_mut())?;
-        let inner =
-            <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(reader, type_name, size, tz)?;
+        let inner = <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(
+            reader,
+            type_name,
+            size,
+            tz,
+            settings
+        )?;
         Ok(NullableColumnData { inner, nulls })
     }
 }
@@ -40,10 +47,16 @@ impl ColumnData for NullableColumnData {
         SqlType::Nullable(inner_type.into())
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
         let nulls: &[u8] = self.nulls.as_ref();
         encoder.write_bytes(&nulls[start..end]);
-        self.inner.save(encoder, start, end);
+        self.inner.save(encoder, start, end, settings);
     }
 
     fn len(&self) -> usize {
@@ -111,3 +124,81 @@ impl ColumnData for NullableColumnData {
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::types::block::BlockInfo;
+    use crate::types::column::round_trip;
+    use crate::types::Column;
+    use crate::types::Simple;
+    use crate::Block;
+
+    #[test]
+    fn test_nullable_round_trip() {
+        let block = Block::<Simple>::new()
+            .column("name", vec![Some("a".to_string()), None, Some("".to_string()), None])
+            .column("id", vec![None, Some(2_u32), Some(3), None]);
+
+        let rblock = round_trip(&block);
+        assert_eq!(rblock.columns()[0].sql_type(), SqlType::Nullable(&SqlType::String));
+        assert_eq!(rblock.columns()[1].sql_type(), SqlType::Nullable(&SqlType::UInt32));
+        assert_eq!(block, rblock);
+
+        let names: Vec<Option<String>> =
+            (0..4).map(|i| rblock.get(i, "name").unwrap()).collect();
+        assert_eq!(names, vec![Some("a".to_string()), None, Some("".to_string()), None]);
+
+        let ids: Vec<Option<u32>> = (0..4).map(|i| rblock.get(i, "id").unwrap()).collect();
+        assert_eq!(ids, vec![None, Some(2), Some(3), None]);
+    }
+
+    #[test]
+    fn test_nullable_from() {
+        let column = Column::<Simple>::nullable_from(vec![Some(1_i64), None, Some(3)]);
+        assert_eq!(column.sql_type(), SqlType::Nullable(&SqlType::Int64));
+
+        let data = column.downcast_ref::<NullableColumnData>().unwrap();
+        assert_eq!(data.nulls, vec![0, 1, 0]);
+        assert_eq!(data.inner.at(0), ValueRef::Int64(1));
+        assert_eq!(data.inner.at(2), ValueRef::Int64(3));
+
+        let block = Block::<Simple>::new()
+            .column("a", column)
+            .column("b", Column::<Simple>::from(vec![4_i64, 5, 6]));
+        assert_eq!(block.columns()[1].sql_type(), SqlType::Int64);
+
+        let rows: Vec<(Option<i64>, i64)> = block
+            .rows()
+            .map(|row| (row.get("a").unwrap(), row.get("b").unwrap()))
+            .collect();
+        assert_eq!(rows, vec![(Some(1), 4), (None, 5), (Some(3), 6)]);
+    }
+
+    #[test]
+    fn test_load_nullable_nothing() {
+        let mut encoder = Encoder::new();
+        BlockInfo::default().write(&mut encoder);
+        encoder.uvarint(1);
+        encoder.uvarint(3);
+        encoder.string("x");
+        encoder.string("Nullable(Nothing)");
+        encoder.write_bytes(&[1, 1, 1]);
+        encoder.write_bytes(b"000");
+        let expected = encoder.get_buffer();
+
+        let mut reader = Cursor::new(expected.as_slice());
+        let block = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+        assert_eq!(block.row_count(), 3);
+        assert_eq!(block.columns()[0].sql_type(), SqlType::Nullable(&SqlType::Nothing));
+
+        let value: Option<String> = block.get(1, "x").unwrap();
+        assert_eq!(value, None);
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+        assert_eq!(encoder.get_buffer(), expected);
+    }
+}
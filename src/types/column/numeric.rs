@@ -10,6 +10,7 @@ use crate::binary::Encoder;
 use crate::binary::ReadEx;
 use crate::errors::Result;
 use crate::types::column::array::ArrayColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::column::nullable::NullableColumnData;
 use crate::types::column::ArcColumnWrapper;
 use crate::types::column::ColumnWrapper;
@@ -158,6 +159,11 @@ where T: StatBuffer
         }
     }
 
+    /// The values of the column.
+    pub fn as_slice(&self) -> &[T] {
+        self.data.as_slice()
+    }
+
     pub(crate) fn load<R: ReadEx>(reader: &mut R, size: usize) -> Result<VectorColumnData<T>> {
         let mut data = List::with_capacity(size);
         unsafe {
@@ -184,7 +190,13 @@ where T: StatBuffer
         T::sql_type()
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         save_data::<T>(self.data.as_ref(), encoder, start, end);
     }
 
@@ -203,11 +215,15 @@ where T: StatBuffer
             Value::UInt16(x) => ValueRef::UInt16(x),
             Value::UInt32(x) => ValueRef::UInt32(x),
             Value::UInt64(x) => ValueRef::UInt64(x),
+            Value::UInt128(x) => ValueRef::UInt128(x),
+            Value::UInt256(x) => ValueRef::UInt256(x),
 
             Value::Int8(x) => ValueRef::Int8(x),
             Value::Int16(x) => ValueRef::Int16(x),
             Value::Int32(x) => ValueRef::Int32(x),
             Value::Int64(x) => ValueRef::Int64(x),
+            Value::Int128(x) => ValueRef::Int128(x),
+            Value::Int256(x) => ValueRef::Int256(x),
 
             Value::Float32(x) => ValueRef::Float32(x),
             Value::Float64(x) => ValueRef::Float64(x),
@@ -235,3 +251,117 @@ pub(crate) fn save_data<T>(data: &[u8], encoder: &mut Encoder, start: usize, end
     let end_index = end * mem::size_of::<T>();
     encoder.write_bytes(&data[start_index..end_index]);
 }
+
+#[cfg(test)]
+mod test {
+    use crate::binary::Encoder;
+    use crate::errors::Error;
+    use crate::errors::FromSqlError;
+    use crate::row;
+    use crate::types::column::round_trip;
+    use crate::types::column::Either;
+    use crate::types::column::SerializationSettings;
+    use crate::types::Block;
+    use crate::types::Column;
+    use crate::types::Simple;
+    use crate::types::SqlType;
+    use crate::types::Value;
+    use crate::types::I256;
+    use crate::types::U256;
+
+    #[test]
+    fn test_push_values() {
+        let mut column: Column<Simple> = Column::from(Vec::<i64>::new());
+        for i in 0..10 {
+            column.push(Value::Int64(i * 10)).unwrap();
+        }
+        let err = column.push(Value::from("eleven")).unwrap_err();
+        assert!(matches!(err, Error::FromSql(FromSqlError::InvalidType { .. })));
+
+        let block = Block::new().column("n", column);
+        assert_eq!(block.row_count(), 10);
+        assert_eq!(block.get::<i64, _>(9, "n").unwrap(), 90);
+        let values: Vec<i64> = (0..10).map(|i| i * 10).collect();
+        assert_eq!(block, Block::new().column("n", values));
+
+        let sql_type = SqlType::Nullable(&SqlType::Int64);
+        let mut column: Column<Simple> = Column::with_type(sql_type).unwrap();
+        column.push(Value::Nullable(Either::Left(&SqlType::Int64))).unwrap();
+        column.push(Value::Int64(1)).unwrap();
+        let block = Block::new().column("n", column);
+        assert_eq!(block.get::<Option<i64>, _>(0, "n").unwrap(), None);
+        assert_eq!(block.get::<Option<i64>, _>(1, "n").unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_wide_integers() {
+        let mut block = Block::<Simple>::new();
+        block
+            .push(row! {
+                u128: u128::MAX,
+                i128: -42_i128,
+                u256: U256::MAX,
+                i256: I256::from(-42_i128)
+            })
+            .unwrap();
+        block
+            .push(row! {
+                u128: 1_u128,
+                i128: i128::MIN,
+                u256: U256::from(1_u128),
+                i256: I256::from(i128::MIN)
+            })
+            .unwrap();
+
+        let rblock = round_trip(&block);
+        let types: Vec<_> = rblock.columns().iter().map(|c| c.sql_type().to_string()).collect();
+        assert_eq!(types, vec!["UInt128", "Int128", "UInt256", "Int256"]);
+        assert_eq!(block, rblock);
+
+        assert_eq!(rblock.get::<u128, _>(0, "u128").unwrap(), u128::MAX);
+        assert_eq!(rblock.get::<i128, _>(0, "i128").unwrap(), -42);
+        assert_eq!(rblock.get::<i128, _>(1, "i128").unwrap(), i128::MIN);
+        assert_eq!(rblock.get::<U256, _>(0, "u256").unwrap(), U256::MAX);
+        assert_eq!(rblock.get::<I256, _>(0, "i256").unwrap().to_le_bytes()[31], 0xff);
+        assert_eq!(format!("{}", rblock.columns()[3].at(1)), i128::MIN.to_string());
+    }
+
+    #[test]
+    fn test_wide_integer_layout() {
+        let block = Block::<Simple>::new()
+            .column("u", vec![u128::MAX - 1])
+            .column("i", vec![I256::from(-2_i128)]);
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 1, SerializationSettings::default());
+        block.columns()[1].data.save(&mut encoder, 0, 1, SerializationSettings::default());
+
+        let mut expected = vec![0xfe_u8];
+        expected.extend_from_slice(&[0xff; 15]);
+        expected.push(0xfe);
+        expected.extend_from_slice(&[0xff; 31]);
+        assert_eq!(encoder.get_buffer_ref(), expected.as_slice());
+    }
+
+    #[test]
+    fn test_special_floats() {
+        let values = vec![f64::NAN, f64::INFINITY, f64::NEG_INFINITY, -0.0_f64];
+        let narrow: Vec<f32> = values.iter().map(|v| *v as f32).collect();
+        let block = Block::<Simple>::new()
+            .column("f64", values.clone())
+            .column("f32", narrow.clone());
+
+        let mut encoder = Encoder::new();
+        block.columns()[0].data.save(&mut encoder, 0, 1, SerializationSettings::default());
+        assert_eq!(encoder.get_buffer_ref(), &f64::NAN.to_bits().to_le_bytes());
+
+        let rblock = round_trip(&block);
+        for (i, value) in values.iter().enumerate() {
+            let actual: f64 = rblock.get(i, "f64").unwrap();
+            assert_eq!(actual.to_bits(), value.to_bits());
+
+            let actual: f32 = rblock.get(i, "f32").unwrap();
+            assert_eq!(actual.to_bits(), narrow[i].to_bits());
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use chrono_tz::Tz;
+
+use crate::binary::Encoder;
+use crate::binary::ReadEx;
+use crate::errors::Result;
+use crate::types::column::column_data::ArcColumnData;
+use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
+use crate::types::column::string::StringColumnData;
+use crate::types::column::ArcColumnWrapper;
+use crate::types::column::ColumnData;
+use crate::types::SqlType;
+use crate::types::Value;
+use crate::types::ValueRef;
+
+/// `JSON` or `Object('json')`, kept in the form serialized as a `String` per
+/// row. The objects are never parsed, a block of them is forwarded as is.
+/// Build one from strings with `Block::column_as`.
+pub(crate) struct ObjectColumnData {
+    pub(crate) sql_type: SqlType,
+    pub(crate) inner: ArcColumnData
+}
+
+impl ObjectColumnData {
+    pub(crate) fn load<R: ReadEx>(
+        reader: &mut R,
+        sql_type: SqlType,
+        size: usize,
+        tz: Tz,
+        settings: SerializationSettings
+    ) -> Result<Self> {
+        let inner = <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(
+            reader,
+            "String",
+            size,
+            tz,
+            settings
+        )?;
+        Ok(Self { sql_type, inner })
+    }
+
+    pub(crate) fn with_capacity(sql_type: SqlType, capacity: usize) -> Self {
+        Self {
+            sql_type,
+            inner: Arc::new(StringColumnData::with_capacity(capacity))
+        }
+    }
+}
+
+impl ColumnData for ObjectColumnData {
+    fn sql_type(&self) -> SqlType {
+        self.sql_type.clone()
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
+        self.inner.save(encoder, start, end, settings);
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn push(&mut self, value: Value) {
+        let inner_column = Arc::get_mut(&mut self.inner).unwrap();
+        inner_column.push(value);
+    }
+
+    fn at(&self, index: usize) -> ValueRef<'_> {
+        self.inner.at(index)
+    }
+
+    fn clone_instance(&self) -> BoxColumnData {
+        Box::new(Self {
+            sql_type: self.sql_type.clone(),
+            inner: self.inner.clone()
+        })
+    }
+
+    unsafe fn get_internal(&self, pointers: &[*mut *const u8], level: u8) -> Result<()> {
+        self.inner.get_internal(pointers, level)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::types::Block;
+
+    #[test]
+    fn test_forward_objects() {
+        let objects = vec![r#"{"id":1,"tags":["a"]}"#, r#"{"id":2,"nested":{"x":null}}"#];
+
+        for sql_type in &[SqlType::Json, SqlType::Object("json".to_string())] {
+            let block = Block::new()
+                .column_as("j", objects.clone(), sql_type.clone())
+                .unwrap();
+
+            let mut encoder = Encoder::new();
+            block.write(&mut encoder, false);
+
+            let mut expected = Encoder::new();
+            for object in &objects {
+                expected.string(object);
+            }
+            assert!(encoder.get_buffer_ref().ends_with(expected.get_buffer_ref()));
+
+            let mut reader = Cursor::new(encoder.get_buffer_ref());
+            let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+            assert_eq!(rblock.columns()[0].sql_type(), *sql_type);
+            assert_eq!(block, rblock);
+
+            let mut forwarded = Encoder::new();
+            rblock.write(&mut forwarded, false);
+            assert_eq!(forwarded.get_buffer_ref(), encoder.get_buffer_ref());
+            assert_eq!(rblock.get::<&str, _>(1, "j").unwrap(), objects[1]);
+        }
+
+        assert_eq!(SqlType::Json.to_string(), "JSON");
+        assert_eq!(SqlType::Object("json".to_string()).to_string(), "Object('json')");
+    }
+}
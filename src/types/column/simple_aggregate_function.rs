@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use chrono_tz::Tz;
+
+use crate::binary::Encoder;
+use crate::binary::ReadEx;
+use crate::errors::Result;
+use crate::types::column::column_data::ArcColumnData;
+use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
+use crate::types::column::ArcColumnWrapper;
+use crate::types::column::ColumnData;
+use crate::types::SqlType;
+use crate::types::Value;
+use crate::types::ValueRef;
+
+/// `SimpleAggregateFunction(f, T)` is stored exactly like `T`, only the type
+/// name in the header differs. Build one with `Block::column_as`.
+pub(crate) struct SimpleAggregateFunctionColumnData {
+    pub(crate) function: String,
+    pub(crate) inner: ArcColumnData
+}
+
+impl SimpleAggregateFunctionColumnData {
+    pub(crate) fn load<R: ReadEx>(
+        reader: &mut R,
+        function: &str,
+        inner_type: &str,
+        size: usize,
+        tz: Tz,
+        settings: SerializationSettings
+    ) -> Result<Self> {
+        let inner = <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(
+            reader,
+            inner_type,
+            size,
+            tz,
+            settings
+        )?;
+        Ok(Self {
+            function: function.to_string(),
+            inner
+        })
+    }
+}
+
+impl ColumnData for SimpleAggregateFunctionColumnData {
+    fn sql_type(&self) -> SqlType {
+        SqlType::SimpleAggregateFunction(self.function.clone(), self.inner.sql_type().into())
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
+        self.inner.save(encoder, start, end, settings);
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn push(&mut self, value: Value) {
+        let inner_column = Arc::get_mut(&mut self.inner).unwrap();
+        inner_column.push(value);
+    }
+
+    fn at(&self, index: usize) -> ValueRef<'_> {
+        self.inner.at(index)
+    }
+
+    fn clone_instance(&self) -> BoxColumnData {
+        Box::new(Self {
+            function: self.function.clone(),
+            inner: self.inner.clone()
+        })
+    }
+
+    unsafe fn get_internal(&self, pointers: &[*mut *const u8], level: u8) -> Result<()> {
+        self.inner.get_internal(pointers, level)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::types::Block;
+
+    #[test]
+    fn test_write_and_read() {
+        let sql_type = SqlType::SimpleAggregateFunction("max".to_string(), &SqlType::Float64);
+        let block = Block::new()
+            .column_as("m", vec![1.5_f64, -0.25, f64::MAX], sql_type.clone())
+            .unwrap();
+        assert_eq!(block.columns()[0].sql_type(), sql_type);
+
+        let mut encoder = Encoder::new();
+        block.write(&mut encoder, false);
+
+        let mut expected = Encoder::new();
+        for value in &[1.5_f64, -0.25, f64::MAX] {
+            expected.write(*value);
+        }
+        assert!(encoder.get_buffer_ref().ends_with(expected.get_buffer_ref()));
+
+        let mut reader = Cursor::new(encoder.get_buffer_ref());
+        let rblock = Block::load(&mut reader, Tz::Zulu, false).unwrap();
+
+        assert_eq!(
+            rblock.columns()[0].sql_type().to_string(),
+            "SimpleAggregateFunction(max, Float64)"
+        );
+        assert_eq!(block, rblock);
+        assert_eq!(rblock.get::<f64, _>(1, "m").unwrap(), -0.25);
+        assert_eq!(rblock.get::<f64, _>(2, "m").unwrap(), f64::MAX);
+    }
+}
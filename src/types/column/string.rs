@@ -9,6 +9,7 @@ use crate::binary::Encoder;
 use crate::binary::ReadEx;
 use crate::errors::Result;
 use crate::types::column::array::ArrayColumnData;
+use crate::types::column::column_data::SerializationSettings;
 use crate::types::column::list::List;
 use crate::types::column::nullable::NullableColumnData;
 use crate::types::column::ArcColumnWrapper;
@@ -22,7 +23,8 @@ use crate::types::SqlType;
 use crate::types::Value;
 use crate::types::ValueRef;
 
-pub(crate) struct StringColumnData {
+/// A `String` column, the values are kept as bytes.
+pub struct StringColumnData {
     pool: StringPool
 }
 
@@ -37,15 +39,35 @@ impl StringColumnData {
         }
     }
 
-    pub(crate) fn load<T: ReadEx>(reader: &mut T, size: usize) -> Result<Self> {
+    /// Values longer than `max_string_size` bytes fail before anything is
+    /// allocated for them.
+    pub(crate) fn load<T: ReadEx>(
+        reader: &mut T,
+        size: usize,
+        max_string_size: usize
+    ) -> Result<Self> {
         let mut data = Self::with_capacity(size);
 
         for _ in 0..size {
-            reader.read_str_into_buffer(&mut data.pool)?;
+            reader.read_str_into_buffer(&mut data.pool, max_string_size)?;
         }
 
         Ok(data)
     }
+
+    /// The value in row `index`, if there is such a row.
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        if index < self.pool.len() {
+            Some(self.pool.get(index))
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over the values of the column.
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        self.pool.strings()
+    }
 }
 
 impl ColumnFrom for Vec<String> {
@@ -169,7 +191,13 @@ impl ColumnData for StringColumnData {
         SqlType::String
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         let strings = self.pool.strings();
         for v in strings.skip(start).take(end - start) {
             encoder.byte_string(v);
@@ -210,7 +238,13 @@ impl<K: ColumnType> ColumnData for StringAdapter<K> {
         SqlType::String
     }
 
-    fn save(&self, encoder: &mut Encoder, start: usize, end: usize) {
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        _settings: SerializationSettings
+    ) {
         for index in start..end {
             let buf: Vec<u8> = Vec::from_sql(self.column.at(index)).unwrap();
             encoder.byte_string(buf);
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use chrono_tz::Tz;
+
+use crate::binary::Encoder;
+use crate::binary::ReadEx;
+use crate::errors::Result;
+use crate::types::column::column_data::ArcColumnData;
+use crate::types::column::column_data::BoxColumnData;
+use crate::types::column::column_data::SerializationSettings;
+use crate::types::column::ArcColumnWrapper;
+use crate::types::column::ColumnData;
+use crate::types::SqlType;
+use crate::types::Value;
+use crate::types::ValueRef;
+
+/// Tuples are stored column-wise: one inner column per element, written one
+/// after another. `names` is empty for positional tuples.
+pub(crate) struct TupleColumnData {
+    pub(crate) inner: Vec<ArcColumnData>,
+    pub(crate) names: Vec<String>
+}
+
+impl TupleColumnData {
+    pub(crate) fn load<R: ReadEx>(
+        reader: &mut R,
+        elements: Vec<(Option<&str>, &str)>,
+        size: usize,
+        tz: Tz,
+        settings: SerializationSettings
+    ) -> Result<Self> {
+        let mut inner = Vec::with_capacity(elements.len());
+        let mut names = Vec::with_capacity(elements.len());
+
+        for (name, type_name) in elements {
+            if let Some(name) = name {
+                names.push(name.to_string());
+            }
+            let column = <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(
+                reader,
+                type_name,
+                size,
+                tz,
+                settings
+            )?;
+            inner.push(column);
+        }
+
+        if !names.is_empty() && names.len() != inner.len() {
+            return Err("Tuple elements must be either all named or all positional.".into());
+        }
+
+        Ok(TupleColumnData { inner, names })
+    }
+}
+
+impl ColumnData for TupleColumnData {
+    fn sql_type(&self) -> SqlType {
+        let elements = self
+            .inner
+            .iter()
+            .enumerate()
+            .map(|(i, column)| (self.names.get(i).cloned(), column.sql_type().into()))
+            .collect();
+        SqlType::Tuple(elements)
+    }
+
+    fn serialized_type(&self, settings: SerializationSettings) -> SqlType {
+        let elements = self
+            .inner
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let sql_type = column.serialized_type(settings);
+                (self.names.get(i).cloned(), sql_type.into())
+            })
+            .collect();
+        SqlType::Tuple(elements)
+    }
+
+    fn save(
+        &self,
+        encoder: &mut Encoder,
+        start: usize,
+        end: usize,
+        settings: SerializationSettings
+    ) {
+        for column in &self.inner {
+            column.save(encoder, start, end, settings);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.inner.first().map_or(0, |column| column.len())
+    }
+
+    fn push(&mut self, value: Value) {
+        if let Value::Tuple(vs) = value {
+            assert_eq!(vs.len(), self.inner.len(), "tuple has a wrong number of elements");
+            for (column, v) in self.inner.iter_mut().zip(vs.iter()) {
+                let inner_column = Arc::get_mut(column).unwrap();
+                inner_column.push(v.clone());
+            }
+        } else {
+            panic!("value should be a tuple")
+        }
+    }
+
+    fn at(&self, index: usize) -> ValueRef<'_> {
+        let vs = self.inner.iter().map(|column| column.at(index)).collect();
+        ValueRef::Tuple(Arc::new(vs))
+    }
+
+    fn clone_instance(&self) -> BoxColumnData {
+        Box::new(Self {
+            inner: self.inner.clone(),
+            names: self.names.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::row;
+    use crate::types::column::round_trip;
+    use crate::types::Simple;
+    use crate::Block;
+
+    fn tuple(name: &str, ids: Vec<u32>) -> Value {
+        let ids = ids.into_iter().map(Value::from).collect();
+        Value::Tuple(Arc::new(vec![
+            Value::from(name),
+            Value::Array(&SqlType::UInt32, Arc::new(ids)),
+        ]))
+    }
+
+    #[test]
+    fn test_tuple_with_array() {
+        let mut block = Block::<Simple>::new();
+        block.push(row! { t: tuple("a", vec![1, 2]) }).unwrap();
+        block.push(row! { t: tuple("", vec![]) }).unwrap();
+        block.push(row! { t: tuple("bc", vec![3]) }).unwrap();
+
+        let rblock = round_trip(&block);
+        assert_eq!(
+            rblock.columns()[0].sql_type().to_string(),
+            "Tuple(String, Array(UInt32))"
+        );
+        assert_eq!(block, rblock);
+
+        let (name, ids): (String, Vec<u32>) = rblock.get(2, "t").unwrap();
+        assert_eq!(name, "bc");
+        assert_eq!(ids, vec![3]);
+    }
+
+    #[test]
+    fn test_array_of_named_tuples() {
+        let mut encoder = Encoder::new();
+        encoder.write(1_u64);
+        encoder.write(3_u64);
+        encoder.string("x");
+        encoder.string("y");
+        encoder.string("z");
+        encoder.write(7_u32);
+        encoder.write(8_u32);
+        encoder.write(9_u32);
+        let data = encoder.get_buffer();
+
+        let mut reader = Cursor::new(data.as_slice());
+        let column = <dyn ColumnData>::load_data::<ArcColumnWrapper, _>(
+            &mut reader,
+            "Array(Tuple(name String, age UInt32))",
+            2,
+            Tz::Zulu,
+            Default::default()
+        )
+        .unwrap();
+
+        assert_eq!(column.sql_type().to_string(), "Array(Tuple(name String, age UInt32))");
+        assert_eq!(format!("{}", column.at(0)), "[(x, 7)]");
+        assert_eq!(format!("{}", column.at(1)), "[(y, 8), (z, 9)]");
+
+        let mut encoder = Encoder::new();
+        column.save(&mut encoder, 0, 2, SerializationSettings::default());
+        assert_eq!(encoder.get_buffer(), data);
+    }
+}
@@ -1,21 +1,40 @@
+use std::convert::TryFrom;
+
 use chrono::prelude::*;
 use chrono::Date;
 use chrono_tz::Tz;
 
+use crate::errors::Error;
+use crate::errors::FromSqlError;
+use crate::errors::Result;
 use crate::types::DateTimeType;
 use crate::types::SqlType;
 use crate::types::Value;
 use crate::types::ValueRef;
 
+const UNIX_EPOCH_DAY: i64 = 719_163;
+
+/// Number of days between 1970-01-01 and `date`, negative for earlier dates.
+pub(crate) fn days_since_epoch(date: NaiveDate) -> i64 {
+    i64::from(date.num_days_from_ce()) - UNIX_EPOCH_DAY
+}
+
+/// The date `days` after 1970-01-01, `None` past the range of `NaiveDate`.
+pub(crate) fn date_from_days(days: i64) -> Option<NaiveDate> {
+    let days = i32::try_from(days + UNIX_EPOCH_DAY).ok()?;
+    NaiveDate::from_num_days_from_ce_opt(days)
+}
+
 pub trait DateConverter {
     fn to_date(&self, tz: Tz) -> ValueRef<'static>;
     fn get_stamp(source: Value) -> Self;
     fn date_type() -> SqlType;
 
-    fn get_days(date: Date<Tz>) -> u16 {
-        const UNIX_EPOCH_DAY: i64 = 719_163;
-        let gregorian_day = i64::from(date.num_days_from_ce());
-        (gregorian_day - UNIX_EPOCH_DAY) as u16
+    /// Days of `date` for a `Date` column, dates outside 1970-01-01..2149-06-06
+    /// fail with `FromSqlError::OutOfRange`.
+    fn get_days(date: Date<Tz>) -> Result<u16> {
+        u16::try_from(days_since_epoch(date.naive_local()))
+            .map_err(|_| Error::FromSql(FromSqlError::OutOfRange))
     }
 }
 
@@ -25,7 +44,11 @@ impl DateConverter for u16 {
     }
 
     fn get_stamp(source: Value) -> Self {
-        Self::get_days(Date::<Tz>::from(source))
+        if let Value::Date(days, _) = source {
+            return days;
+        }
+        let from = SqlType::from(source);
+        panic!("Can't convert Value::{} into {}", from, "Date")
     }
 
     fn date_type() -> SqlType {
@@ -33,6 +56,23 @@ impl DateConverter for u16 {
     }
 }
 
+impl DateConverter for i32 {
+    fn to_date(&self, tz: Tz) -> ValueRef<'static> {
+        ValueRef::Date32(*self, tz)
+    }
+
+    fn get_stamp(source: Value) -> Self {
+        match source {
+            Value::Date32(days, _) => days,
+            _ => days_since_epoch(NaiveDate::from(source)) as Self
+        }
+    }
+
+    fn date_type() -> SqlType {
+        SqlType::Date32
+    }
+}
+
 impl DateConverter for u32 {
     fn to_date(&self, tz: Tz) -> ValueRef<'static> {
         ValueRef::DateTime(*self, tz)
@@ -1,7 +1,7 @@
 use std::cmp::Ordering;
 use std::fmt;
 
-static FACTORS10: &[i64] = &[
+static FACTORS10: &[i128] = &[
     1,
     10,
     100,
@@ -20,28 +20,49 @@ static FACTORS10: &[i64] = &[
     1_000_000_000_000_000,
     10_000_000_000_000_000,
     100_000_000_000_000_000,
-    1_000_000_000_000_000_000
+    1_000_000_000_000_000_000,
+    10_000_000_000_000_000_000,
+    100_000_000_000_000_000_000,
+    1_000_000_000_000_000_000_000,
+    10_000_000_000_000_000_000_000,
+    100_000_000_000_000_000_000_000,
+    1_000_000_000_000_000_000_000_000,
+    10_000_000_000_000_000_000_000_000,
+    100_000_000_000_000_000_000_000_000,
+    1_000_000_000_000_000_000_000_000_000,
+    10_000_000_000_000_000_000_000_000_000,
+    100_000_000_000_000_000_000_000_000_000,
+    1_000_000_000_000_000_000_000_000_000_000,
+    10_000_000_000_000_000_000_000_000_000_000,
+    100_000_000_000_000_000_000_000_000_000_000,
+    1_000_000_000_000_000_000_000_000_000_000_000,
+    10_000_000_000_000_000_000_000_000_000_000_000,
+    100_000_000_000_000_000_000_000_000_000_000_000,
+    1_000_000_000_000_000_000_000_000_000_000_000_000,
+    10_000_000_000_000_000_000_000_000_000_000_000_000,
+    100_000_000_000_000_000_000_000_000_000_000_000_000
 ];
 
 pub trait Base {
-    fn scale(self, scale: i64) -> i64;
+    fn scale(self, scale: i128) -> i128;
 }
 
 pub trait InternalResult {
-    fn get(underlying: i64) -> Self;
+    fn get(underlying: i128) -> Self;
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub(crate) enum NoBits {
     N32,
-    N64
+    N64,
+    N128
 }
 
 /// Provides arbitrary-precision floating point decimal.
 #[derive(Clone)]
 pub struct Decimal {
-    pub(crate) underlying: i64,
-    pub(crate) nobits: NoBits, // its domain is {32, 64}
+    pub(crate) underlying: i128,
+    pub(crate) nobits: NoBits, // its domain is {32, 64, 128}
     pub(crate) precision: u8,
     pub(crate) scale: u8
 }
@@ -61,8 +82,8 @@ macro_rules! base_for {
     ( $( $t:ty: $cast:expr ),* ) => {
         $(
             impl Base for $t {
-                fn scale(self, scale: i64) -> i64 {
-                    $cast(self * (scale as $t)) as i64
+                fn scale(self, scale: i128) -> i128 {
+                    $cast(self * (scale as $t)) as i128
                 }
             }
         )*
@@ -72,26 +93,34 @@ macro_rules! base_for {
 base_for! {
     f32: std::convert::identity,
     f64: std::convert::identity,
-    i8: i64::from,
-    i16: i64::from,
-    i32: i64::from,
-    i64: std::convert::identity,
-    u8: i64::from,
-    u16: i64::from,
-    u32: i64::from,
-    u64 : std::convert::identity
+    i8: i128::from,
+    i16: i128::from,
+    i32: i128::from,
+    i64: i128::from,
+    i128: std::convert::identity,
+    u8: i128::from,
+    u16: i128::from,
+    u32: i128::from,
+    u64: i128::from
 }
 
 impl InternalResult for i32 {
     #[inline(always)]
-    fn get(underlying: i64) -> Self {
+    fn get(underlying: i128) -> Self {
         underlying as Self
     }
 }
 
 impl InternalResult for i64 {
     #[inline(always)]
-    fn get(underlying: i64) -> Self {
+    fn get(underlying: i128) -> Self {
+        underlying as Self
+    }
+}
+
+impl InternalResult for i128 {
+    #[inline(always)]
+    fn get(underlying: i128) -> Self {
         underlying
     }
 }
@@ -102,6 +131,8 @@ impl NoBits {
             Some(NoBits::N32)
         } else if precision <= 18 {
             Some(NoBits::N64)
+        } else if precision <= 38 {
+            Some(NoBits::N128)
         } else {
             None
         }
@@ -127,7 +158,7 @@ impl PartialEq for Decimal {
 }
 
 fn decimal2str(decimal: &Decimal) -> String {
-    let mut r = format!("{}", decimal.underlying);
+    let mut r = format!("{}", decimal.underlying.unsigned_abs());
     while r.len() < decimal.scale() {
         r.insert(0, '0');
     }
@@ -136,6 +167,9 @@ fn decimal2str(decimal: &Decimal) -> String {
     if r.starts_with('.') {
         r.insert(0, '0');
     }
+    if decimal.underlying < 0 {
+        r.insert(0, '-');
+    }
     r
 }
 
@@ -163,6 +197,29 @@ impl From<Decimal> for f64 {
     }
 }
 
+impl From<(i128, u8)> for Decimal {
+    /// Makes a `Decimal128` from its unscaled value and scale.
+    fn from((underlying, scale): (i128, u8)) -> Self {
+        let precision = 38;
+        if scale > precision {
+            panic!("scale can't be greater than 38");
+        }
+
+        Decimal {
+            underlying,
+            precision,
+            scale,
+            nobits: NoBits::N128
+        }
+    }
+}
+
+impl From<Decimal> for (i128, u8) {
+    fn from(value: Decimal) -> Self {
+        (value.underlying, value.scale)
+    }
+}
+
 impl Decimal {
     /// Method of creating a Decimal.
     pub fn new(underlying: i64, scale: u8) -> Decimal {
@@ -172,7 +229,7 @@ impl Decimal {
         }
 
         Decimal {
-            underlying,
+            underlying: i128::from(underlying),
             precision,
             scale,
             nobits: NoBits::N64
@@ -186,7 +243,7 @@ impl Decimal {
         }
 
         let underlying = source.scale(FACTORS10[scale as usize]);
-        if underlying > FACTORS10[precision as usize] as i64 {
+        if underlying > FACTORS10[precision as usize] {
             panic!("{} > {}", underlying, FACTORS10[precision as usize]);
         }
 
@@ -198,7 +255,7 @@ impl Decimal {
         }
     }
 
-    /// Get the internal representation of decimal as [`i32`] or [`i64`].
+    /// Get the internal representation of decimal as [`i32`], [`i64`] or [`i128`].
     ///
     /// example:
     /// ```rust
@@ -293,6 +350,19 @@ mod test {
         assert_eq!(internal, 20000_i64);
     }
 
+    #[test]
+    fn test_internal128() {
+        let internal: i128 = Decimal::from((-12_345_678_901_234_567_890_i128, 10)).internal();
+        assert_eq!(internal, -12_345_678_901_234_567_890_i128);
+    }
+
+    #[test]
+    fn test_parts() {
+        let decimal = Decimal::from((-1_i128 << 100, 20));
+        assert_eq!(decimal.precision, 38);
+        assert_eq!(<(i128, u8)>::from(decimal), (-1_i128 << 100, 20));
+    }
+
     #[test]
     fn test_scale() {
         assert_eq!(Decimal::of(2, 4).scale(), 4);
@@ -333,5 +403,8 @@ mod test {
         let d = Decimal::of(0.00001, 5);
         let actual = decimal2str(&d);
         assert_eq!(actual, "0.00001".to_string());
+
+        let d = Decimal::new(-5, 2);
+        assert_eq!(decimal2str(&d), "-0.05".to_string());
     }
 }
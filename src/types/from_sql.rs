@@ -9,12 +9,15 @@ use crate::errors::FromSqlError;
 use crate::errors::Result;
 use crate::types::column::datetime64::to_datetime;
 use crate::types::column::Either;
+use crate::types::date_converter::date_from_days;
 use crate::types::value::decode_ipv4;
 use crate::types::value::decode_ipv6;
 use crate::types::Decimal;
 use crate::types::Enum16;
 use crate::types::Enum8;
+use crate::types::I256;
 use crate::types::SqlType;
+use crate::types::U256;
 use crate::types::ValueRef;
 
 pub type FromSqlResult<T> = Result<T>;
@@ -102,7 +105,22 @@ impl<'a> FromSql<'a> for &'a [u8] {
 
 impl<'a> FromSql<'a> for String {
     fn from_sql(value: ValueRef<'a>) -> FromSqlResult<Self> {
-        value.as_str().map(str::to_string)
+        match value {
+            ValueRef::Enum8(ref values, v) => enum_name(values, v.internal(), &value),
+            ValueRef::Enum16(ref values, v) => enum_name(values, v.internal(), &value),
+            _ => value.as_str().map(str::to_string)
+        }
+    }
+}
+
+// Resolves an enum value to its name, an `Enum8`/`Enum16` target reads the number.
+fn enum_name<T: PartialEq>(values: &[(String, T)], v: T, value: &ValueRef) -> Result<String> {
+    match values.iter().find(|(_, item)| *item == v) {
+        Some((name, _)) => Ok(name.clone()),
+        None => Err(Error::FromSql(FromSqlError::InvalidType {
+            src: SqlType::from(value.clone()).to_string(),
+            dst: "String".into()
+        }))
     }
 }
 
@@ -239,10 +257,12 @@ from_sql_vec_impl! {
     i16: Int16,
     i32: Int32,
     i64: Int64,
+    i128: Int128,
 
     u16: UInt16,
     u32: UInt32,
     u64: UInt64,
+    u128: UInt128,
 
     f32: Float32,
     f64: Float64
@@ -289,6 +309,25 @@ impl<'a> FromSql<'a> for Date<Tz> {
     }
 }
 
+impl<'a> FromSql<'a> for NaiveDate {
+    fn from_sql(value: ValueRef<'a>) -> FromSqlResult<Self> {
+        match value {
+            // Any `UInt16` day is a valid date.
+            ValueRef::Date(v, _) => Ok(date_from_days(i64::from(v)).unwrap()),
+            ValueRef::Date32(v, _) => {
+                date_from_days(i64::from(v)).ok_or(Error::FromSql(FromSqlError::OutOfRange))
+            }
+            _ => {
+                let from = SqlType::from(value).to_string();
+                Err(Error::FromSql(FromSqlError::InvalidType {
+                    src: from,
+                    dst: "NaiveDate".into()
+                }))
+            }
+        }
+    }
+}
+
 impl<'a> FromSql<'a> for DateTime<Tz> {
     fn from_sql(value: ValueRef<'a>) -> FromSqlResult<Self> {
         match value {
@@ -316,18 +355,81 @@ from_sql_impl! {
     u16: UInt16,
     u32: UInt32,
     u64: UInt64,
+    u128: UInt128,
+    U256: UInt256,
 
     i8: Int8,
     i16: Int16,
     i32: Int32,
     i64: Int64,
+    i128: Int128,
+    I256: Int256,
 
     f32: Float32,
-    f64: Float64
+    f64: Float64,
+
+    bool: Bool
+}
+
+macro_rules! from_sql_tuple_impl {
+    ( $( $n:literal => ($($t:ident: $i:tt),+) ),* ) => {
+        $(
+            impl<'a, $($t),+> FromSql<'a> for ($($t,)+)
+            where $($t: FromSql<'a>),+ {
+                fn from_sql(value: ValueRef<'a>) -> FromSqlResult<Self> {
+                    match value {
+                        ValueRef::Tuple(vs) if vs.len() == $n => {
+                            Ok(($($t::from_sql(vs[$i].clone())?,)+))
+                        }
+                        _ => {
+                            let from = SqlType::from(value.clone()).to_string();
+                            Err(Error::FromSql(FromSqlError::InvalidType {
+                                src: from,
+                                dst: stringify!(($($t,)+)).into()
+                            }))
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_sql_tuple_impl! {
+    2 => (A: 0, B: 1),
+    3 => (A: 0, B: 1, C: 2)
+}
+
+/// Reads `Map(K, V)` values, and `Array(Tuple(K, V))` ones, as a list of pairs.
+impl<'a, K, V> FromSql<'a> for Vec<(K, V)>
+where
+    K: FromSql<'a>,
+    V: FromSql<'a>
+{
+    fn from_sql(value: ValueRef<'a>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Map(_, _, pairs) => pairs
+                .iter()
+                .map(|(k, v)| Ok((K::from_sql(k.clone())?, V::from_sql(v.clone())?)))
+                .collect(),
+            ValueRef::Array(SqlType::Tuple(_), vs) => {
+                vs.iter().map(|v| <(K, V)>::from_sql(v.clone())).collect()
+            }
+            _ => {
+                let from = SqlType::from(value.clone()).to_string();
+                Err(Error::FromSql(FromSqlError::InvalidType {
+                    src: from,
+                    dst: "Vec<(K, V)>".into()
+                }))
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use chrono::prelude::*;
     use chrono_tz::Tz;
 
@@ -344,6 +446,16 @@ mod test {
         assert_eq!(actual, 42_u8);
     }
 
+    #[test]
+    fn test_tuple() {
+        let v = ValueRef::Tuple(Arc::new(vec![ValueRef::from("a"), ValueRef::from(42_u32)]));
+        let actual = <(String, u32)>::from_sql(v.clone()).unwrap();
+        assert_eq!(actual, ("a".to_string(), 42));
+
+        assert!(<(String, u32, u8)>::from_sql(v.clone()).is_err());
+        assert!(<(String, u64)>::from_sql(v).is_err());
+    }
+
     #[test]
     fn test_bad_convert() {
         let v = ValueRef::from(42_u16);
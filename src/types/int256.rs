@@ -0,0 +1,146 @@
+use std::fmt;
+
+/// Unsigned 256-bit integer, as the 32 little-endian bytes ClickHouse sends
+/// for `UInt256`. Only conversions and formatting are provided, no arithmetic.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct U256(pub(crate) [u8; 32]);
+
+/// Signed (two's complement) 256-bit integer, stored like [`U256`].
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct I256(pub(crate) [u8; 32]);
+
+impl U256 {
+    pub const MAX: U256 = U256([0xff; 32]);
+
+    pub fn from_le_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn to_le_bytes(self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl I256 {
+    pub fn from_le_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn to_le_bytes(self) -> [u8; 32] {
+        self.0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0[31] & 0x80 != 0
+    }
+
+    /// Magnitude of the value, e.g. `1` for `-1`.
+    fn unsigned_abs(self) -> U256 {
+        if !self.is_negative() {
+            return U256(self.0);
+        }
+
+        let mut bytes = self.0;
+        let mut carry = true;
+        for byte in bytes.iter_mut() {
+            *byte = !*byte;
+            if carry {
+                let (sum, overflow) = byte.overflowing_add(1);
+                *byte = sum;
+                carry = overflow;
+            }
+        }
+        U256(bytes)
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(value: u128) -> Self {
+        let mut bytes = [0_u8; 32];
+        bytes[..16].copy_from_slice(&value.to_le_bytes());
+        Self(bytes)
+    }
+}
+
+impl From<i128> for I256 {
+    fn from(value: i128) -> Self {
+        let fill = if value < 0 { 0xff } else { 0 };
+        let mut bytes = [fill; 32];
+        bytes[..16].copy_from_slice(&value.to_le_bytes());
+        Self(bytes)
+    }
+}
+
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Long division by 10 over big-endian 32-bit limbs.
+        let mut limbs = [0_u32; 8];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let mut chunk = [0_u8; 4];
+            chunk.copy_from_slice(&self.0[28 - i * 4..32 - i * 4]);
+            *limb = u32::from_le_bytes(chunk);
+        }
+
+        let mut digits = Vec::new();
+        while limbs.iter().any(|limb| *limb != 0) {
+            let mut remainder = 0_u64;
+            for limb in limbs.iter_mut() {
+                let current = (remainder << 32) | u64::from(*limb);
+                *limb = (current / 10) as u32;
+                remainder = current % 10;
+            }
+            digits.push(b'0' + remainder as u8);
+        }
+
+        if digits.is_empty() {
+            digits.push(b'0');
+        }
+        digits.reverse();
+        f.pad_integral(true, "", &String::from_utf8(digits).unwrap())
+    }
+}
+
+impl fmt::Display for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.unsigned_abs().to_string();
+        f.pad_integral(!self.is_negative(), "", &digits)
+    }
+}
+
+impl fmt::Debug for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Debug for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(U256::default().to_string(), "0");
+        assert_eq!(U256::from(u128::MAX).to_string(), u128::MAX.to_string());
+        assert_eq!(
+            U256::MAX.to_string(),
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        );
+
+        assert_eq!(I256::from(-1).to_string(), "-1");
+        assert_eq!(I256::from(i128::MIN).to_string(), i128::MIN.to_string());
+        assert_eq!(I256::from(42).to_string(), "42");
+
+        let mut min = [0_u8; 32];
+        min[31] = 0x80;
+        assert_eq!(
+            I256::from_le_bytes(min).to_string(),
+            "-57896044618658097711785492504343953926634992332820282019728792003956564819968"
+        );
+    }
+}
@@ -1,3 +1,6 @@
+use crate::types::I256;
+use crate::types::U256;
+
 pub trait Marshal {
     fn marshal(&self, scratch: &mut [u8]);
 }
@@ -74,6 +77,30 @@ impl Marshal for i64 {
     }
 }
 
+impl Marshal for i128 {
+    fn marshal(&self, scratch: &mut [u8]) {
+        scratch[..16].copy_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl Marshal for u128 {
+    fn marshal(&self, scratch: &mut [u8]) {
+        scratch[..16].copy_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl Marshal for I256 {
+    fn marshal(&self, scratch: &mut [u8]) {
+        scratch[..32].copy_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl Marshal for U256 {
+    fn marshal(&self, scratch: &mut [u8]) {
+        scratch[..32].copy_from_slice(&self.to_le_bytes());
+    }
+}
+
 impl Marshal for f32 {
     fn marshal(&self, scratch: &mut [u8]) {
         let bits = self.to_bits();
@@ -152,6 +179,11 @@ mod test {
         test_some::<u64>()
     }
 
+    #[test]
+    fn test_u128() {
+        test_some::<u128>()
+    }
+
     #[test]
     fn test_i8() {
         test_some::<i8>()
@@ -2,6 +2,8 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt;
 use std::mem;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
 use std::pin::Pin;
 use std::sync::Mutex;
 
@@ -11,30 +13,48 @@ use hostname::get;
 use lazy_static::lazy_static;
 
 pub use self::block::Block;
+pub use self::block::FromBlock;
+pub use self::block::IntoBlock;
+pub use self::block::Nested;
 pub use self::block::RCons;
 pub use self::block::RNil;
 pub use self::block::Row;
 pub use self::block::RowBuilder;
 pub use self::block::Rows;
+pub use self::column::aggregate_function::AggregateFunctionState;
+pub use self::column::geo::MultiPolygon;
+pub use self::column::geo::Point;
+pub use self::column::geo::Polygon;
+pub use self::column::geo::Ring;
 pub use self::column::Column;
 pub use self::column::ColumnType;
 pub use self::column::Complex;
 pub use self::column::Simple;
+pub use self::column::StringColumnData;
+pub use self::column::VectorColumnData;
 pub(crate) use self::date_converter::DateConverter;
 pub use self::decimal::Decimal;
 pub use self::enums::Enum16;
 pub use self::enums::Enum8;
 pub use self::from_sql::FromSql;
 pub use self::from_sql::FromSqlResult;
+pub use self::int256::I256;
+pub use self::int256::U256;
 pub(crate) use self::marshal::Marshal;
 pub use self::options::Options;
 pub(crate) use self::options::OptionsSource;
 pub use self::query::Query;
+pub use self::settings::Settings;
 pub(crate) use self::stat_buffer::StatBuffer;
 pub(crate) use self::unmarshal::Unmarshal;
 pub use self::value::Value;
 pub use self::value_ref::ValueRef;
+#[cfg(feature = "derive")]
+pub use clickhouse_srv_derive::FromBlock;
+#[cfg(feature = "derive")]
+pub use clickhouse_srv_derive::IntoBlock;
 use crate::binary::Encoder;
+use crate::protocols::DBMS_MIN_PROTOCOL_VERSION_WITH_SERVER_QUERY_TIME_IN_PROGRESS;
 use crate::protocols::DBMS_MIN_REVISION_WITH_CLIENT_WRITE_INFO;
 use crate::protocols::SERVER_PROGRESS;
 
@@ -54,13 +74,26 @@ mod query;
 
 mod decimal;
 mod enums;
+mod int256;
 mod options;
+mod settings;
 
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Progress {
+    /// Rows read so far.
     pub rows: u64,
+    /// Bytes read so far.
     pub bytes: u64,
-    pub total_rows: u64
+    /// Rows the query is expected to read in total, zero if unknown.
+    pub total_rows: u64,
+
+    // Sent to clients with revision >= 54420
+    pub written_rows: u64,
+    pub written_bytes: u64,
+
+    // Sent to clients with revision >= 54460
+    /// Time the query has been running.
+    pub elapsed_ns: u64
 }
 
 impl Progress {
@@ -71,8 +104,12 @@ impl Progress {
         encoder.uvarint(self.total_rows);
 
         if client_revision >= DBMS_MIN_REVISION_WITH_CLIENT_WRITE_INFO {
-            encoder.uvarint(0);
-            encoder.uvarint(0);
+            encoder.uvarint(self.written_rows);
+            encoder.uvarint(self.written_bytes);
+        }
+
+        if client_revision >= DBMS_MIN_PROTOCOL_VERSION_WITH_SERVER_QUERY_TIME_IN_PROGRESS {
+            encoder.uvarint(self.elapsed_ns);
         }
     }
 }
@@ -165,21 +202,33 @@ has_sql_type! {
     u16: SqlType::UInt16,
     u32: SqlType::UInt32,
     u64: SqlType::UInt64,
+    u128: SqlType::UInt128,
     i8: SqlType::Int8,
     i16: SqlType::Int16,
     i32: SqlType::Int32,
     i64: SqlType::Int64,
+    i128: SqlType::Int128,
+    I256: SqlType::Int256,
+    U256: SqlType::UInt256,
     &str: SqlType::String,
     String: SqlType::String,
     f32: SqlType::Float32,
     f64: SqlType::Float64,
+    bool: SqlType::Bool,
     Date<Tz>: SqlType::Date,
-    DateTime<Tz>: SqlType::DateTime(DateTimeType::DateTime32)
+    NaiveDate: SqlType::Date32,
+    DateTime<Tz>: SqlType::DateTime(DateTimeType::DateTime32),
+    Ipv4Addr: SqlType::Ipv4,
+    Ipv6Addr: SqlType::Ipv6,
+    uuid::Uuid: SqlType::Uuid
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum DateTimeType {
     DateTime32,
+    /// `DateTime('tz')`, a `DateTime` whose values are shown in `tz` rather than
+    /// in the session timezone.
+    DateTime32Tz(Tz),
     DateTime64(u32, Tz),
     Chrono
 }
@@ -190,24 +239,47 @@ pub enum SqlType {
     UInt16,
     UInt32,
     UInt64,
+    UInt128,
+    UInt256,
     Int8,
     Int16,
     Int32,
     Int64,
+    Int128,
+    Int256,
     String,
     FixedString(usize),
     Float32,
     Float64,
+    Bool,
     Date,
+    Date32,
     DateTime(DateTimeType),
     Ipv4,
     Ipv6,
     Uuid,
+    Nothing,
+    Point,
+    Ring,
+    Polygon,
+    MultiPolygon,
     Nullable(&'static SqlType),
     Array(&'static SqlType),
+    LowCardinality(&'static SqlType),
+    Tuple(Vec<(Option<String>, &'static SqlType)>),
+    Map(&'static SqlType, &'static SqlType),
     Decimal(u8, u8),
     Enum8(Vec<(String, i8)>),
-    Enum16(Vec<(String, i16)>)
+    Enum16(Vec<(String, i16)>),
+    /// Values of `T` reduced by the function, e.g. `max`, when parts merge.
+    SimpleAggregateFunction(String, &'static SqlType),
+    /// Opaque states of the aggregate function and argument types inside the
+    /// parentheses, e.g. `sum, UInt64`.
+    AggregateFunction(String),
+    /// `JSON` objects, forwarded as their serialized strings.
+    Json,
+    /// `Object('json')`, the older name of `JSON`, with the schema in quotes.
+    Object(String)
 }
 
 lazy_static! {
@@ -225,10 +297,21 @@ impl From<SqlType> for &'static SqlType {
             SqlType::Int16 => &SqlType::Int16,
             SqlType::Int32 => &SqlType::Int32,
             SqlType::Int64 => &SqlType::Int64,
+            SqlType::Int128 => &SqlType::Int128,
+            SqlType::UInt128 => &SqlType::UInt128,
+            SqlType::Int256 => &SqlType::Int256,
+            SqlType::UInt256 => &SqlType::UInt256,
             SqlType::String => &SqlType::String,
             SqlType::Float32 => &SqlType::Float32,
             SqlType::Float64 => &SqlType::Float64,
+            SqlType::Bool => &SqlType::Bool,
             SqlType::Date => &SqlType::Date,
+            SqlType::Date32 => &SqlType::Date32,
+            SqlType::Nothing => &SqlType::Nothing,
+            SqlType::Point => &SqlType::Point,
+            SqlType::Ring => &SqlType::Ring,
+            SqlType::Polygon => &SqlType::Polygon,
+            SqlType::MultiPolygon => &SqlType::MultiPolygon,
             _ => {
                 let mut guard = TYPES_CACHE.lock().unwrap();
                 loop {
@@ -253,41 +336,77 @@ impl SqlType {
             SqlType::UInt16 => "UInt16".into(),
             SqlType::UInt32 => "UInt32".into(),
             SqlType::UInt64 => "UInt64".into(),
+            SqlType::UInt128 => "UInt128".into(),
+            SqlType::UInt256 => "UInt256".into(),
             SqlType::Int8 => "Int8".into(),
             SqlType::Int16 => "Int16".into(),
             SqlType::Int32 => "Int32".into(),
             SqlType::Int64 => "Int64".into(),
+            SqlType::Int128 => "Int128".into(),
+            SqlType::Int256 => "Int256".into(),
             SqlType::String => "String".into(),
             SqlType::FixedString(str_len) => format!("FixedString({})", str_len).into(),
             SqlType::Float32 => "Float32".into(),
             SqlType::Float64 => "Float64".into(),
+            SqlType::Bool => "Bool".into(),
             SqlType::Date => "Date".into(),
+            SqlType::Date32 => "Date32".into(),
             SqlType::DateTime(DateTimeType::DateTime64(precision, tz)) => {
                 format!("DateTime64({}, '{:?}')", precision, tz).into()
             }
+            SqlType::DateTime(DateTimeType::DateTime32Tz(tz)) => {
+                format!("DateTime('{:?}')", tz).into()
+            }
             SqlType::DateTime(_) => "DateTime".into(),
             SqlType::Ipv4 => "IPv4".into(),
             SqlType::Ipv6 => "IPv6".into(),
             SqlType::Uuid => "UUID".into(),
+            SqlType::Nothing => "Nothing".into(),
+            SqlType::Point => "Point".into(),
+            SqlType::Ring => "Ring".into(),
+            SqlType::Polygon => "Polygon".into(),
+            SqlType::MultiPolygon => "MultiPolygon".into(),
             SqlType::Nullable(nested) => format!("Nullable({})", &nested).into(),
             SqlType::Array(nested) => format!("Array({})", &nested).into(),
+            SqlType::Tuple(elements) => {
+                let a: Vec<String> = elements
+                    .iter()
+                    .map(|(name, element_type)| match name {
+                        Some(name) => format!("{} {}", name, element_type),
+                        None => element_type.to_string()
+                    })
+                    .collect();
+                format!("Tuple({})", a.join(", ")).into()
+            }
+            SqlType::Map(key_type, value_type) => {
+                format!("Map({}, {})", key_type, value_type).into()
+            }
+            SqlType::LowCardinality(nested) => format!("LowCardinality({})", &nested).into(),
             SqlType::Decimal(precision, scale) => {
                 format!("Decimal({}, {})", precision, scale).into()
             }
             SqlType::Enum8(values) => {
                 let a: Vec<String> = values
                     .iter()
-                    .map(|(name, value)| format!("'{}' = {}", name, value))
+                    .map(|(name, value)| format!("'{}' = {}", escape_enum_name(name), value))
                     .collect();
                 format!("Enum8({})", a.join(",")).into()
             }
             SqlType::Enum16(values) => {
                 let a: Vec<String> = values
                     .iter()
-                    .map(|(name, value)| format!("'{}' = {}", name, value))
+                    .map(|(name, value)| format!("'{}' = {}", escape_enum_name(name), value))
                     .collect();
                 format!("Enum16({})", a.join(",")).into()
             }
+            SqlType::SimpleAggregateFunction(function, inner) => {
+                format!("SimpleAggregateFunction({}, {})", function, inner).into()
+            }
+            SqlType::AggregateFunction(function) => {
+                format!("AggregateFunction({})", function).into()
+            }
+            SqlType::Json => "JSON".into(),
+            SqlType::Object(schema) => format!("Object('{}')", schema).into()
         }
     }
 
@@ -300,6 +419,21 @@ impl SqlType {
     }
 }
 
+fn escape_enum_name(name: &str) -> Cow<'_, str> {
+    if !name.contains(['\\', '\'']) {
+        return name.into();
+    }
+
+    let mut escaped = String::with_capacity(name.len() + 2);
+    for c in name.chars() {
+        if c == '\\' || c == '\'' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped.into()
+}
+
 impl fmt::Display for SqlType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", Self::to_string(self))
@@ -319,3 +453,27 @@ fn test_to_string() {
     let actual = SqlType::Nullable(&SqlType::UInt8).to_string();
     assert_eq!(expected, actual)
 }
+
+#[test]
+fn test_write_progress() {
+    let progress = Progress {
+        rows: 1,
+        bytes: 2,
+        total_rows: 3,
+        written_rows: 4,
+        written_bytes: 5,
+        elapsed_ns: 6
+    };
+
+    let mut encoder = Encoder::new();
+    progress.write(&mut encoder, 54405);
+    assert_eq!(encoder.get_buffer(), vec![3_u8, 1, 2, 3]);
+
+    let mut encoder = Encoder::new();
+    progress.write(&mut encoder, 54420);
+    assert_eq!(encoder.get_buffer(), vec![3_u8, 1, 2, 3, 4, 5]);
+
+    let mut encoder = Encoder::new();
+    progress.write(&mut encoder, 54460);
+    assert_eq!(encoder.get_buffer(), vec![3_u8, 1, 2, 3, 4, 5, 6]);
+}
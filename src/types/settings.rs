@@ -0,0 +1,161 @@
+use std::collections::hash_map;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::errors::DriverError;
+use crate::errors::Error;
+use crate::errors::Result;
+
+/// Settings sent with a query, kept as the strings they came in and parsed on
+/// access. Each typed getter returns `None` for a setting the client did not
+/// send, its `_or` variant the supplied default, and fails with
+/// `DriverError::InvalidSetting` when the value does not parse.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Settings {
+    values: HashMap<String, String>
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The settings as `(name, value)` pairs, in no particular order.
+    pub fn iter(&self) -> hash_map::Iter<'_, String, String> {
+        self.values.iter()
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    pub fn get_str_or<'a>(&'a self, name: &str, default: &'a str) -> &'a str {
+        self.get_str(name).unwrap_or(default)
+    }
+
+    pub fn get_u64(&self, name: &str) -> Result<Option<u64>> {
+        self.parse(name, "an unsigned integer")
+    }
+
+    pub fn get_u64_or(&self, name: &str, default: u64) -> Result<u64> {
+        Ok(self.get_u64(name)?.unwrap_or(default))
+    }
+
+    /// Fractional values are accepted, as for `max_execution_time` in seconds.
+    pub fn get_f64(&self, name: &str) -> Result<Option<f64>> {
+        self.parse(name, "a number")
+    }
+
+    pub fn get_f64_or(&self, name: &str, default: f64) -> Result<f64> {
+        Ok(self.get_f64(name)?.unwrap_or(default))
+    }
+
+    /// `0`, `1`, `true` or `false` in any case, as the server accepts them.
+    pub fn get_bool(&self, name: &str) -> Result<Option<bool>> {
+        let value = match self.get_str(name) {
+            None => return Ok(None),
+            Some(value) => value
+        };
+        match value.to_ascii_lowercase().as_str() {
+            "1" | "true" => Ok(Some(true)),
+            "0" | "false" => Ok(Some(false)),
+            _ => Err(invalid_setting(name, value, "a boolean"))
+        }
+    }
+
+    pub fn get_bool_or(&self, name: &str, default: bool) -> Result<bool> {
+        Ok(self.get_bool(name)?.unwrap_or(default))
+    }
+
+    fn parse<T: FromStr>(&self, name: &str, expected: &'static str) -> Result<Option<T>> {
+        match self.get_str(name) {
+            None => Ok(None),
+            Some(value) => value
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|_| invalid_setting(name, value, expected))
+        }
+    }
+}
+
+fn invalid_setting(name: &str, value: &str, expected: &'static str) -> Error {
+    Error::Driver(DriverError::InvalidSetting {
+        name: name.to_string(),
+        value: value.to_string(),
+        expected
+    })
+}
+
+impl From<HashMap<String, String>> for Settings {
+    fn from(values: HashMap<String, String>) -> Self {
+        Self { values }
+    }
+}
+
+impl<'a> IntoIterator for &'a Settings {
+    type Item = (&'a String, &'a String);
+    type IntoIter = hash_map::Iter<'a, String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error_codes;
+
+    #[test]
+    fn test_typed_getters() {
+        let mut settings = Settings::new();
+        settings.insert("max_block_size", "100");
+        settings.insert("extremes", "1");
+        settings.insert("max_execution_time", "1.5");
+        settings.insert("network_compression_method", "ZSTD");
+
+        assert_eq!(settings.get_u64("max_block_size").unwrap(), Some(100));
+        assert_eq!(settings.get_bool("extremes").unwrap(), Some(true));
+        assert_eq!(settings.get_f64("max_execution_time").unwrap(), Some(1.5));
+        assert_eq!(settings.get_str("network_compression_method"), Some("ZSTD"));
+
+        assert_eq!(settings.get_u64("max_threads").unwrap(), None);
+        assert_eq!(settings.get_u64_or("max_threads", 8).unwrap(), 8);
+        assert!(!settings.get_bool_or("readonly", false).unwrap());
+        assert_eq!(settings.get_str_or("readonly", "0"), "0");
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        let mut settings = Settings::new();
+        settings.insert("max_block_size", "many");
+        settings.insert("extremes", "yes");
+
+        let err = settings.get_u64("max_block_size").unwrap_err();
+        assert_eq!(err.code(), error_codes::BAD_ARGUMENTS);
+        assert_eq!(
+            err.to_string(),
+            "Driver error: `Invalid value `many` of setting max_block_size, \
+             expected an unsigned integer.`"
+        );
+        assert!(settings.get_bool_or("extremes", false).is_err());
+        assert!(settings.get_f64("max_block_size").is_err());
+    }
+}
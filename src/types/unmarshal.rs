@@ -1,3 +1,6 @@
+use crate::types::I256;
+use crate::types::U256;
+
 pub trait Unmarshal<T: Copy> {
     fn unmarshal(scratch: &[u8]) -> T;
 }
@@ -70,6 +73,38 @@ impl Unmarshal<i64> for i64 {
     }
 }
 
+impl Unmarshal<i128> for i128 {
+    fn unmarshal(scratch: &[u8]) -> Self {
+        let mut bytes = [0_u8; 16];
+        bytes.copy_from_slice(&scratch[..16]);
+        Self::from_le_bytes(bytes)
+    }
+}
+
+impl Unmarshal<u128> for u128 {
+    fn unmarshal(scratch: &[u8]) -> Self {
+        let mut bytes = [0_u8; 16];
+        bytes.copy_from_slice(&scratch[..16]);
+        Self::from_le_bytes(bytes)
+    }
+}
+
+impl Unmarshal<I256> for I256 {
+    fn unmarshal(scratch: &[u8]) -> Self {
+        let mut bytes = [0_u8; 32];
+        bytes.copy_from_slice(&scratch[..32]);
+        Self::from_le_bytes(bytes)
+    }
+}
+
+impl Unmarshal<U256> for U256 {
+    fn unmarshal(scratch: &[u8]) -> Self {
+        let mut bytes = [0_u8; 32];
+        bytes.copy_from_slice(&scratch[..32]);
+        Self::from_le_bytes(bytes)
+    }
+}
+
 impl Unmarshal<f32> for f32 {
     fn unmarshal(scratch: &[u8]) -> Self {
         let bits = u32::from(scratch[0])
@@ -10,16 +10,23 @@ use chrono::prelude::*;
 use chrono_tz::Tz;
 use uuid::Uuid;
 
+use crate::types::column::date::date_value;
+use crate::types::column::datetime64::from_datetime;
 use crate::types::column::datetime64::to_datetime;
+use crate::types::column::geo::storage_type;
 use crate::types::column::Either;
 use crate::types::decimal::Decimal;
 use crate::types::decimal::NoBits;
+use crate::types::date_converter::date_from_days;
+use crate::types::date_converter::days_since_epoch;
 use crate::types::DateConverter;
 use crate::types::DateTimeType;
 use crate::types::Enum16;
 use crate::types::Enum8;
 use crate::types::HasSqlType;
+use crate::types::I256;
 use crate::types::SqlType;
+use crate::types::U256;
 
 pub(crate) type AppDateTime = DateTime<Tz>;
 pub(crate) type AppDate = Date<Tz>;
@@ -31,14 +38,20 @@ pub enum Value {
     UInt16(u16),
     UInt32(u32),
     UInt64(u64),
+    UInt128(u128),
+    UInt256(U256),
     Int8(i8),
     Int16(i16),
     Int32(i32),
     Int64(i64),
+    Int128(i128),
+    Int256(I256),
     String(Arc<Vec<u8>>),
     Float32(f32),
     Float64(f64),
+    Bool(bool),
     Date(u16, Tz),
+    Date32(i32, Tz),
     DateTime(u32, Tz),
     DateTime64(i64, (u32, Tz)),
     Ipv4([u8; 4]),
@@ -46,6 +59,8 @@ pub enum Value {
     Uuid([u8; 16]),
     Nullable(Either<&'static SqlType, Box<Value>>),
     Array(&'static SqlType, Arc<Vec<Value>>),
+    Tuple(Arc<Vec<Value>>),
+    Map(&'static SqlType, &'static SqlType, Arc<Vec<(Value, Value)>>),
     Decimal(Decimal),
     Enum8(Vec<(String, i8)>, Enum8),
     Enum16(Vec<(String, i16)>, Enum16)
@@ -58,18 +73,24 @@ impl PartialEq for Value {
             (Value::UInt16(a), Value::UInt16(b)) => *a == *b,
             (Value::UInt32(a), Value::UInt32(b)) => *a == *b,
             (Value::UInt64(a), Value::UInt64(b)) => *a == *b,
+            (Value::UInt128(a), Value::UInt128(b)) => *a == *b,
+            (Value::UInt256(a), Value::UInt256(b)) => *a == *b,
             (Value::Int8(a), Value::Int8(b)) => *a == *b,
             (Value::Int16(a), Value::Int16(b)) => *a == *b,
             (Value::Int32(a), Value::Int32(b)) => *a == *b,
             (Value::Int64(a), Value::Int64(b)) => *a == *b,
+            (Value::Int128(a), Value::Int128(b)) => *a == *b,
+            (Value::Int256(a), Value::Int256(b)) => *a == *b,
             (Value::String(a), Value::String(b)) => *a == *b,
             (Value::Float32(a), Value::Float32(b)) => *a == *b,
             (Value::Float64(a), Value::Float64(b)) => *a == *b,
+            (Value::Bool(a), Value::Bool(b)) => *a == *b,
             (Value::Date(a, tz_a), Value::Date(b, tz_b)) => {
                 let time_a = tz_a.timestamp(i64::from(*a) * 24 * 3600, 0);
                 let time_b = tz_b.timestamp(i64::from(*b) * 24 * 3600, 0);
                 time_a.date() == time_b.date()
             }
+            (Value::Date32(a, _), Value::Date32(b, _)) => *a == *b,
             (Value::DateTime(a, tz_a), Value::DateTime(b, tz_b)) => {
                 let time_a = tz_a.timestamp(i64::from(*a), 0);
                 let time_b = tz_b.timestamp(i64::from(*b), 0);
@@ -77,7 +98,12 @@ impl PartialEq for Value {
             }
             (Value::Nullable(a), Value::Nullable(b)) => *a == *b,
             (Value::Array(ta, a), Value::Array(tb, b)) => *ta == *tb && *a == *b,
+            (Value::Tuple(a), Value::Tuple(b)) => *a == *b,
+            (Value::Map(ka, va, a), Value::Map(kb, vb, b)) => *ka == *kb && *va == *vb && *a == *b,
             (Value::Decimal(a), Value::Decimal(b)) => *a == *b,
+            (Value::Ipv4(a), Value::Ipv4(b)) => *a == *b,
+            (Value::Ipv6(a), Value::Ipv6(b)) => *a == *b,
+            (Value::Uuid(a), Value::Uuid(b)) => *a == *b,
             (Value::Enum16(values_a, val_a), Value::Enum16(values_b, val_b)) => {
                 *values_a == *values_b && *val_a == *val_b
             }
@@ -87,6 +113,29 @@ impl PartialEq for Value {
 }
 
 impl Value {
+    /// Makes a `DateTime64(precision)` value, keeping `precision` digits of
+    /// the sub-second part of `time`. A plain `DateTime<Tz>` converts into a
+    /// `DateTime` value, which only has whole seconds.
+    pub fn datetime64(time: DateTime<Tz>, precision: u32) -> Value {
+        let tz = time.timezone();
+        Value::DateTime64(from_datetime(time, precision), (precision, tz))
+    }
+
+    /// Makes a `Map(K, V)` value out of key/value pairs. Pairs keep their
+    /// order and duplicate keys are not merged, the same as in ClickHouse.
+    pub fn map<K, V, I>(pairs: I) -> Value
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<Value> + HasSqlType,
+        V: Into<Value> + HasSqlType
+    {
+        let pairs = pairs
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        Value::Map(K::get_sql_type().into(), V::get_sql_type().into(), Arc::new(pairs))
+    }
+
     pub(crate) fn default(sql_type: SqlType) -> Value {
         match sql_type {
             SqlType::UInt8 => Value::UInt8(0),
@@ -97,17 +146,34 @@ impl Value {
             SqlType::Int16 => Value::Int16(0),
             SqlType::Int32 => Value::Int32(0),
             SqlType::Int64 => Value::Int64(0),
+            SqlType::Int128 => Value::Int128(0),
+            SqlType::Int256 => Value::Int256(I256::default()),
+            SqlType::UInt128 => Value::UInt128(0),
+            SqlType::UInt256 => Value::UInt256(U256::default()),
             SqlType::String => Value::String(Arc::new(Vec::default())),
             SqlType::FixedString(str_len) => Value::String(Arc::new(vec![0_u8; str_len])),
             SqlType::Float32 => Value::Float32(0.0),
             SqlType::Float64 => Value::Float64(0.0),
+            SqlType::Bool => Value::Bool(false),
             SqlType::Date => 0_u16.to_date(Tz::Zulu).into(),
+            SqlType::Date32 => Value::Date32(0, Tz::Zulu),
             SqlType::DateTime(DateTimeType::DateTime64(_, _)) => {
                 Value::DateTime64(0, (1, Tz::Zulu))
             }
             SqlType::DateTime(_) => 0_u32.to_date(Tz::Zulu).into(),
             SqlType::Nullable(inner) => Value::Nullable(Either::Left(inner)),
             SqlType::Array(inner) => Value::Array(inner, Arc::new(Vec::default())),
+            SqlType::LowCardinality(inner) => Value::default(inner.clone()),
+            SqlType::Map(key_type, value_type) => {
+                Value::Map(key_type, value_type, Arc::new(Vec::default()))
+            }
+            SqlType::Tuple(elements) => {
+                let vs = elements
+                    .into_iter()
+                    .map(|(_, element_type)| Value::default(element_type.clone()))
+                    .collect();
+                Value::Tuple(Arc::new(vs))
+            }
             SqlType::Decimal(precision, scale) => Value::Decimal(Decimal {
                 underlying: 0,
                 precision,
@@ -117,8 +183,16 @@ impl Value {
             SqlType::Ipv4 => Value::Ipv4([0_u8; 4]),
             SqlType::Ipv6 => Value::Ipv6([0_u8; 16]),
             SqlType::Uuid => Value::Uuid([0_u8; 16]),
+            SqlType::Nothing => Value::Nullable(Either::Left(&SqlType::Nothing)),
+            SqlType::Point | SqlType::Ring | SqlType::Polygon | SqlType::MultiPolygon => {
+                Value::default(storage_type(&sql_type))
+            }
             SqlType::Enum8(values) => Value::Enum8(values, Enum8(0)),
-            SqlType::Enum16(values) => Value::Enum16(values, Enum16(0))
+            SqlType::Enum16(values) => Value::Enum16(values, Enum16(0)),
+            SqlType::SimpleAggregateFunction(_, inner) => Value::default(inner.clone()),
+            SqlType::AggregateFunction(_) | SqlType::Json | SqlType::Object(_) => {
+                Value::String(Arc::new(Vec::default()))
+            }
         }
     }
 }
@@ -134,12 +208,17 @@ impl fmt::Display for Value {
             Value::Int16(ref v) => fmt::Display::fmt(v, f),
             Value::Int32(ref v) => fmt::Display::fmt(v, f),
             Value::Int64(ref v) => fmt::Display::fmt(v, f),
+            Value::Int128(ref v) => fmt::Display::fmt(v, f),
+            Value::Int256(ref v) => fmt::Display::fmt(v, f),
+            Value::UInt128(ref v) => fmt::Display::fmt(v, f),
+            Value::UInt256(ref v) => fmt::Display::fmt(v, f),
             Value::String(ref v) => match str::from_utf8(v) {
                 Ok(s) => fmt::Display::fmt(s, f),
                 Err(_) => write!(f, "{:?}", v)
             },
             Value::Float32(ref v) => fmt::Display::fmt(v, f),
             Value::Float64(ref v) => fmt::Display::fmt(v, f),
+            Value::Bool(ref v) => fmt::Display::fmt(v, f),
             Value::DateTime(u, tz) if f.alternate() => {
                 let time = tz.timestamp(i64::from(*u), 0);
                 fmt::Display::fmt(&time, f)
@@ -163,6 +242,10 @@ impl fmt::Display for Value {
                 let date = time.date();
                 fmt::Display::fmt(&date.format("%Y-%m-%d"), f)
             }
+            Value::Date32(v, _) => match date_from_days(i64::from(*v)) {
+                Some(date) => fmt::Display::fmt(&date.format("%Y-%m-%d"), f),
+                None => write!(f, "{}", v)
+            },
             Value::Nullable(v) => match v {
                 Either::Left(_) => write!(f, "NULL"),
                 Either::Right(data) => data.fmt(f)
@@ -171,6 +254,15 @@ impl fmt::Display for Value {
                 let cells: Vec<String> = vs.iter().map(|v| format!("{}", v)).collect();
                 write!(f, "[{}]", cells.join(", "))
             }
+            Value::Tuple(vs) => {
+                let cells: Vec<String> = vs.iter().map(|v| format!("{}", v)).collect();
+                write!(f, "({})", cells.join(", "))
+            }
+            Value::Map(_, _, pairs) => {
+                let cells: Vec<String> =
+                    pairs.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+                write!(f, "{{{}}}", cells.join(", "))
+            }
             Value::Decimal(v) => fmt::Display::fmt(v, f),
             Value::Ipv4(v) => {
                 write!(f, "{}", decode_ipv4(v))
@@ -204,10 +296,16 @@ impl convert::From<Value> for SqlType {
             Value::Int16(_) => SqlType::Int16,
             Value::Int32(_) => SqlType::Int32,
             Value::Int64(_) => SqlType::Int64,
+            Value::Int128(_) => SqlType::Int128,
+            Value::Int256(_) => SqlType::Int256,
+            Value::UInt128(_) => SqlType::UInt128,
+            Value::UInt256(_) => SqlType::UInt256,
             Value::String(_) => SqlType::String,
             Value::Float32(_) => SqlType::Float32,
             Value::Float64(_) => SqlType::Float64,
+            Value::Bool(_) => SqlType::Bool,
             Value::Date(_, _) => SqlType::Date,
+            Value::Date32(_, _) => SqlType::Date32,
             Value::DateTime(_, _) => SqlType::DateTime(DateTimeType::DateTime32),
             Value::Nullable(d) => match d {
                 Either::Left(t) => SqlType::Nullable(t),
@@ -217,6 +315,14 @@ impl convert::From<Value> for SqlType {
                 }
             },
             Value::Array(t, _) => SqlType::Array(t),
+            Value::Map(key_type, value_type, _) => SqlType::Map(key_type, value_type),
+            Value::Tuple(vs) => {
+                let elements = vs
+                    .iter()
+                    .map(|v| (None, SqlType::from(v.clone()).into()))
+                    .collect();
+                SqlType::Tuple(elements)
+            }
             Value::Decimal(v) => SqlType::Decimal(v.precision, v.scale),
             Value::Ipv4(_) => SqlType::Ipv4,
             Value::Ipv6(_) => SqlType::Ipv6,
@@ -259,9 +365,17 @@ macro_rules! value_from {
     };
 }
 
+/// A `Date`, or a `Date32` for a date outside 1970-01-01..2149-06-06 rather
+/// than wrapping it, a `Date` column rejects that one.
 impl convert::From<AppDate> for Value {
     fn from(v: AppDate) -> Value {
-        Value::Date(u16::get_days(v), v.timezone())
+        date_value(v.naive_local(), v.timezone(), true)
+    }
+}
+
+impl convert::From<NaiveDate> for Value {
+    fn from(v: NaiveDate) -> Value {
+        Value::Date32(days_since_epoch(v) as i32, Tz::Zulu)
     }
 }
 
@@ -283,6 +397,29 @@ impl convert::From<AppDateTime> for Value {
     }
 }
 
+impl convert::From<Ipv4Addr> for Value {
+    fn from(v: Ipv4Addr) -> Value {
+        let mut buffer = v.octets();
+        buffer.reverse();
+        Value::Ipv4(buffer)
+    }
+}
+
+impl convert::From<Ipv6Addr> for Value {
+    fn from(v: Ipv6Addr) -> Value {
+        Value::Ipv6(v.octets())
+    }
+}
+
+impl convert::From<Uuid> for Value {
+    fn from(v: Uuid) -> Value {
+        let mut buffer = *v.as_bytes();
+        buffer[..8].reverse();
+        buffer[8..].reverse();
+        Value::Uuid(buffer)
+    }
+}
+
 impl convert::From<String> for Value {
     fn from(v: String) -> Value {
         Value::String(Arc::new(v.into_bytes()))
@@ -306,15 +443,21 @@ value_from! {
     u16: UInt16,
     u32: UInt32,
     u64: UInt64,
+    u128: UInt128,
+    U256: UInt256,
 
     i8: Int8,
     i16: Int16,
     i32: Int32,
     i64: Int64,
+    i128: Int128,
+    I256: Int256,
 
     f32: Float32,
     f64: Float64,
 
+    bool: Bool,
+
     Decimal: Decimal
 }
 
@@ -378,6 +521,23 @@ impl convert::From<Value> for AppDate {
     }
 }
 
+impl convert::From<Value> for NaiveDate {
+    fn from(v: Value) -> NaiveDate {
+        match v {
+            // Any `UInt16` day is a valid date.
+            Value::Date(x, _) => date_from_days(i64::from(x)).unwrap(),
+            Value::Date32(x, _) => match date_from_days(i64::from(x)) {
+                Some(date) => date,
+                None => panic!("Value::Date32({}) is out of the range of NaiveDate", x)
+            },
+            _ => {
+                let from = SqlType::from(v);
+                panic!("Can't convert Value::{} into {}", from, "NaiveDate")
+            }
+        }
+    }
+}
+
 impl convert::From<Value> for AppDateTime {
     fn from(v: Value) -> AppDateTime {
         match v {
@@ -399,10 +559,14 @@ from_value! {
     u16: UInt16,
     u32: UInt32,
     u64: UInt64,
+    u128: UInt128,
+    U256: UInt256,
     i8: Int8,
     i16: Int16,
     i32: Int32,
     i64: Int64,
+    i128: Int128,
+    I256: Int256,
     f32: Float32,
     f64: Float64
 }
@@ -520,7 +684,7 @@ mod test {
         let dt: Value = date_time_value.into();
 
         assert_eq!(
-            Value::Date(u16::get_days(date_value), date_value.timezone()),
+            Value::Date(u16::get_days(date_value).unwrap(), date_value.timezone()),
             d
         );
         assert_eq!(
@@ -614,7 +778,7 @@ mod test {
     #[test]
     fn test_size_of() {
         use std::mem;
-        assert_eq!(32, mem::size_of::<[Value; 1]>());
+        assert_eq!(48, mem::size_of::<[Value; 1]>());
     }
 
     #[test]
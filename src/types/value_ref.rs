@@ -12,6 +12,7 @@ use crate::errors::FromSqlError;
 use crate::errors::Result;
 use crate::types::column::datetime64::to_datetime;
 use crate::types::column::Either;
+use crate::types::date_converter::date_from_days;
 use crate::types::decimal::Decimal;
 use crate::types::value::decode_ipv4;
 use crate::types::value::decode_ipv6;
@@ -20,7 +21,9 @@ use crate::types::value::AppDateTime;
 use crate::types::DateTimeType;
 use crate::types::Enum16;
 use crate::types::Enum8;
+use crate::types::I256;
 use crate::types::SqlType;
+use crate::types::U256;
 use crate::types::Value;
 
 #[derive(Clone, Debug)]
@@ -29,18 +32,26 @@ pub enum ValueRef<'a> {
     UInt16(u16),
     UInt32(u32),
     UInt64(u64),
+    UInt128(u128),
+    UInt256(U256),
     Int8(i8),
     Int16(i16),
     Int32(i32),
     Int64(i64),
+    Int128(i128),
+    Int256(I256),
     String(&'a [u8]),
     Float32(f32),
     Float64(f64),
+    Bool(bool),
     Date(u16, Tz),
+    Date32(i32, Tz),
     DateTime(u32, Tz),
     DateTime64(i64, &'a (u32, Tz)),
     Nullable(Either<&'static SqlType, Box<ValueRef<'a>>>),
     Array(&'static SqlType, Arc<Vec<ValueRef<'a>>>),
+    Tuple(Arc<Vec<ValueRef<'a>>>),
+    Map(&'static SqlType, &'static SqlType, Arc<Vec<(ValueRef<'a>, ValueRef<'a>)>>),
     Decimal(Decimal),
     Ipv4([u8; 4]),
     Ipv6([u8; 16]),
@@ -56,18 +67,24 @@ impl<'a> PartialEq for ValueRef<'a> {
             (ValueRef::UInt16(a), ValueRef::UInt16(b)) => *a == *b,
             (ValueRef::UInt32(a), ValueRef::UInt32(b)) => *a == *b,
             (ValueRef::UInt64(a), ValueRef::UInt64(b)) => *a == *b,
+            (ValueRef::UInt128(a), ValueRef::UInt128(b)) => *a == *b,
+            (ValueRef::UInt256(a), ValueRef::UInt256(b)) => *a == *b,
             (ValueRef::Int8(a), ValueRef::Int8(b)) => *a == *b,
             (ValueRef::Int16(a), ValueRef::Int16(b)) => *a == *b,
             (ValueRef::Int32(a), ValueRef::Int32(b)) => *a == *b,
             (ValueRef::Int64(a), ValueRef::Int64(b)) => *a == *b,
+            (ValueRef::Int128(a), ValueRef::Int128(b)) => *a == *b,
+            (ValueRef::Int256(a), ValueRef::Int256(b)) => *a == *b,
             (ValueRef::String(a), ValueRef::String(b)) => *a == *b,
             (ValueRef::Float32(a), ValueRef::Float32(b)) => *a == *b,
             (ValueRef::Float64(a), ValueRef::Float64(b)) => *a == *b,
+            (ValueRef::Bool(a), ValueRef::Bool(b)) => *a == *b,
             (ValueRef::Date(a, tz_a), ValueRef::Date(b, tz_b)) => {
                 let time_a = tz_a.timestamp(i64::from(*a) * 24 * 3600, 0);
                 let time_b = tz_b.timestamp(i64::from(*b) * 24 * 3600, 0);
                 time_a.date() == time_b.date()
             }
+            (ValueRef::Date32(a, _), ValueRef::Date32(b, _)) => *a == *b,
             (ValueRef::DateTime(a, tz_a), ValueRef::DateTime(b, tz_b)) => {
                 let time_a = tz_a.timestamp(i64::from(*a), 0);
                 let time_b = tz_b.timestamp(i64::from(*b), 0);
@@ -75,7 +92,14 @@ impl<'a> PartialEq for ValueRef<'a> {
             }
             (ValueRef::Nullable(a), ValueRef::Nullable(b)) => *a == *b,
             (ValueRef::Array(ta, a), ValueRef::Array(tb, b)) => *ta == *tb && *a == *b,
+            (ValueRef::Tuple(a), ValueRef::Tuple(b)) => *a == *b,
+            (ValueRef::Map(ka, va, a), ValueRef::Map(kb, vb, b)) => {
+                *ka == *kb && *va == *vb && *a == *b
+            }
             (ValueRef::Decimal(a), ValueRef::Decimal(b)) => *a == *b,
+            (ValueRef::Ipv4(a), ValueRef::Ipv4(b)) => *a == *b,
+            (ValueRef::Ipv6(a), ValueRef::Ipv6(b)) => *a == *b,
+            (ValueRef::Uuid(a), ValueRef::Uuid(b)) => *a == *b,
             (ValueRef::Enum8(a0, a1), ValueRef::Enum8(b0, b1)) => *a1 == *b1 && *a0 == *b0,
             (ValueRef::Enum16(a0, a1), ValueRef::Enum16(b0, b1)) => *a1 == *b1 && *a0 == *b0,
             (ValueRef::DateTime64(this, this_params), ValueRef::DateTime64(that, that_params)) => {
@@ -103,12 +127,17 @@ impl<'a> fmt::Display for ValueRef<'a> {
             ValueRef::Int16(v) => fmt::Display::fmt(v, f),
             ValueRef::Int32(v) => fmt::Display::fmt(v, f),
             ValueRef::Int64(v) => fmt::Display::fmt(v, f),
+            ValueRef::Int128(v) => fmt::Display::fmt(v, f),
+            ValueRef::Int256(v) => fmt::Display::fmt(v, f),
+            ValueRef::UInt128(v) => fmt::Display::fmt(v, f),
+            ValueRef::UInt256(v) => fmt::Display::fmt(v, f),
             ValueRef::String(v) => match str::from_utf8(v) {
                 Ok(s) => fmt::Display::fmt(s, f),
                 Err(_) => write!(f, "{:?}", *v)
             },
             ValueRef::Float32(v) => fmt::Display::fmt(v, f),
             ValueRef::Float64(v) => fmt::Display::fmt(v, f),
+            ValueRef::Bool(v) => fmt::Display::fmt(v, f),
             ValueRef::Date(v, tz) if f.alternate() => {
                 let time = tz.timestamp(i64::from(*v) * 24 * 3600, 0);
                 let date = time.date();
@@ -119,6 +148,10 @@ impl<'a> fmt::Display for ValueRef<'a> {
                 let date = time.date();
                 fmt::Display::fmt(&date.format("%Y-%m-%d"), f)
             }
+            ValueRef::Date32(v, _) => match date_from_days(i64::from(*v)) {
+                Some(date) => fmt::Display::fmt(&date.format("%Y-%m-%d"), f),
+                None => write!(f, "{}", v)
+            },
             ValueRef::DateTime(u, tz) if f.alternate() => {
                 let time = tz.timestamp(i64::from(*u), 0);
                 write!(f, "{}", time.to_rfc2822())
@@ -140,6 +173,15 @@ impl<'a> fmt::Display for ValueRef<'a> {
                 let cells: Vec<String> = vs.iter().map(|v| format!("{}", v)).collect();
                 write!(f, "[{}]", cells.join(", "))
             }
+            ValueRef::Tuple(vs) => {
+                let cells: Vec<String> = vs.iter().map(|v| format!("{}", v)).collect();
+                write!(f, "({})", cells.join(", "))
+            }
+            ValueRef::Map(_, _, pairs) => {
+                let cells: Vec<String> =
+                    pairs.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+                write!(f, "{{{}}}", cells.join(", "))
+            }
             ValueRef::Decimal(v) => fmt::Display::fmt(v, f),
             ValueRef::Ipv4(v) => {
                 write!(f, "{}", decode_ipv4(v))
@@ -173,16 +215,30 @@ impl<'a> convert::From<ValueRef<'a>> for SqlType {
             ValueRef::Int16(_) => SqlType::Int16,
             ValueRef::Int32(_) => SqlType::Int32,
             ValueRef::Int64(_) => SqlType::Int64,
+            ValueRef::Int128(_) => SqlType::Int128,
+            ValueRef::Int256(_) => SqlType::Int256,
+            ValueRef::UInt128(_) => SqlType::UInt128,
+            ValueRef::UInt256(_) => SqlType::UInt256,
             ValueRef::String(_) => SqlType::String,
             ValueRef::Float32(_) => SqlType::Float32,
             ValueRef::Float64(_) => SqlType::Float64,
+            ValueRef::Bool(_) => SqlType::Bool,
             ValueRef::Date(_, _) => SqlType::Date,
+            ValueRef::Date32(_, _) => SqlType::Date32,
             ValueRef::DateTime(_, _) => SqlType::DateTime(DateTimeType::DateTime32),
             ValueRef::Nullable(u) => match u {
                 Either::Left(sql_type) => SqlType::Nullable(sql_type),
                 Either::Right(value_ref) => SqlType::Nullable(SqlType::from(*value_ref).into())
             },
             ValueRef::Array(t, _) => SqlType::Array(t),
+            ValueRef::Map(key_type, value_type, _) => SqlType::Map(key_type, value_type),
+            ValueRef::Tuple(vs) => {
+                let elements = vs
+                    .iter()
+                    .map(|v| (None, SqlType::from(v.clone()).into()))
+                    .collect();
+                SqlType::Tuple(elements)
+            }
             ValueRef::Decimal(v) => SqlType::Decimal(v.precision, v.scale),
             ValueRef::Enum8(values, _) => SqlType::Enum8(values),
             ValueRef::Enum16(values, _) => SqlType::Enum16(values),
@@ -237,10 +293,16 @@ impl<'a> From<ValueRef<'a>> for Value {
             ValueRef::Int16(v) => Value::Int16(v),
             ValueRef::Int32(v) => Value::Int32(v),
             ValueRef::Int64(v) => Value::Int64(v),
+            ValueRef::Int128(v) => Value::Int128(v),
+            ValueRef::Int256(v) => Value::Int256(v),
+            ValueRef::UInt128(v) => Value::UInt128(v),
+            ValueRef::UInt256(v) => Value::UInt256(v),
             ValueRef::String(v) => Value::String(Arc::new(v.into())),
             ValueRef::Float32(v) => Value::Float32(v),
             ValueRef::Float64(v) => Value::Float64(v),
+            ValueRef::Bool(v) => Value::Bool(v),
             ValueRef::Date(v, tz) => Value::Date(v, tz),
+            ValueRef::Date32(v, tz) => Value::Date32(v, tz),
             ValueRef::DateTime(v, tz) => Value::DateTime(v, tz),
             ValueRef::Nullable(u) => match u {
                 Either::Left(sql_type) => Value::Nullable(Either::Left((sql_type.clone()).into())),
@@ -257,6 +319,17 @@ impl<'a> From<ValueRef<'a>> for Value {
                 }
                 Value::Array(t, Arc::new(value_list))
             }
+            ValueRef::Map(key_type, value_type, pairs) => {
+                let pairs = pairs
+                    .iter()
+                    .map(|(k, v)| (k.clone().into(), v.clone().into()))
+                    .collect();
+                Value::Map(key_type, value_type, Arc::new(pairs))
+            }
+            ValueRef::Tuple(vs) => {
+                let value_list = vs.iter().map(|v| v.clone().into()).collect();
+                Value::Tuple(Arc::new(value_list))
+            }
             ValueRef::Decimal(v) => Value::Decimal(v),
             ValueRef::Enum8(e_v, v) => Value::Enum8(e_v, v),
             ValueRef::Enum16(e_v, v) => Value::Enum16(e_v, v),
@@ -297,11 +370,15 @@ from_number! {
     u16: UInt16,
     u32: UInt32,
     u64: UInt64,
+    u128: UInt128,
+    U256: UInt256,
 
     i8: Int8,
     i16: Int16,
     i32: Int32,
     i64: Int64,
+    i128: Int128,
+    I256: Int256,
 
     f32: Float32,
     f64: Float64
@@ -318,10 +395,16 @@ impl<'a> From<&'a Value> for ValueRef<'a> {
             Value::Int16(v) => ValueRef::Int16(*v),
             Value::Int32(v) => ValueRef::Int32(*v),
             Value::Int64(v) => ValueRef::Int64(*v),
+            Value::Int128(v) => ValueRef::Int128(*v),
+            Value::Int256(v) => ValueRef::Int256(*v),
+            Value::UInt128(v) => ValueRef::UInt128(*v),
+            Value::UInt256(v) => ValueRef::UInt256(*v),
             Value::String(v) => ValueRef::String(v),
             Value::Float32(v) => ValueRef::Float32(*v),
             Value::Float64(v) => ValueRef::Float64(*v),
+            Value::Bool(v) => ValueRef::Bool(*v),
             Value::Date(v, tz) => ValueRef::Date(*v, *tz),
+            Value::Date32(v, tz) => ValueRef::Date32(*v, *tz),
             Value::DateTime(v, tz) => ValueRef::DateTime(*v, *tz),
             Value::DateTime64(v, params) => ValueRef::DateTime64(*v, params),
             Value::Nullable(u) => match u {
@@ -339,6 +422,14 @@ impl<'a> From<&'a Value> for ValueRef<'a> {
                 }
                 ValueRef::Array(*t, Arc::new(ref_vec))
             }
+            Value::Map(key_type, value_type, pairs) => {
+                let pairs = pairs.iter().map(|(k, v)| (k.into(), v.into())).collect();
+                ValueRef::Map(key_type, value_type, Arc::new(pairs))
+            }
+            Value::Tuple(vs) => {
+                let ref_vec = vs.iter().map(From::from).collect();
+                ValueRef::Tuple(Arc::new(ref_vec))
+            }
             Value::Decimal(v) => ValueRef::Decimal(v.clone()),
             Value::Enum8(values, v) => ValueRef::Enum8(values.to_vec(), *v),
             Value::Enum16(values, v) => ValueRef::Enum16(values.to_vec(), *v),
@@ -398,11 +489,15 @@ value_from! {
     u16: UInt16,
     u32: UInt32,
     u64: UInt64,
+    u128: UInt128,
+    U256: UInt256,
 
     i8: Int8,
     i16: Int16,
     i32: Int32,
     i64: Int64,
+    i128: Int128,
+    I256: Int256,
 
     f32: Float32,
     f64: Float64
@@ -494,7 +589,7 @@ mod test {
     #[test]
     fn test_size_of() {
         use std::mem;
-        assert_eq!(32, mem::size_of::<[ValueRef<'_>; 1]>());
+        assert_eq!(48, mem::size_of::<[ValueRef<'_>; 1]>());
     }
 
     #[test]